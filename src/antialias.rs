@@ -0,0 +1,313 @@
+// This file contains the anti-aliasing modes, which smooth the jagged edges of filled
+// triangles by taking several samples per pixel and averaging them into the window.
+//
+// Supersampling (SSAA) fills the whole frame at a multiple of the window's resolution
+// and filters it down, so every sample is shaded and detail inside triangles is smoothed
+// too. Multisampling (MSAA) tests coverage and depth at several points per pixel but
+// blends each pixel's color once, so it saves the shading of the extra samples but only
+// smooths edges. `--bench` compares the cost of each mode.
+
+use crate::blend::CHANNELS;
+use crate::color::LinearColor;
+use crate::display::{WINDOW_HEIGHT, WINDOW_WIDTH};
+use crate::raster::{self, RasterTarget, MSAA_SAMPLES};
+
+/// How the edges of filled triangles are smoothed.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AntiAliasing {
+    /// One sample at the center of each pixel.
+    Off,
+    /// Supersampling at twice the window's width and height.
+    Ssaa2,
+    /// Supersampling at four times the window's width and height.
+    Ssaa4,
+    /// Multisampling with `MSAA_SAMPLES` samples per pixel.
+    Msaa4,
+}
+
+impl AntiAliasing {
+    /// Parses an anti-aliasing mode from its name in a scene file.
+    ///
+    /// # Arguments
+    /// - `name`: One of `off`, `ssaa2`, `ssaa4` or `msaa4`.
+    ///
+    /// # Returns
+    /// The matching `AntiAliasing`, or `None` if the name is unknown.
+    pub fn from_name(name: &str) -> Option<AntiAliasing> {
+        match name {
+            "off" => Some(AntiAliasing::Off),
+            "ssaa2" => Some(AntiAliasing::Ssaa2),
+            "ssaa4" => Some(AntiAliasing::Ssaa4),
+            "msaa4" => Some(AntiAliasing::Msaa4),
+            _ => None,
+        }
+    }
+
+    /// Returns the name of the mode, as written in a scene file.
+    pub fn name(&self) -> &'static str {
+        match self {
+            AntiAliasing::Off => "off",
+            AntiAliasing::Ssaa2 => "ssaa2",
+            AntiAliasing::Ssaa4 => "ssaa4",
+            AntiAliasing::Msaa4 => "msaa4",
+        }
+    }
+
+    /// Returns the mode after this one, for cycling through them at runtime.
+    pub fn next(&self) -> AntiAliasing {
+        match self {
+            AntiAliasing::Off => AntiAliasing::Ssaa2,
+            AntiAliasing::Ssaa2 => AntiAliasing::Ssaa4,
+            AntiAliasing::Ssaa4 => AntiAliasing::Msaa4,
+            AntiAliasing::Msaa4 => AntiAliasing::Off,
+        }
+    }
+
+    /// Returns the factor the frame's width and height are multiplied by.
+    pub fn scale(&self) -> u32 {
+        match self {
+            AntiAliasing::Ssaa2 => 2,
+            AntiAliasing::Ssaa4 => 4,
+            AntiAliasing::Off | AntiAliasing::Msaa4 => 1,
+        }
+    }
+
+    /// Returns the number of samples stored for each pixel of the frame.
+    pub fn samples(&self) -> u32 {
+        match self {
+            AntiAliasing::Msaa4 => MSAA_SAMPLES,
+            _ => 1,
+        }
+    }
+}
+
+/// How a supersampled frame is filtered down to the window's resolution.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DownsampleFilter {
+    /// Averages the samples inside each pixel.
+    Box,
+    /// Weighs samples by their distance from the pixel's center, reaching into the
+    /// neighboring pixels. It is softer than `Box` and flickers less on moving edges.
+    Tent,
+}
+
+impl DownsampleFilter {
+    /// Parses a filter from its name in a scene file.
+    ///
+    /// # Arguments
+    /// - `name`: Either `box` or `tent`.
+    ///
+    /// # Returns
+    /// The matching `DownsampleFilter`, or `None` if the name is unknown.
+    pub fn from_name(name: &str) -> Option<DownsampleFilter> {
+        match name {
+            "box" => Some(DownsampleFilter::Box),
+            "tent" => Some(DownsampleFilter::Tent),
+            _ => None,
+        }
+    }
+
+    /// Returns the name of the filter, as written in a scene file.
+    pub fn name(&self) -> &'static str {
+        match self {
+            DownsampleFilter::Box => "box",
+            DownsampleFilter::Tent => "tent",
+        }
+    }
+
+    /// Returns the other filter, for switching between them at runtime.
+    pub fn toggled(&self) -> DownsampleFilter {
+        match self {
+            DownsampleFilter::Box => DownsampleFilter::Tent,
+            DownsampleFilter::Tent => DownsampleFilter::Box,
+        }
+    }
+}
+
+/// The buffers an anti-aliased frame is filled into before it is resolved into the
+/// window's HDR color buffer.
+pub struct SampleBuffers {
+    /// The color of every sample, `CHANNELS` floats in linear light per sample.
+    pub color_buffer: Vec<f32>,
+    /// The inverse view depth of every sample.
+    pub depth_buffer: Vec<f32>,
+    /// The mode the buffers are laid out for.
+    mode: AntiAliasing,
+    /// The rows of the frame part way through the tent filter, kept to save allocating
+    /// them every frame.
+    filtered: Vec<[f32; CHANNELS]>,
+}
+
+impl SampleBuffers {
+    /// Creates empty buffers, sized on the first call to `prepare`.
+    pub fn new() -> SampleBuffers {
+        SampleBuffers {
+            color_buffer: Vec::new(),
+            depth_buffer: Vec::new(),
+            mode: AntiAliasing::Off,
+            filtered: Vec::new(),
+        }
+    }
+
+    /// Sizes the buffers for a mode and clears them for a new frame.
+    ///
+    /// # Arguments
+    /// - `mode`: The anti-aliasing mode the frame is drawn with.
+    /// - `background`: The color every sample is cleared to, in linear light.
+    pub fn prepare(&mut self, mode: AntiAliasing, background: LinearColor) {
+        let scale = mode.scale();
+        let samples = (WINDOW_WIDTH * scale * WINDOW_HEIGHT * scale * mode.samples()) as usize;
+        self.mode = mode;
+        self.color_buffer.resize(samples * CHANNELS, 0.0);
+        self.depth_buffer.resize(samples, 0.0);
+        raster::clear_color_buffer(&mut self.color_buffer, background);
+        raster::clear_depth_buffer(&mut self.depth_buffer);
+    }
+
+    /// Creates a target for filling the whole frame, in the resolution of the mode
+    /// passed to `prepare`. Triangles must be scaled by the mode's `scale` first.
+    pub fn target(&mut self) -> RasterTarget<'_> {
+        let scale = self.mode.scale();
+        RasterTarget::new(
+            &mut self.color_buffer,
+            &mut self.depth_buffer,
+            WINDOW_WIDTH * scale,
+            WINDOW_HEIGHT * scale,
+            self.mode.samples(),
+        )
+    }
+
+    /// Averages the samples of the frame into the window's pixels. The samples are in
+    /// linear light, so an edge half covering a pixel gives it half the light of each
+    /// side.
+    ///
+    /// # Arguments
+    /// - `filter`: How a supersampled frame is filtered; multisampled pixels always
+    ///   average their own samples.
+    /// - `color_buffer`: The window's HDR color buffer, which is overwritten.
+    pub fn resolve(&mut self, filter: DownsampleFilter, color_buffer: &mut [f32]) {
+        let scale = self.mode.scale() as usize;
+        match (self.mode, filter) {
+            (AntiAliasing::Off, _) => color_buffer.copy_from_slice(&self.color_buffer),
+            (AntiAliasing::Msaa4, _) => {
+                average_samples(&self.color_buffer, MSAA_SAMPLES as usize, color_buffer)
+            }
+            (_, DownsampleFilter::Box) => downsample_box(&self.color_buffer, scale, color_buffer),
+            (_, DownsampleFilter::Tent) => {
+                downsample_tent(&self.color_buffer, scale, &mut self.filtered, color_buffer)
+            }
+        }
+    }
+}
+
+/// Averages groups of samples stored one after another into single pixels.
+fn average_samples(source: &[f32], samples: usize, target: &mut [f32]) {
+    for (pixel, group) in target
+        .chunks_exact_mut(CHANNELS)
+        .zip(source.chunks_exact(samples * CHANNELS))
+    {
+        for (channel, value) in pixel.iter_mut().enumerate() {
+            let sum: f32 = (0..samples).map(|s| group[s * CHANNELS + channel]).sum();
+            *value = sum / samples as f32;
+        }
+    }
+}
+
+/// Averages the `scale` by `scale` samples inside each pixel of a supersampled frame.
+fn downsample_box(source: &[f32], scale: usize, target: &mut [f32]) {
+    let (width, height) = (WINDOW_WIDTH as usize, WINDOW_HEIGHT as usize);
+    let source_width = width * scale;
+    let count = (scale * scale) as f32;
+    for y in 0..height {
+        for x in 0..width {
+            let mut sum = [0.0f32; CHANNELS];
+            for sy in y * scale..(y + 1) * scale {
+                let row = (sy * source_width + x * scale) * CHANNELS;
+                for sample in source[row..row + scale * CHANNELS].chunks_exact(CHANNELS) {
+                    for (total, &value) in sum.iter_mut().zip(sample) {
+                        *total += value;
+                    }
+                }
+            }
+            let index = (y * width + x) * CHANNELS;
+            for (value, total) in target[index..index + CHANNELS].iter_mut().zip(sum) {
+                *value = total / count;
+            }
+        }
+    }
+}
+
+/// Filters a supersampled frame down with a tent filter two pixels wide.
+///
+/// The filter is separable, so the rows are filtered down to the window's width first,
+/// into `filtered`, and the columns of the result after.
+fn downsample_tent(
+    source: &[f32],
+    scale: usize,
+    filtered: &mut Vec<[f32; CHANNELS]>,
+    target: &mut [f32],
+) {
+    let (width, height) = (WINDOW_WIDTH as usize, WINDOW_HEIGHT as usize);
+    let source_width = width * scale;
+    let columns = tent_taps(width, scale);
+    let rows = tent_taps(height, scale);
+
+    filtered.resize(width * height * scale, [0.0; CHANNELS]);
+    for (sy, source_row) in source.chunks_exact(source_width * CHANNELS).enumerate() {
+        for (x, (first, weights)) in columns.iter().enumerate() {
+            let samples = &source_row[first * CHANNELS..(first + weights.len()) * CHANNELS];
+            let mut sum = [0.0f32; CHANNELS];
+            for (sample, weight) in samples.chunks_exact(CHANNELS).zip(weights) {
+                for (total, &value) in sum.iter_mut().zip(sample) {
+                    *total += value * weight;
+                }
+            }
+            filtered[sy * width + x] = sum;
+        }
+    }
+    for (y, (first, weights)) in rows.iter().enumerate() {
+        // Accumulate whole rows at a time, which reads the rows in order.
+        let mut sums = vec![[0.0f32; CHANNELS]; width];
+        for (tap, weight) in weights.iter().enumerate() {
+            let row = &filtered[(first + tap) * width..(first + tap + 1) * width];
+            for (sum, value) in sums.iter_mut().zip(row) {
+                for (total, channel) in sum.iter_mut().zip(value) {
+                    *total += channel * weight;
+                }
+            }
+        }
+        let row_length = width * CHANNELS;
+        let target_row = &mut target[y * row_length..(y + 1) * row_length];
+        for (pixel, sum) in target_row.chunks_exact_mut(CHANNELS).zip(sums) {
+            pixel.copy_from_slice(&sum);
+        }
+    }
+}
+
+/// Lists the samples a tent filter reads along one axis for each pixel.
+///
+/// The filter covers `2 * scale` samples, starting half a pixel before the pixel, and
+/// weighs each by its distance from the pixel's center. Samples past the frame's edges
+/// are left out and the remaining weights renormalized.
+///
+/// # Returns
+/// For each pixel, the index of its first sample and the weight of each sample from it.
+fn tent_taps(pixels: usize, scale: usize) -> Vec<(usize, Vec<f32>)> {
+    (0..pixels)
+        .map(|pixel| {
+            let start = (pixel * scale) as isize - (scale / 2) as isize;
+            let taps: Vec<(usize, f32)> = (0..2 * scale)
+                .filter_map(|tap| {
+                    let index = usize::try_from(start + tap as isize).ok()?;
+                    let weight = 1.0 - (tap as f32 + 0.5 - scale as f32).abs() / scale as f32;
+                    (index < pixels * scale).then_some((index, weight))
+                })
+                .collect();
+            let total: f32 = taps.iter().map(|(_, weight)| weight).sum();
+            (
+                taps[0].0,
+                taps.iter().map(|(_, weight)| weight / total).collect(),
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,300 @@
+// This file contains the benchmarks run by `--bench`, which time stages of the pipeline
+// without opening a window.
+//
+// Each benchmark prints a table to stdout. Results are only comparable between runs on
+// the same machine.
+
+use std::time::Instant;
+
+use crate::antialias::{AntiAliasing, DownsampleFilter, SampleBuffers};
+use crate::blend::{BlendMode, CHANNELS};
+use crate::camera::OrbitCamera;
+use crate::color::LinearColor;
+use crate::display::{WINDOW_HEIGHT, WINDOW_WIDTH};
+use crate::mesh::Mesh;
+use crate::pipeline;
+use crate::raster;
+use crate::scene::Transform;
+use crate::simd::Kernel;
+use crate::triangle::Triangle;
+use crate::vector::Vec3;
+use crate::DEFAULT_FOV_FACTOR;
+
+/// Number of frames each measurement is averaged over.
+const FRAMES: u32 = 50;
+
+/// Number of times the vertex stage is repeated per measurement, as one pass over a
+/// small mesh is too quick to time.
+const TRANSFORM_PASSES: u32 = 500;
+
+/// Runs every benchmark.
+pub fn run() {
+    let meshes = [
+        ("f22.obj", Mesh::load("./assets/f22.obj")),
+        ("icosphere(6)", Ok(Mesh::new_icosphere(1.0, 6))),
+    ];
+    for (name, mesh) in meshes {
+        match mesh {
+            Ok(mesh) => {
+                vertex_stage(name, &mesh);
+                let triangles = project(mesh);
+                raster_kernels(name, &triangles);
+                raster_scaling(name, &triangles);
+                antialiasing(name, &triangles);
+            }
+            Err(e) => eprintln!("Skipping {}: {}", name, e),
+        }
+    }
+}
+
+/// Lists thread counts to measure: powers of two up to the number of CPU cores, and the
+/// number of cores itself.
+fn thread_counts() -> Vec<usize> {
+    let cores = std::thread::available_parallelism().map_or(1, |count| count.get());
+    let mut counts: Vec<usize> = std::iter::successors(Some(1), |n| Some(n * 2))
+        .take_while(|&n| n <= cores)
+        .collect();
+    if counts.last() != Some(&cores) {
+        counts.push(cores);
+    }
+    counts
+}
+
+/// Times moving a mesh into view space by transforming the corners of every face, as
+/// `Renderer::update` used to, against transforming each vertex once into a cache.
+fn vertex_stage(name: &str, mesh: &Mesh) {
+    let mut camera = OrbitCamera::new(Vec3::new(0.0, 0.0, 0.0), 4.0);
+    camera.yaw = 0.6;
+    camera.pitch = 0.4;
+    let mut transform = Transform::identity();
+    transform.rotation = Vec3::new(0.3, 0.5, 0.7);
+    let world = transform.matrix();
+    let model_view = camera.view_matrix() * world;
+
+    println!(
+        "Transforming {} ({} vertices, {} face corners)",
+        name,
+        mesh.vertices.len(),
+        mesh.faces.len() * 3
+    );
+    println!("path          threads  ms/frame  speedup");
+    let time = |run: &mut dyn FnMut()| {
+        let start = Instant::now();
+        for _ in 0..TRANSFORM_PASSES {
+            run();
+        }
+        start.elapsed().as_secs_f32() * 1000.0 / TRANSFORM_PASSES as f32
+    };
+
+    let mut per_face: Vec<[Vec3; 3]> = Vec::new();
+    let reference = time(&mut || {
+        per_face = mesh
+            .faces
+            .iter()
+            .map(|face| {
+                [face.a, face.b, face.c].map(|index| {
+                    camera.world_to_view(world.transform_point(mesh.vertices[index - 1]))
+                })
+            })
+            .collect();
+    });
+    println!("per face      {:7}  {:8.3}  {:6.2}x", 1, reference, 1.0);
+
+    for threads in thread_counts() {
+        let mut cached = Vec::new();
+        let millis = time(&mut || {
+            cached = pipeline::transform_vertices(&mesh.vertices, &model_view, threads);
+        });
+        println!(
+            "cached        {:7}  {:8.3}  {:6.2}x",
+            threads,
+            millis,
+            reference / millis
+        );
+
+        // Both paths must place every corner at the same spot, up to rounding.
+        let matches = mesh.faces.iter().zip(&per_face).all(|(face, corners)| {
+            [face.a, face.b, face.c]
+                .iter()
+                .zip(corners)
+                .all(|(index, corner)| (cached[index - 1] - *corner).len() < 1e-4)
+        });
+        if !matches {
+            println!("              cached vertices differ from the per-face path");
+        }
+    }
+    println!();
+}
+
+/// Places a mesh in front of the camera, filling most of the window, and projects its
+/// front-facing triangles.
+///
+/// Vertex colors follow the position, so the rasterizer blends colors as it would for a
+/// shaded mesh.
+fn project(mut mesh: Mesh) -> Vec<Triangle> {
+    mesh.center();
+    mesh.normalize(3.0);
+    let view = |v: Vec3| v.rotate_x(0.4).rotate_y(0.6) + Vec3::new(0.0, 0.0, 4.0);
+    let color = |v: Vec3| {
+        let channel = |c: f32| (c / 3.0 + 0.5).clamp(0.0, 1.0);
+        LinearColor::new(channel(v.x), channel(v.y), channel(v.z), 1.0)
+    };
+
+    let mut triangles = Vec::new();
+    for face in &mesh.faces {
+        let vertices = [face.a, face.b, face.c].map(|index| mesh.vertices[index - 1]);
+        let [a, b, c] = vertices.map(view);
+        if (b - a).cross(c - a).dot(-a) < 0.0 {
+            continue; // Faces away from the camera.
+        }
+        triangles.push(Triangle::new(
+            [a, b, c].map(|v| pipeline::project(v, DEFAULT_FOV_FACTOR)),
+            [a, b, c].map(|v| 1.0 / v.z),
+            vertices.map(color),
+            BlendMode::Replace,
+        ));
+    }
+    triangles
+}
+
+/// Fills triangles into a cleared frame `FRAMES` times.
+///
+/// # Returns
+/// The color buffer of the last frame and the average time per frame in milliseconds.
+fn time_fill(triangles: &[Triangle], threads: usize, kernel: Kernel) -> (Vec<f32>, f32) {
+    let pixels = (WINDOW_WIDTH * WINDOW_HEIGHT) as usize;
+    let mut color_buffer = vec![0.0f32; pixels * CHANNELS];
+    let mut depth_buffer = vec![0.0f32; pixels];
+    let start = Instant::now();
+    for _ in 0..FRAMES {
+        color_buffer.fill(0.0);
+        raster::clear_depth_buffer(&mut depth_buffer);
+        raster::fill_triangles(
+            raster::RasterTarget::frame(&mut color_buffer, &mut depth_buffer),
+            triangles,
+            &[],
+            threads,
+            kernel,
+        );
+    }
+    let millis = start.elapsed().as_secs_f32() * 1000.0 / FRAMES as f32;
+    (color_buffer, millis)
+}
+
+/// Prints a row of a table comparing runs against the first one.
+fn print_comparison(label: &str, millis: f32, reference: &(Vec<f32>, f32), pixels: &[f32]) {
+    println!(
+        "{:>7}  {:8.3}  {:6.2}x  {}",
+        label,
+        millis,
+        reference.1 / millis,
+        if reference.0 == pixels { "yes" } else { "NO" }
+    );
+}
+
+/// Times each rasterizer kernel the CPU supports on one thread, and checks that they
+/// produce the same pixels and find the same covered pixels as the scalar kernel.
+fn raster_kernels(name: &str, triangles: &[Triangle]) {
+    println!("Rasterizing {} ({} triangles)", name, triangles.len());
+    println!(" kernel  ms/frame  speedup  identical  coverage");
+    let coverage = |kernel| raster::coverage_masks(triangles, WINDOW_WIDTH, WINDOW_HEIGHT, kernel);
+    let reference = time_fill(triangles, 1, Kernel::Scalar);
+    let reference_coverage = coverage(Kernel::Scalar);
+
+    let mut kernels = vec![Kernel::Scalar];
+    #[cfg(target_arch = "x86_64")]
+    {
+        kernels.push(Kernel::Sse2);
+        if Kernel::best() == Kernel::Avx2 {
+            kernels.push(Kernel::Avx2);
+        }
+    }
+    for kernel in kernels {
+        let (pixels, millis) = match kernel {
+            Kernel::Scalar => reference.clone(),
+            _ => time_fill(triangles, 1, kernel),
+        };
+        println!(
+            "{:>7}  {:8.3}  {:6.2}x  {:<9}  {}",
+            kernel.name(),
+            millis,
+            reference.1 / millis,
+            if reference.0 == pixels { "yes" } else { "NO" },
+            if coverage(kernel) == reference_coverage {
+                "same"
+            } else {
+                "DIFFERS"
+            }
+        );
+    }
+    println!();
+}
+
+/// Times filling triangles on increasing numbers of threads, and checks that every
+/// thread count produces the same pixels as one thread.
+fn raster_scaling(name: &str, triangles: &[Triangle]) {
+    let kernel = Kernel::best();
+    println!(
+        "Rasterizing {} ({} triangles, {} kernel)",
+        name,
+        triangles.len(),
+        kernel.name()
+    );
+    println!("threads  ms/frame  speedup  identical");
+    let mut reference = None;
+    for threads in thread_counts() {
+        let (pixels, millis) = time_fill(triangles, threads, kernel);
+        let reference = reference.get_or_insert_with(|| (pixels.clone(), millis));
+        print_comparison(&threads.to_string(), millis, reference, &pixels);
+    }
+    println!();
+}
+
+/// Times filling triangles and resolving the frame in each anti-aliasing mode, against
+/// filling them without anti-aliasing.
+fn antialiasing(name: &str, triangles: &[Triangle]) {
+    let threads = thread_counts().pop().unwrap_or(1);
+    println!(
+        "Anti-aliasing {} ({} triangles, {} threads)",
+        name,
+        triangles.len(),
+        threads
+    );
+    println!("mode        ms/frame  cost");
+    let kernel = Kernel::best();
+    let reference = time_fill(triangles, threads, kernel).1;
+    println!("{:10}  {:8.3}  {:4.1}x", "off", reference, 1.0);
+
+    let mut buffers = SampleBuffers::new();
+    let mut color_buffer = vec![0.0f32; (WINDOW_WIDTH * WINDOW_HEIGHT) as usize * CHANNELS];
+    let modes = [
+        (AntiAliasing::Ssaa2, DownsampleFilter::Box),
+        (AntiAliasing::Ssaa2, DownsampleFilter::Tent),
+        (AntiAliasing::Ssaa4, DownsampleFilter::Box),
+        (AntiAliasing::Ssaa4, DownsampleFilter::Tent),
+        (AntiAliasing::Msaa4, DownsampleFilter::Box),
+    ];
+    for (mode, filter) in modes {
+        let scale = mode.scale() as f32;
+        let scaled: Vec<Triangle> = triangles
+            .iter()
+            .map(|triangle| Triangle {
+                points: triangle.points.map(|point| point * scale),
+                ..*triangle
+            })
+            .collect();
+        let start = Instant::now();
+        for _ in 0..FRAMES {
+            buffers.prepare(mode, LinearColor::new(0.0, 0.0, 0.0, 1.0));
+            raster::fill_triangles(buffers.target(), &scaled, &[], threads, kernel);
+            buffers.resolve(filter, &mut color_buffer);
+        }
+        let millis = start.elapsed().as_secs_f32() * 1000.0 / FRAMES as f32;
+        let label = match mode.scale() {
+            1 => mode.name().to_string(),
+            _ => format!("{} {}", mode.name(), filter.name()),
+        };
+        println!("{:10}  {:8.3}  {:4.1}x", label, millis, millis / reference);
+    }
+    println!();
+}
@@ -0,0 +1,219 @@
+// This file contains the blend modes, which combine a color drawn onto a color buffer
+// with the color already there.
+//
+// The scene is drawn into an HDR color buffer, which holds the red, green and blue of
+// every sample as floats in linear light, without alpha. The window's color buffer, on
+// which the wireframe and the overlay are drawn, holds four bytes per pixel, red, green,
+// blue and alpha. Every mode but `Replace` reads the alpha of the color drawn; the alpha
+// written back to bytes accumulates how much of the pixel has been covered, as with the
+// "over" operator.
+
+use crate::color::{Color, LinearColor};
+
+/// Number of bytes each pixel takes in a color buffer.
+pub const BYTES_PER_PIXEL: usize = 4;
+
+/// Number of floats each sample takes in an HDR color buffer.
+pub const CHANNELS: usize = 3;
+
+/// How a color is combined with the color buffer.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BlendMode {
+    /// The color overwrites the pixel, alpha included.
+    Replace,
+    /// The color is laid over the pixel in proportion to its alpha.
+    AlphaOver,
+    /// Like `AlphaOver`, for colors whose channels were already multiplied by their
+    /// alpha. An alpha of 0 then adds the color to the pixel.
+    Premultiplied,
+    /// The color, weighed by its alpha, is added to the pixel, which brightens it.
+    Additive,
+    /// The pixel is multiplied by the color, weighed by its alpha, which darkens it.
+    Multiply,
+}
+
+impl BlendMode {
+    /// Parses a blend mode from its name in a scene file.
+    ///
+    /// # Arguments
+    /// - `name`: One of `replace`, `alpha`, `premultiplied`, `additive` or `multiply`.
+    ///
+    /// # Returns
+    /// The matching `BlendMode`, or `None` if the name is unknown.
+    pub fn from_name(name: &str) -> Option<BlendMode> {
+        match name {
+            "replace" => Some(BlendMode::Replace),
+            "alpha" => Some(BlendMode::AlphaOver),
+            "premultiplied" => Some(BlendMode::Premultiplied),
+            "additive" => Some(BlendMode::Additive),
+            "multiply" => Some(BlendMode::Multiply),
+            _ => None,
+        }
+    }
+
+    /// Checks whether drawing a color leaves nothing of the pixel beneath it, so it can
+    /// be copied instead of blended.
+    ///
+    /// # Arguments
+    /// - `alpha`: The alpha of the color, from 0.0 to 1.0.
+    pub fn replaces(&self, alpha: f32) -> bool {
+        match self {
+            BlendMode::Replace => true,
+            BlendMode::AlphaOver | BlendMode::Premultiplied => alpha >= 1.0,
+            BlendMode::Additive | BlendMode::Multiply => false,
+        }
+    }
+
+    /// Combines a color with a pixel of the window's color buffer. The bytes are
+    /// blended as they are, without decoding them into linear light.
+    ///
+    /// # Arguments
+    /// - `pixel`: The pixel's four bytes, which are overwritten.
+    /// - `color`: The color drawn (RGBA).
+    /// - `coverage`: How much of the pixel the color covers, from 0.0 to 1.0. It scales
+    ///   the color's alpha, or for `Replace` blends the color over the pixel by it.
+    pub fn apply(&self, pixel: &mut [u8], color: Color, coverage: f32) {
+        if coverage >= 1.0 && self.replaces(color.a as f32 / 255.0) {
+            pixel.copy_from_slice(&[color.r, color.g, color.b, color.a]);
+            return;
+        }
+        let [r, g, b, alpha] = color.to_encoded();
+        let coverage = coverage.clamp(0.0, 1.0);
+        let target = [pixel[0], pixel[1], pixel[2]].map(|c| c as f32 / 255.0);
+        let rgb = self.blend(target, [r, g, b], alpha, coverage);
+
+        let target_alpha = pixel[3] as f32 / 255.0;
+        let out_alpha = match self {
+            BlendMode::Replace => target_alpha + (alpha - target_alpha) * coverage,
+            _ => alpha * coverage + target_alpha * (1.0 - alpha * coverage),
+        };
+        for (value, channel) in pixel.iter_mut().zip([rgb[0], rgb[1], rgb[2], out_alpha]) {
+            // Adding a half rounds to nearest, as every channel is positive.
+            *value = (channel.min(1.0) * 255.0 + 0.5) as u8;
+        }
+    }
+
+    /// Combines a color with a sample of an HDR color buffer. Nothing is clamped, so
+    /// additive blending can brighten a sample past 1.0.
+    ///
+    /// # Arguments
+    /// - `sample`: The sample's `CHANNELS` floats, which are overwritten.
+    /// - `color`: The color drawn, in linear light.
+    /// - `coverage`: How much of the sample the color covers, from 0.0 to 1.0, as for
+    ///   `apply`.
+    pub fn apply_linear(&self, sample: &mut [f32], color: LinearColor, coverage: f32) {
+        if coverage >= 1.0 && self.replaces(color.a) {
+            sample.copy_from_slice(&[color.r, color.g, color.b]);
+            return;
+        }
+        let target = [sample[0], sample[1], sample[2]];
+        let source = [color.r, color.g, color.b];
+        sample.copy_from_slice(&self.blend(target, source, color.a, coverage.clamp(0.0, 1.0)));
+    }
+
+    /// Blends the red, green and blue of a color over those of a pixel.
+    ///
+    /// # Arguments
+    /// - `target`: The pixel's channels.
+    /// - `source`: The channels of the color drawn, in the same encoding as `target`.
+    /// - `alpha`: The alpha of the color drawn, from 0.0 to 1.0.
+    /// - `coverage`: How much of the pixel the color covers, from 0.0 to 1.0.
+    ///
+    /// # Returns
+    /// The blended channels, unclamped.
+    fn blend(&self, target: [f32; 3], source: [f32; 3], alpha: f32, coverage: f32) -> [f32; 3] {
+        let alpha = alpha * coverage;
+        match self {
+            BlendMode::Replace => {
+                std::array::from_fn(|i| target[i] + (source[i] - target[i]) * coverage)
+            }
+            BlendMode::AlphaOver => {
+                std::array::from_fn(|i| source[i] * alpha + target[i] * (1.0 - alpha))
+            }
+            BlendMode::Premultiplied => {
+                std::array::from_fn(|i| source[i] * coverage + target[i] * (1.0 - alpha))
+            }
+            BlendMode::Additive => std::array::from_fn(|i| target[i] + source[i] * alpha),
+            BlendMode::Multiply => {
+                std::array::from_fn(|i| target[i] * (1.0 - alpha + source[i] * alpha))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODES: [BlendMode; 5] = [
+        BlendMode::Replace,
+        BlendMode::AlphaOver,
+        BlendMode::Premultiplied,
+        BlendMode::Additive,
+        BlendMode::Multiply,
+    ];
+
+    #[test]
+    fn apply_blends_bytes() {
+        let color = Color::rgba(230, 30, 120, 77);
+        let expected = [
+            // Full coverage, then 60%.
+            ([230, 30, 120, 77], [178, 99, 95, 97]),
+            ([140, 151, 76, 166], [124, 172, 68, 151]),
+            ([255, 172, 160, 166], [221, 184, 119, 151]),
+            ([170, 212, 93, 166], [143, 208, 79, 151]),
+            ([98, 149, 48, 166], [99, 171, 52, 151]),
+        ];
+        for (mode, (full, partial)) in MODES.into_iter().zip(expected) {
+            for (coverage, expected) in [(1.0, full), (0.6, partial)] {
+                let mut pixel = [101, 203, 57, 128];
+                mode.apply(&mut pixel, color, coverage);
+                assert_eq!(pixel, expected, "{:?} at {}", mode, coverage);
+            }
+        }
+    }
+
+    #[test]
+    fn apply_copies_opaque_colors() {
+        for mode in [BlendMode::AlphaOver, BlendMode::Premultiplied] {
+            let mut pixel = [101, 203, 57, 128];
+            mode.apply(&mut pixel, Color::rgb(1, 2, 3), 1.0);
+            assert_eq!(pixel, [1, 2, 3, 255], "{:?}", mode);
+        }
+        // Nothing is drawn without coverage, whatever the mode.
+        for mode in MODES {
+            let mut pixel = [101, 203, 57, 128];
+            mode.apply(&mut pixel, Color::rgb(1, 2, 3), 0.0);
+            assert_eq!(pixel, [101, 203, 57, 128], "{:?}", mode);
+        }
+    }
+
+    #[test]
+    fn apply_linear_blends_unclamped_floats() {
+        let color = LinearColor::new(0.8, 0.1, 4.0, 0.25);
+        let expected = [
+            // Full coverage, then 50%.
+            ([0.8, 0.1, 4.0], [0.65, 1.05, 2.125]),
+            ([0.575, 1.525, 1.1875], [0.5375, 1.7625, 0.71875]),
+            ([1.175, 1.6, 4.1875], [0.8375, 1.8, 2.21875]),
+            ([0.7, 2.025, 1.25], [0.6, 2.0125, 0.75]),
+            ([0.475, 1.55, 0.4375], [0.4875, 1.775, 0.34375]),
+        ];
+        for (mode, (full, partial)) in MODES.into_iter().zip(expected) {
+            for (coverage, expected) in [(1.0, full), (0.5, partial)] {
+                let mut sample = [0.5, 2.0, 0.25];
+                mode.apply_linear(&mut sample, color, coverage);
+                for (value, expected) in sample.iter().zip(expected) {
+                    assert!(
+                        (value - expected).abs() < 1e-6,
+                        "{:?} at {}: {:?} is not {:?}",
+                        mode,
+                        coverage,
+                        sample,
+                        expected
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,258 @@
+// This file contains bounding volumes, used to frame, place and cull meshes without
+// looking at every vertex.
+//
+// `Aabb` is an axis-aligned box, tight but changed in shape by rotation. `BoundingSphere`
+// ignores rotation, which makes it cheap to move along with a mesh.
+
+use crate::matrix::Mat4;
+use crate::vector::Vec3;
+
+/// An axis-aligned bounding box.
+#[derive(Debug, Copy, Clone)]
+pub struct Aabb {
+    /// The corner with the smallest coordinates.
+    pub min: Vec3,
+    /// The corner with the largest coordinates.
+    pub max: Vec3,
+}
+
+#[allow(dead_code)]
+impl Aabb {
+    /// Calculates the smallest box containing some points.
+    ///
+    /// # Arguments
+    /// - `points`: The points to enclose.
+    ///
+    /// # Returns
+    /// The box, or `None` if there are no points.
+    pub fn from_points(points: &[Vec3]) -> Option<Aabb> {
+        let first = *points.first()?;
+        Some(points.iter().fold(
+            Aabb {
+                min: first,
+                max: first,
+            },
+            |aabb, &point| {
+                aabb.union(Aabb {
+                    min: point,
+                    max: point,
+                })
+            },
+        ))
+    }
+
+    /// Calculates the smallest box containing this box and another.
+    pub fn union(&self, other: Aabb) -> Aabb {
+        Aabb {
+            min: Vec3::new(
+                self.min.x.min(other.min.x),
+                self.min.y.min(other.min.y),
+                self.min.z.min(other.min.z),
+            ),
+            max: Vec3::new(
+                self.max.x.max(other.max.x),
+                self.max.y.max(other.max.y),
+                self.max.z.max(other.max.z),
+            ),
+        }
+    }
+
+    /// Returns the point in the middle of the box.
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) / 2.0
+    }
+
+    /// Returns the size of the box along each axis.
+    pub fn size(&self) -> Vec3 {
+        self.max - self.min
+    }
+
+    /// Returns the eight corners of the box.
+    pub fn corners(&self) -> [Vec3; 8] {
+        std::array::from_fn(|i| {
+            Vec3::new(
+                if i & 1 == 0 { self.min.x } else { self.max.x },
+                if i & 2 == 0 { self.min.y } else { self.max.y },
+                if i & 4 == 0 { self.min.z } else { self.max.z },
+            )
+        })
+    }
+
+    /// Calculates the axis-aligned box containing this box after a transformation.
+    ///
+    /// Each axis of the result collects the smallest and largest contribution of every
+    /// matrix element, which encloses all eight transformed corners without
+    /// transforming them one by one.
+    ///
+    /// # Arguments
+    /// - `matrix`: The affine transformation to apply, such as a world matrix.
+    ///
+    /// # Returns
+    /// The transformed box.
+    pub fn transformed(&self, matrix: &Mat4) -> Aabb {
+        let m = &matrix.m;
+        let (min, max) = (
+            [self.min.x, self.min.y, self.min.z],
+            [self.max.x, self.max.y, self.max.z],
+        );
+        let mut result_min = [m[0][3], m[1][3], m[2][3]];
+        let mut result_max = result_min;
+        for row in 0..3 {
+            for column in 0..3 {
+                let a = m[row][column] * min[column];
+                let b = m[row][column] * max[column];
+                result_min[row] += a.min(b);
+                result_max[row] += a.max(b);
+            }
+        }
+        Aabb {
+            min: Vec3::new(result_min[0], result_min[1], result_min[2]),
+            max: Vec3::new(result_max[0], result_max[1], result_max[2]),
+        }
+    }
+
+    /// Calculates the sphere passing through the corners of the box.
+    pub fn bounding_sphere(&self) -> BoundingSphere {
+        BoundingSphere {
+            center: self.center(),
+            radius: self.size().len() / 2.0,
+        }
+    }
+}
+
+/// A sphere enclosing a set of points.
+#[derive(Debug, Copy, Clone)]
+pub struct BoundingSphere {
+    /// The center of the sphere.
+    pub center: Vec3,
+    /// The radius of the sphere.
+    pub radius: f32,
+}
+
+#[allow(dead_code)]
+impl BoundingSphere {
+    /// Calculates a sphere containing some points, using Ritter's algorithm.
+    ///
+    /// The sphere starts across two points far apart and grows to take in any point left
+    /// outside. It is usually within a few percent of the smallest possible sphere.
+    ///
+    /// # Arguments
+    /// - `points`: The points to enclose.
+    ///
+    /// # Returns
+    /// The sphere, or `None` if there are no points.
+    pub fn from_points(points: &[Vec3]) -> Option<BoundingSphere> {
+        let farthest_from = |origin: Vec3| {
+            points
+                .iter()
+                .copied()
+                .max_by(|a, b| (*a - origin).len().total_cmp(&(*b - origin).len()))
+        };
+        let a = farthest_from(*points.first()?)?;
+        let b = farthest_from(a)?;
+
+        let mut sphere = BoundingSphere {
+            center: (a + b) / 2.0,
+            radius: (b - a).len() / 2.0,
+        };
+        for &point in points {
+            let distance = (point - sphere.center).len();
+            if distance > sphere.radius {
+                // Grow just enough to reach the point, keeping the far side in place.
+                let radius = (sphere.radius + distance) / 2.0;
+                sphere.center =
+                    sphere.center + (point - sphere.center) * ((radius - sphere.radius) / distance);
+                sphere.radius = radius;
+            }
+        }
+        Some(sphere)
+    }
+
+    /// Calculates a sphere containing this sphere after a transformation.
+    ///
+    /// The radius grows by an upper bound of how far the transformation can stretch any
+    /// direction, so the sphere stays enclosing under non-uniform scaling and shear. The
+    /// bound is exact when the matrix's axes stay perpendicular, as they do for a
+    /// rotation and scale of a single node.
+    ///
+    /// # Arguments
+    /// - `matrix`: The affine transformation to apply, such as a world matrix.
+    ///
+    /// # Returns
+    /// The transformed sphere.
+    pub fn transformed(&self, matrix: &Mat4) -> BoundingSphere {
+        let columns = [
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            Vec3::new(0.0, 0.0, 1.0),
+        ]
+        .map(|axis| matrix.transform_direction(axis));
+
+        // The largest stretch squared is the largest eigenvalue of the columns' Gram
+        // matrix. Each row sum of its absolute values bounds it (Gershgorin), and so does
+        // its trace, the squared Frobenius norm; under shear either can be the tighter.
+        let gram = |i: usize, j: usize| columns[i].dot(columns[j]).abs();
+        let row_bound = (0..3)
+            .map(|i| gram(i, 0) + gram(i, 1) + gram(i, 2))
+            .fold(0.0, f32::max);
+        let frobenius = gram(0, 0) + gram(1, 1) + gram(2, 2);
+        BoundingSphere {
+            center: matrix.transform_point(self.center),
+            radius: self.radius * row_bound.min(frobenius).sqrt(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks that a sphere still encloses the unit sphere's surface after `matrix`.
+    fn assert_encloses_unit_sphere(matrix: &Mat4) {
+        let unit = BoundingSphere {
+            center: Vec3::new(0.0, 0.0, 0.0),
+            radius: 1.0,
+        };
+        let sphere = unit.transformed(matrix);
+        for i in 0..64 {
+            for j in 0..32 {
+                let (yaw, pitch) = (i as f32 * 0.1, j as f32 * 0.1 - 1.6);
+                let direction = Vec3::new(
+                    pitch.cos() * yaw.cos(),
+                    pitch.sin(),
+                    pitch.cos() * yaw.sin(),
+                );
+                let point = matrix.transform_point(direction);
+                assert!((point - sphere.center).len() <= sphere.radius * 1.0001);
+            }
+        }
+    }
+
+    #[test]
+    fn transformed_sphere_encloses_a_sheared_sphere() {
+        // [[1, 1], [0, 1]] stretches some directions by about 1.618, more than the
+        // length of either column.
+        let mut shear = Mat4::identity();
+        shear.m[0][1] = 1.0;
+        assert_encloses_unit_sphere(&shear);
+
+        // A non-uniform parent scale over a rotated child shears the child.
+        let matrix = Mat4::scale(Vec3::new(3.0, 1.0, 0.5)) * Mat4::rotation_z(0.7);
+        assert_encloses_unit_sphere(&matrix);
+    }
+
+    #[test]
+    fn transformed_sphere_is_exact_without_shear() {
+        let unit = BoundingSphere {
+            center: Vec3::new(1.0, 0.0, 0.0),
+            radius: 1.0,
+        };
+        let matrix = Mat4::translation(Vec3::new(0.0, 2.0, 0.0))
+            * Mat4::rotation_y(0.5)
+            * Mat4::scale(Vec3::new(2.0, 3.0, 0.5));
+        let sphere = unit.transformed(&matrix);
+
+        assert!((sphere.radius - 3.0).abs() < 1e-5);
+        assert_encloses_unit_sphere(&matrix);
+    }
+}
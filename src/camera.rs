@@ -0,0 +1,167 @@
+// This file contains the orbit camera, which circles a target point and turns world
+// coordinates into view space.
+//
+// The camera is described by a yaw, a pitch and a distance from its target rather than a
+// free position, so mouse drags can orbit, pan and dolly without the view rolling.
+
+use std::f32::consts::FRAC_PI_2;
+
+use crate::matrix::Mat4;
+use crate::vector::Vec3;
+
+/// Distance in front of the camera at which triangles are clipped, so nothing closer is
+/// projected.
+pub const NEAR_PLANE: f32 = 0.1;
+
+/// Radians of orbit rotation applied per pixel of mouse movement.
+const ORBIT_SENSITIVITY: f32 = 0.01;
+/// Factor the orbit distance is multiplied by for each wheel step towards the target.
+const DOLLY_FACTOR: f32 = 0.9;
+/// Closest the camera may get to its target.
+const MIN_DISTANCE: f32 = 0.1;
+/// Keeps the pitch just short of the poles, where the view basis degenerates.
+const MAX_PITCH: f32 = FRAC_PI_2 - 0.01;
+
+/// A camera that orbits around a target point, suited for inspecting a single model.
+///
+/// The camera looks at `target` from `distance` units away. `yaw` spins it around the
+/// world Y axis and `pitch` tilts it above or below the target.
+#[derive(Debug, Copy, Clone)]
+pub struct OrbitCamera {
+    /// The point the camera orbits around and looks at.
+    pub target: Vec3,
+    /// Rotation around the world Y axis, in radians.
+    pub yaw: f32,
+    /// Elevation above the target, in radians.
+    pub pitch: f32,
+    /// Distance between the camera and its target.
+    pub distance: f32,
+}
+
+impl OrbitCamera {
+    /// Creates a new `OrbitCamera` looking down the +Z axis at `target`.
+    ///
+    /// # Arguments
+    /// - `target`: The point to orbit around.
+    /// - `distance`: The distance from the camera to the target.
+    ///
+    /// # Returns
+    /// A new `OrbitCamera` with no yaw or pitch.
+    pub fn new(target: Vec3, distance: f32) -> OrbitCamera {
+        OrbitCamera {
+            target,
+            yaw: 0.0,
+            pitch: 0.0,
+            distance,
+        }
+    }
+
+    /// Calculates the camera's orthonormal basis.
+    ///
+    /// # Returns
+    /// The `(right, up, forward)` vectors of the camera in world space.
+    pub fn basis(&self) -> (Vec3, Vec3, Vec3) {
+        let forward = Vec3::new(
+            self.yaw.sin() * self.pitch.cos(),
+            self.pitch.sin(),
+            self.yaw.cos() * self.pitch.cos(),
+        );
+        let right = Vec3::new(0.0, 1.0, 0.0).cross(forward).normalize();
+        let up = forward.cross(right);
+        (right, up, forward)
+    }
+
+    /// Calculates the position of the camera in world space.
+    ///
+    /// # Returns
+    /// The camera position as a `Vec3`.
+    pub fn position(&self) -> Vec3 {
+        let (_, _, forward) = self.basis();
+        self.target - forward * self.distance
+    }
+
+    /// Transforms a point from world space into camera (view) space, where the camera
+    /// sits at the origin looking down +Z.
+    ///
+    /// # Arguments
+    /// - `point`: The world space point to transform.
+    ///
+    /// # Returns
+    /// The point in view space.
+    pub fn world_to_view(&self, point: Vec3) -> Vec3 {
+        let (right, up, forward) = self.basis();
+        let offset = point - self.position();
+        Vec3::new(offset.dot(right), offset.dot(up), offset.dot(forward))
+    }
+
+    /// Builds the matrix performing `world_to_view`, to combine with world matrices.
+    ///
+    /// # Returns
+    /// The view matrix as a `Mat4`.
+    pub fn view_matrix(&self) -> Mat4 {
+        let (right, up, forward) = self.basis();
+        let position = self.position();
+        let row = |axis: Vec3| [axis.x, axis.y, axis.z, -axis.dot(position)];
+        Mat4 {
+            m: [row(right), row(up), row(forward), [0.0, 0.0, 0.0, 1.0]],
+        }
+    }
+
+    /// Rotates a direction from world space into camera (view) space.
+    ///
+    /// # Arguments
+    /// - `direction`: The world space direction to rotate.
+    ///
+    /// # Returns
+    /// The direction in view space.
+    pub fn direction_to_view(&self, direction: Vec3) -> Vec3 {
+        let (right, up, forward) = self.basis();
+        Vec3::new(
+            direction.dot(right),
+            direction.dot(up),
+            direction.dot(forward),
+        )
+    }
+
+    /// Rotates the camera around its target.
+    ///
+    /// # Arguments
+    /// - `dx`: Horizontal mouse movement in pixels.
+    /// - `dy`: Vertical mouse movement in pixels.
+    pub fn orbit(&mut self, dx: f32, dy: f32) {
+        self.yaw += dx * ORBIT_SENSITIVITY;
+        self.pitch = (self.pitch + dy * ORBIT_SENSITIVITY).clamp(-MAX_PITCH, MAX_PITCH);
+    }
+
+    /// Moves the target parallel to the view plane so the scene follows the mouse.
+    ///
+    /// # Arguments
+    /// - `dx`: Horizontal mouse movement in pixels.
+    /// - `dy`: Vertical mouse movement in pixels.
+    /// - `fov_factor`: The projection's field of view factor, used to convert pixels
+    ///   into world units at the target's depth.
+    pub fn pan(&mut self, dx: f32, dy: f32, fov_factor: f32) {
+        let (right, up, _) = self.basis();
+        let units_per_pixel = self.distance / fov_factor;
+        self.target = self.target - (right * dx + up * dy) * units_per_pixel;
+    }
+
+    /// Moves the camera towards or away from its target.
+    ///
+    /// # Arguments
+    /// - `steps`: Number of wheel steps; positive values move closer.
+    pub fn dolly(&mut self, steps: f32) {
+        self.distance = (self.distance * DOLLY_FACTOR.powf(steps)).max(MIN_DISTANCE);
+    }
+
+    /// Centers the camera on a bounding sphere and backs off until it fits the view.
+    ///
+    /// # Arguments
+    /// - `center`: The center of the sphere to frame.
+    /// - `radius`: The radius of the sphere to frame.
+    /// - `half_fov`: Half of the narrowest field of view angle, in radians.
+    pub fn frame(&mut self, center: Vec3, radius: f32, half_fov: f32) {
+        self.target = center;
+        self.distance = (radius / half_fov.sin()).max(MIN_DISTANCE);
+    }
+}
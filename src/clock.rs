@@ -0,0 +1,113 @@
+// This file contains the frame clock and the fixed timestep that drive the main loop.
+//
+// The frame clock measures how long each frame took and sleeps to cap the frame rate.
+// The fixed timestep turns those variable frame times into steps of equal length, so the
+// simulation behaves the same at any frame rate.
+
+use std::time::{Duration, Instant};
+
+/// Length of one fixed simulation step, in seconds.
+pub const FIXED_TIMESTEP: f32 = 1.0 / 60.0;
+/// Longest frame delta fed into the simulation, in seconds. Stalls such as dragging the
+/// window would otherwise make the fixed-step loop try to catch up on seconds of work.
+const MAX_DELTA: f32 = 0.25;
+
+/// Measures the time between frames and caps the frame rate.
+pub struct FrameClock {
+    /// Minimum duration of a frame.
+    target_frame_time: Duration,
+    /// When the current frame started.
+    frame_start: Instant,
+}
+
+impl FrameClock {
+    /// Creates a new `FrameClock`.
+    ///
+    /// # Arguments
+    /// - `frames_per_second`: The frame rate the limiter should not exceed.
+    ///
+    /// # Returns
+    /// A new `FrameClock` whose first frame starts now.
+    pub fn new(frames_per_second: u32) -> FrameClock {
+        FrameClock {
+            target_frame_time: Duration::from_secs(1) / frames_per_second,
+            frame_start: Instant::now(),
+        }
+    }
+
+    /// Starts a new frame.
+    ///
+    /// # Returns
+    /// The seconds elapsed since the previous frame started, clamped to `MAX_DELTA`.
+    pub fn tick(&mut self) -> f32 {
+        let now = Instant::now();
+        let delta = now.duration_since(self.frame_start).as_secs_f32();
+        self.frame_start = now;
+        delta.min(MAX_DELTA)
+    }
+
+    /// Sleeps for whatever is left of the frame budget, so slow frames are not
+    /// delayed any further.
+    pub fn limit(&self) {
+        let elapsed = self.frame_start.elapsed();
+        if elapsed < self.target_frame_time {
+            std::thread::sleep(self.target_frame_time - elapsed);
+        }
+    }
+}
+
+/// Splits variable frame deltas into fixed-size simulation steps.
+///
+/// Leftover time is carried over to the next frame, and the fraction of a step it
+/// represents is used to interpolate between the last two simulated states.
+pub struct FixedTimestep {
+    /// Length of one simulation step, in seconds.
+    pub step: f32,
+    /// Time that has not been simulated yet, in seconds.
+    accumulator: f32,
+}
+
+impl FixedTimestep {
+    /// Creates a new `FixedTimestep`.
+    ///
+    /// # Arguments
+    /// - `step`: The length of one simulation step, in seconds.
+    ///
+    /// # Returns
+    /// A new `FixedTimestep` with nothing accumulated.
+    pub fn new(step: f32) -> FixedTimestep {
+        FixedTimestep {
+            step,
+            accumulator: 0.0,
+        }
+    }
+
+    /// Adds frame time waiting to be simulated.
+    ///
+    /// # Arguments
+    /// - `delta`: The elapsed time in seconds.
+    pub fn accumulate(&mut self, delta: f32) {
+        self.accumulator += delta;
+    }
+
+    /// Takes one step off the accumulated time if there is enough of it.
+    ///
+    /// # Returns
+    /// `true` if a simulation step should run.
+    pub fn consume(&mut self) -> bool {
+        if self.accumulator >= self.step {
+            self.accumulator -= self.step;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Calculates how far the leftover time reaches into the next step.
+    ///
+    /// # Returns
+    /// A blend factor in `0.0..1.0` between the previous and current states.
+    pub fn alpha(&self) -> f32 {
+        self.accumulator / self.step
+    }
+}
@@ -0,0 +1,509 @@
+// This file contains the renderer's color types, which every drawing function takes so
+// that only the window code depends on SDL.
+//
+// `Color` holds four bytes, as they are stored in the color buffer and shown on screen,
+// and as colors are written in files: encoded with the sRGB transfer curve, which spends
+// more of the 256 levels on dark shades, where the eye tells them apart best.
+// `LinearColor` holds four floats proportional to the light they stand for, which is
+// what lighting and blending must add and multiply; values above 1.0 are brighter than
+// the screen can show.
+//
+// `Color::to_linear` and `LinearColor::to_srgb` convert between the two through the
+// transfer curve, as do the `From` conversions. Math done on the encoded values, such as
+// blending the window's bytes, goes through `Color::to_encoded` and `Color::from_encoded`
+// instead, which only scale the channels by 255.
+
+use std::ops::{Add, Mul, Sub};
+
+use crate::vector::Vec3;
+
+/// A color with 8-bit red, green, blue and alpha channels.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Color {
+    /// The red channel.
+    pub r: u8,
+    /// The green channel.
+    pub g: u8,
+    /// The blue channel.
+    pub b: u8,
+    /// The alpha channel, from 0 (transparent) to 255 (opaque).
+    pub a: u8,
+}
+
+/// The colors that can be given by name in a scene file.
+const NAMED_COLORS: [(&str, Color); 12] = [
+    ("black", Color::BLACK),
+    ("white", Color::WHITE),
+    ("gray", Color::GRAY),
+    ("red", Color::RED),
+    ("green", Color::GREEN),
+    ("blue", Color::BLUE),
+    ("yellow", Color::YELLOW),
+    ("cyan", Color::CYAN),
+    ("magenta", Color::MAGENTA),
+    ("orange", Color::rgb(255, 165, 0)),
+    ("purple", Color::rgb(128, 0, 128)),
+    ("transparent", Color::TRANSPARENT),
+];
+
+#[allow(dead_code)]
+impl Color {
+    pub const BLACK: Color = Color::rgb(0, 0, 0);
+    pub const WHITE: Color = Color::rgb(255, 255, 255);
+    pub const GRAY: Color = Color::rgb(128, 128, 128);
+    pub const RED: Color = Color::rgb(255, 0, 0);
+    pub const GREEN: Color = Color::rgb(0, 255, 0);
+    pub const BLUE: Color = Color::rgb(0, 0, 255);
+    pub const YELLOW: Color = Color::rgb(255, 255, 0);
+    pub const CYAN: Color = Color::rgb(0, 255, 255);
+    pub const MAGENTA: Color = Color::rgb(255, 0, 255);
+    pub const TRANSPARENT: Color = Color::rgba(0, 0, 0, 0);
+
+    /// Creates an opaque color.
+    ///
+    /// # Arguments
+    /// - `r`, `g`, `b`: The red, green and blue channels.
+    ///
+    /// # Returns
+    /// A new `Color` with an alpha of 255.
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Color {
+        Color { r, g, b, a: 255 }
+    }
+
+    /// Creates a color with an alpha channel.
+    ///
+    /// # Arguments
+    /// - `r`, `g`, `b`: The red, green and blue channels.
+    /// - `a`: The alpha channel, from 0 (transparent) to 255 (opaque).
+    ///
+    /// # Returns
+    /// A new `Color`.
+    pub const fn rgba(r: u8, g: u8, b: u8, a: u8) -> Color {
+        Color { r, g, b, a }
+    }
+
+    /// Returns the same color with a different alpha.
+    pub const fn with_alpha(self, a: u8) -> Color {
+        Color { a, ..self }
+    }
+
+    /// Parses a color from a hex string.
+    ///
+    /// # Arguments
+    /// - `hex`: `#rgb`, `#rrggbb` or `#rrggbbaa`. Colors without alpha are opaque.
+    ///
+    /// # Returns
+    /// The parsed `Color`.
+    ///
+    /// # Errors
+    /// Returns an error if the string has no `#`, the wrong number of digits, or
+    /// characters that are not hex digits.
+    pub fn from_hex(hex: &str) -> Result<Color, String> {
+        let invalid = || format!("`{}` is not a color like \"#rrggbb\"", hex);
+        let digits = match hex.strip_prefix('#') {
+            Some(digits) if digits.chars().all(|c| c.is_ascii_hexdigit()) => digits,
+            _ => return Err(invalid()),
+        };
+        let channel = |i: usize, len: usize| {
+            let value = u8::from_str_radix(&digits[i * len..(i + 1) * len], 16).unwrap_or(0);
+            // A single digit is repeated, so `f` is `ff`.
+            if len == 1 {
+                value * 17
+            } else {
+                value
+            }
+        };
+        match digits.len() {
+            3 => Ok(Color::rgb(channel(0, 1), channel(1, 1), channel(2, 1))),
+            6 => Ok(Color::rgb(channel(0, 2), channel(1, 2), channel(2, 2))),
+            8 => Ok(Color::rgba(
+                channel(0, 2),
+                channel(1, 2),
+                channel(2, 2),
+                channel(3, 2),
+            )),
+            _ => Err(invalid()),
+        }
+    }
+
+    /// Looks up a color by name, such as `red` or `white`.
+    ///
+    /// # Returns
+    /// The matching `Color`, or `None` if the name is unknown.
+    pub fn from_name(name: &str) -> Option<Color> {
+        NAMED_COLORS
+            .iter()
+            .find(|(named, _)| named.eq_ignore_ascii_case(name))
+            .map(|&(_, color)| color)
+    }
+
+    /// Parses a color given either by name or as a hex string; see `from_name` and
+    /// `from_hex`.
+    ///
+    /// # Errors
+    /// Returns an error if the string is neither a known name nor a valid hex color.
+    pub fn parse(text: &str) -> Result<Color, String> {
+        match Color::from_name(text) {
+            Some(color) => Ok(color),
+            None if text.starts_with('#') => Color::from_hex(text),
+            None => Err(format!(
+                "`{}` is neither a color name nor a color like \"#rrggbb\"",
+                text
+            )),
+        }
+    }
+
+    /// Creates an opaque color from hue, saturation and value, which describe the
+    /// encoded channels.
+    ///
+    /// # Arguments
+    /// - `hue`: The hue in degrees; any value is wrapped into 0 to 360.
+    /// - `saturation`: From 0.0 (gray) to 1.0 (pure hue).
+    /// - `value`: From 0.0 (black) to 1.0 (full brightness).
+    ///
+    /// # Returns
+    /// The matching `Color`.
+    pub fn from_hsv(hue: f32, saturation: f32, value: f32) -> Color {
+        let (saturation, value) = (saturation.clamp(0.0, 1.0), value.clamp(0.0, 1.0));
+        let sector = hue.rem_euclid(360.0) / 60.0;
+        let chroma = value * saturation;
+        let second = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+        let (r, g, b) = match sector as u32 {
+            0 => (chroma, second, 0.0),
+            1 => (second, chroma, 0.0),
+            2 => (0.0, chroma, second),
+            3 => (0.0, second, chroma),
+            4 => (second, 0.0, chroma),
+            _ => (chroma, 0.0, second),
+        };
+        let lightest = value - chroma;
+        Color::from_encoded([r + lightest, g + lightest, b + lightest, 1.0])
+    }
+
+    /// Converts the encoded channels of the color to hue, saturation and value, ignoring
+    /// alpha.
+    ///
+    /// # Returns
+    /// The hue in degrees from 0 to 360, and the saturation and value from 0.0 to 1.0.
+    /// Grays have a hue of 0.
+    pub fn to_hsv(self) -> (f32, f32, f32) {
+        let [r, g, b, _] = self.to_encoded();
+        let value = r.max(g).max(b);
+        let chroma = value - r.min(g).min(b);
+        let saturation = if value > 0.0 { chroma / value } else { 0.0 };
+        let hue = if chroma == 0.0 {
+            0.0
+        } else if value == r {
+            60.0 * ((g - b) / chroma).rem_euclid(6.0)
+        } else if value == g {
+            60.0 * ((b - r) / chroma + 2.0)
+        } else {
+            60.0 * ((r - g) / chroma + 4.0)
+        };
+        (hue, saturation, value)
+    }
+
+    /// Decodes the color into linear light. Alpha is not encoded, so it is only scaled.
+    pub fn to_linear(self) -> LinearColor {
+        let channel = |c: u8| srgb_to_linear(c as f32 / 255.0);
+        LinearColor::new(
+            channel(self.r),
+            channel(self.g),
+            channel(self.b),
+            self.a as f32 / 255.0,
+        )
+    }
+
+    /// Scales the channels to 0.0 to 1.0 without decoding them, for math done on the
+    /// encoded values.
+    ///
+    /// # Returns
+    /// The red, green, blue and alpha channels, with red, green and blue still encoded.
+    pub fn to_encoded(self) -> [f32; 4] {
+        [self.r, self.g, self.b, self.a].map(|c| c as f32 / 255.0)
+    }
+
+    /// Converts channels from `to_encoded` back to bytes, clamping every channel to 0.0
+    /// to 1.0, scaling it by 255 and rounding to the nearest byte.
+    pub fn from_encoded(channels: [f32; 4]) -> Color {
+        let [r, g, b, a] = channels.map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8);
+        Color::rgba(r, g, b, a)
+    }
+
+    /// Linearly interpolates between the encoded channels of two colors, alpha included.
+    ///
+    /// # Arguments
+    /// - `other`: The color to interpolate towards.
+    /// - `t`: The interpolation factor, where `0.0` gives `self` and `1.0` gives `other`.
+    ///
+    /// # Returns
+    /// The interpolated `Color`, rounded to the nearest byte.
+    pub fn lerp(self, other: Color, t: f32) -> Color {
+        let (from, to) = (self.to_encoded(), other.to_encoded());
+        Color::from_encoded(std::array::from_fn(|i| from[i] + (to[i] - from[i]) * t))
+    }
+}
+
+/// Implements the addition operator for `Color`, channel by channel with alpha included.
+/// Sums above 255 saturate.
+impl Add for Color {
+    type Output = Color;
+
+    fn add(self, other: Color) -> Color {
+        Color {
+            r: self.r.saturating_add(other.r),
+            g: self.g.saturating_add(other.g),
+            b: self.b.saturating_add(other.b),
+            a: self.a.saturating_add(other.a),
+        }
+    }
+}
+
+/// Implements the subtraction operator for `Color`, channel by channel with alpha
+/// included. Differences below 0 saturate.
+impl Sub for Color {
+    type Output = Color;
+
+    fn sub(self, other: Color) -> Color {
+        Color {
+            r: self.r.saturating_sub(other.r),
+            g: self.g.saturating_sub(other.g),
+            b: self.b.saturating_sub(other.b),
+            a: self.a.saturating_sub(other.a),
+        }
+    }
+}
+
+/// Implements scaling a `Color`: every channel, alpha included, is multiplied by the
+/// scalar, rounded and clamped to 0 to 255.
+///
+/// # Example
+/// ```
+/// let half = Color::rgba(200, 100, 0, 255) * 0.5; // Color { r: 100, g: 50, b: 0, a: 128 }
+/// ```
+impl Mul<f32> for Color {
+    type Output = Color;
+
+    fn mul(self, scalar: f32) -> Color {
+        Color::from_encoded(self.to_encoded().map(|c| c * scalar))
+    }
+}
+
+/// Implements modulating one `Color` by another, multiplying the encoded channels with
+/// alpha included, as when tinting a texture by a vertex color. White leaves a color
+/// unchanged.
+impl Mul for Color {
+    type Output = Color;
+
+    fn mul(self, other: Color) -> Color {
+        let (a, b) = (self.to_encoded(), other.to_encoded());
+        Color::from_encoded(std::array::from_fn(|i| a[i] * b[i]))
+    }
+}
+
+/// A color with floating-point red, green, blue and alpha channels, nominally from 0.0 to
+/// 1.0. Values outside that range are kept until the color is converted to a `Color`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct LinearColor {
+    /// The red channel.
+    pub r: f32,
+    /// The green channel.
+    pub g: f32,
+    /// The blue channel.
+    pub b: f32,
+    /// The alpha channel, from 0.0 (transparent) to 1.0 (opaque).
+    pub a: f32,
+}
+
+#[allow(dead_code)]
+impl LinearColor {
+    /// Creates a new instance of `LinearColor`.
+    pub fn new(r: f32, g: f32, b: f32, a: f32) -> LinearColor {
+        LinearColor { r, g, b, a }
+    }
+
+    /// Creates an opaque color from a vector of red, green and blue channels, as
+    /// materials and lights store them.
+    pub fn from_rgb(rgb: Vec3) -> LinearColor {
+        LinearColor::new(rgb.x, rgb.y, rgb.z, 1.0)
+    }
+
+    /// Returns the red, green and blue channels as a vector.
+    pub fn rgb(&self) -> Vec3 {
+        Vec3::new(self.r, self.g, self.b)
+    }
+
+    /// Encodes the color with the sRGB transfer curve, clamping every channel to 0.0 to
+    /// 1.0 first. Alpha is not encoded, so it is only scaled.
+    pub fn to_srgb(self) -> Color {
+        let channel = |c: f32| (linear_to_srgb(c.clamp(0.0, 1.0)) * 255.0).round() as u8;
+        Color::rgba(
+            channel(self.r),
+            channel(self.g),
+            channel(self.b),
+            (self.a.clamp(0.0, 1.0) * 255.0).round() as u8,
+        )
+    }
+
+    /// Linearly interpolates between two colors, alpha included.
+    ///
+    /// # Arguments
+    /// - `other`: The color to interpolate towards.
+    /// - `t`: The interpolation factor, where `0.0` gives `self` and `1.0` gives `other`.
+    ///
+    /// # Returns
+    /// A new interpolated `LinearColor`.
+    pub fn lerp(&self, other: LinearColor, t: f32) -> LinearColor {
+        *self + (other - *self) * t
+    }
+}
+
+/// Implements the addition operator for `LinearColor`, alpha included.
+impl Add for LinearColor {
+    type Output = LinearColor;
+
+    fn add(self, other: LinearColor) -> LinearColor {
+        LinearColor {
+            r: self.r + other.r,
+            g: self.g + other.g,
+            b: self.b + other.b,
+            a: self.a + other.a,
+        }
+    }
+}
+
+/// Implements the subtraction operator for `LinearColor`, alpha included.
+impl Sub for LinearColor {
+    type Output = LinearColor;
+
+    fn sub(self, other: LinearColor) -> LinearColor {
+        LinearColor {
+            r: self.r - other.r,
+            g: self.g - other.g,
+            b: self.b - other.b,
+            a: self.a - other.a,
+        }
+    }
+}
+
+/// Implements scalar multiplication for `LinearColor`, alpha included.
+impl Mul<f32> for LinearColor {
+    type Output = LinearColor;
+
+    fn mul(self, scalar: f32) -> LinearColor {
+        LinearColor {
+            r: self.r * scalar,
+            g: self.g * scalar,
+            b: self.b * scalar,
+            a: self.a * scalar,
+        }
+    }
+}
+
+/// Implements multiplying two `LinearColor`s channel by channel, alpha included.
+impl Mul for LinearColor {
+    type Output = LinearColor;
+
+    fn mul(self, other: LinearColor) -> LinearColor {
+        LinearColor {
+            r: self.r * other.r,
+            g: self.g * other.g,
+            b: self.b * other.b,
+            a: self.a * other.a,
+        }
+    }
+}
+
+/// Decodes a `Color` into linear light; see `Color::to_linear`.
+impl From<Color> for LinearColor {
+    fn from(color: Color) -> LinearColor {
+        color.to_linear()
+    }
+}
+
+/// Encodes a `LinearColor` with the sRGB transfer curve; see `LinearColor::to_srgb`.
+impl From<LinearColor> for Color {
+    fn from(color: LinearColor) -> Color {
+        color.to_srgb()
+    }
+}
+
+/// Decodes a channel from the sRGB transfer curve into linear light.
+///
+/// # Arguments
+/// - `c`: The encoded channel, from 0.0 to 1.0.
+///
+/// # Returns
+/// The channel in linear light, from 0.0 to 1.0.
+pub fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Encodes a channel in linear light with the sRGB transfer curve.
+///
+/// # Arguments
+/// - `c`: The channel in linear light, from 0.0 to 1.0.
+///
+/// # Returns
+/// The encoded channel, from 0.0 to 1.0.
+pub fn linear_to_srgb(c: f32) -> f32 {
+    if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Decodes every channel of an sRGB-encoded color vector into linear light, as colors
+/// read from files are stored.
+pub fn srgb_to_linear_rgb(rgb: Vec3) -> Vec3 {
+    Vec3::new(
+        srgb_to_linear(rgb.x),
+        srgb_to_linear(rgb.y),
+        srgb_to_linear(rgb.z),
+    )
+}
+
+/// Encodes every channel of a color vector in linear light with the sRGB transfer curve.
+pub fn linear_to_srgb_rgb(rgb: Vec3) -> Vec3 {
+    Vec3::new(
+        linear_to_srgb(rgb.x),
+        linear_to_srgb(rgb.y),
+        linear_to_srgb(rgb.z),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_goes_through_the_transfer_curve() {
+        let color = Color::rgba(128, 0, 255, 51);
+        let linear = LinearColor::from(color);
+        assert_eq!(linear, color.to_linear());
+        assert!((linear.r - 0.2158605).abs() < 1e-6, "{}", linear.r);
+        assert_eq!((linear.g, linear.b, linear.a), (0.0, 1.0, 0.2));
+        for value in 0..=255 {
+            let color = Color::rgba(value, value, value, value);
+            assert_eq!(Color::from(LinearColor::from(color)), color);
+        }
+    }
+
+    #[test]
+    fn encoded_math_keeps_the_encoding() {
+        let color = Color::rgba(200, 100, 0, 255);
+        assert_eq!(Color::from_encoded(color.to_encoded()), color);
+        assert_eq!(color * 0.5, Color::rgba(100, 50, 0, 128));
+        assert_eq!(color * Color::GRAY, Color::rgba(100, 50, 0, 255));
+        assert_eq!(Color::BLACK.lerp(Color::WHITE, 0.5), Color::GRAY);
+        assert_eq!(Color::from_hsv(120.0, 1.0, 0.5), Color::rgb(0, 128, 0));
+        let (hue, saturation, value) = Color::rgb(255, 128, 0).to_hsv();
+        assert!((hue - 30.1176).abs() < 1e-3, "{}", hue);
+        assert_eq!((saturation, value), (1.0, 1.0));
+    }
+}
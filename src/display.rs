@@ -5,6 +5,9 @@ extern crate sdl2;
 use sdl2::render::Canvas;
 use sdl2::video::{Window, WindowBuildError};
 
+use crate::blend::{BlendMode, BYTES_PER_PIXEL};
+use crate::color::Color;
+use crate::font::Font;
 use crate::vector::Vec2;
 
 /// The width of the application window in pixels.
@@ -14,6 +17,9 @@ pub const WINDOW_HEIGHT: u32 = 600;
 /// The target frame rate for the application.
 pub const FRAMES_PER_SECOND: u32 = 30;
 
+/// The widest line `draw_stroke` is asked to draw, in pixels.
+pub const MAX_LINE_WIDTH: f32 = 32.0;
+
 /// The title of the application window.
 const WINDOW_TITLE: &str = "Renderer Learning";
 
@@ -35,35 +41,41 @@ pub fn initialize_window(sdl_context: &sdl2::Sdl) -> Result<Window, WindowBuildE
     Ok(window)
 }
 
-/// Clears the color buffer by resetting all pixel values to black.
-///
-/// # Arguments
-/// - `color_buffer`: A mutable reference to the color buffer.
-pub fn clear_color_buffer(color_buffer: &mut Vec<u8>) {
-    *color_buffer = vec![0; (WINDOW_WIDTH * WINDOW_HEIGHT * 3) as usize];
+/// Counts the rows of pixels in a color buffer `WINDOW_WIDTH` pixels wide.
+fn buffer_height(color_buffer: &[u8]) -> u32 {
+    (color_buffer.len() / (WINDOW_WIDTH as usize * BYTES_PER_PIXEL)) as u32
 }
 
-/// Renders the contents of the color buffer onto the SDL canvas.
+/// Renders the contents of the color buffer onto the SDL canvas. The alpha channel is
+/// ignored, as the window is opaque.
 ///
 /// # Arguments
 /// - `canvas`: The mutable SDL canvas for rendering.
 /// - `color_buffer`: The buffer containing pixel data to be rendered.
-pub fn render_color_buffer(canvas: &mut Canvas<Window>, color_buffer: &Vec<u8>) {
+pub fn render_color_buffer(canvas: &mut Canvas<Window>, color_buffer: &[u8]) {
     let texture_creator = canvas.texture_creator();
     let mut texture = texture_creator
         .create_texture_streaming(
-            sdl2::pixels::PixelFormatEnum::RGB24,
+            sdl2::pixels::PixelFormatEnum::RGBA32,
             WINDOW_WIDTH,
             WINDOW_HEIGHT,
         )
         .unwrap();
 
     texture
-        .update(None, &color_buffer, (WINDOW_WIDTH * 3) as usize)
+        .update(None, color_buffer, WINDOW_WIDTH as usize * BYTES_PER_PIXEL)
         .unwrap();
     canvas.copy(&texture, None, None).unwrap();
 }
 
+/// Converts a renderer color to SDL's, for drawing on the canvas itself. This is the only
+/// place the two meet; everything else draws into the color buffer.
+impl From<Color> for sdl2::pixels::Color {
+    fn from(color: Color) -> sdl2::pixels::Color {
+        sdl2::pixels::Color::RGBA(color.r, color.g, color.b, color.a)
+    }
+}
+
 /// Draws a single pixel at a specified position in the color buffer.
 ///
 /// # Arguments
@@ -71,14 +83,46 @@ pub fn render_color_buffer(canvas: &mut Canvas<Window>, color_buffer: &Vec<u8>)
 /// - `x`: The x-coordinate of the pixel.
 /// - `y`: The y-coordinate of the pixel.
 /// - `color`: The color of the pixel (RGBA).
-pub fn draw_pixel(color_buffer: &mut [u8], x: u32, y: u32, color: sdl2::pixels::Color) {
-    if x >= WINDOW_WIDTH || y >= color_buffer.len() as u32 / (WINDOW_WIDTH * 3) {
+/// - `blend`: How the color is combined with the pixel.
+pub fn draw_pixel(color_buffer: &mut [u8], x: u32, y: u32, color: Color, blend: BlendMode) {
+    if x >= WINDOW_WIDTH || y >= buffer_height(color_buffer) {
         return; // Ignore out-of-bound pixels.
     }
-    let pixel_index = (y * WINDOW_WIDTH + x) as usize * 3;
-    color_buffer[pixel_index] = color.r;
-    color_buffer[pixel_index + 1] = color.g;
-    color_buffer[pixel_index + 2] = color.b;
+    let pixel_index = (y * WINDOW_WIDTH + x) as usize * BYTES_PER_PIXEL;
+    blend.apply(
+        &mut color_buffer[pixel_index..pixel_index + BYTES_PER_PIXEL],
+        color,
+        1.0,
+    );
+}
+
+/// Blends a color over part of a single pixel of the color buffer.
+///
+/// # Arguments
+/// - `color_buffer`: A mutable reference to the color buffer.
+/// - `x`: The x-coordinate of the pixel; pixels outside the buffer are ignored.
+/// - `y`: The y-coordinate of the pixel; pixels outside the buffer are ignored.
+/// - `color`: The color to blend in (RGBA).
+/// - `blend`: How the color is combined with the pixel.
+/// - `coverage`: How much of the pixel the color covers, from 0.0 to 1.0.
+pub fn blend_pixel(
+    color_buffer: &mut [u8],
+    x: i32,
+    y: i32,
+    color: Color,
+    blend: BlendMode,
+    coverage: f32,
+) {
+    let height = buffer_height(color_buffer);
+    if x < 0 || y < 0 || x as u32 >= WINDOW_WIDTH || y as u32 >= height || coverage <= 0.0 {
+        return; // Ignore out-of-bound and uncovered pixels.
+    }
+    let pixel_index = (y as u32 * WINDOW_WIDTH + x as u32) as usize * BYTES_PER_PIXEL;
+    blend.apply(
+        &mut color_buffer[pixel_index..pixel_index + BYTES_PER_PIXEL],
+        color,
+        coverage,
+    );
 }
 
 /// Draws a filled rectangle on the color buffer.
@@ -90,13 +134,15 @@ pub fn draw_pixel(color_buffer: &mut [u8], x: u32, y: u32, color: sdl2::pixels::
 /// - `width`: The width of the rectangle.
 /// - `height`: The height of the rectangle.
 /// - `color`: The color of the rectangle (RGBA).
+/// - `blend`: How the color is combined with the pixels.
 pub fn draw_rect(
-    color_buffer: &mut Vec<u8>,
+    color_buffer: &mut [u8],
     x: u32,
     y: u32,
     width: u32,
     height: u32,
-    color: sdl2::pixels::Color,
+    color: Color,
+    blend: BlendMode,
 ) {
     if x >= WINDOW_WIDTH || y >= WINDOW_HEIGHT {
         return; // Ignore out-of-bound rectangles.
@@ -104,7 +150,7 @@ pub fn draw_rect(
 
     for row in y..y + height {
         for col in x..x + width {
-            draw_pixel(color_buffer, col, row, color);
+            draw_pixel(color_buffer, col, row, color, blend);
         }
     }
 }
@@ -115,53 +161,257 @@ pub fn draw_rect(
 /// - `color_buffer`: A mutable reference to the color buffer.
 /// - `size`: The size of each grid cell in pixels.
 #[allow(dead_code)] // Suppresses warnings for unused function during development.
-pub fn draw_grid(color_buffer: &mut Vec<u8>, size: usize) {
+pub fn draw_grid(color_buffer: &mut [u8], size: usize) {
     for y in (0..WINDOW_HEIGHT).step_by(size) {
         for x in (0..WINDOW_WIDTH).step_by(size) {
             draw_pixel(
                 color_buffer,
                 x,
                 y,
-                sdl2::pixels::Color::RGBA(255, 255, 255, 255), // White grid lines.
+                Color::WHITE, // White grid lines.
+                BlendMode::Replace,
             );
         }
     }
 }
 
+/// Draws a string using the built-in 8x8 bitmap font.
+///
+/// # Arguments
+/// - `color_buffer`: A mutable reference to the color buffer.
+/// - `x`: The x-coordinate of the top-left corner of the text.
+/// - `y`: The y-coordinate of the top-left corner of the text.
+/// - `text`: The text to draw. `\n` starts a new line and characters outside printable
+///   ASCII are drawn as `?`.
+/// - `color`: The color of the text (RGBA).
+/// - `blend`: How the color is combined with the pixels.
+/// - `scale`: The integer factor each font pixel is scaled by.
+#[allow(dead_code)]
+pub fn draw_text(
+    color_buffer: &mut [u8],
+    x: u32,
+    y: u32,
+    text: &str,
+    color: Color,
+    blend: BlendMode,
+    scale: u32,
+) {
+    draw_text_with_font(
+        color_buffer,
+        Font::builtin(),
+        x,
+        y,
+        text,
+        color,
+        blend,
+        scale,
+    );
+}
+
+/// Draws a string using the given bitmap font.
+///
+/// # Arguments
+/// - `color_buffer`: A mutable reference to the color buffer.
+/// - `font`: The font to draw the text with.
+/// - `x`: The x-coordinate of the top-left corner of the text.
+/// - `y`: The y-coordinate of the top-left corner of the text.
+/// - `text`: The text to draw. `\n` starts a new line.
+/// - `color`: The color of the text (RGBA).
+/// - `blend`: How the color is combined with the pixels.
+/// - `scale`: The integer factor each font pixel is scaled by.
+#[allow(clippy::too_many_arguments)] // Mirrors `draw_text`, plus the font.
+pub fn draw_text_with_font(
+    color_buffer: &mut [u8],
+    font: &Font,
+    x: u32,
+    y: u32,
+    text: &str,
+    color: Color,
+    blend: BlendMode,
+    scale: u32,
+) {
+    let mut pen_x = x as i32;
+    let mut pen_y = y as i32;
+
+    for c in text.chars() {
+        if c == '\n' {
+            pen_x = x as i32;
+            pen_y += (font.line_height * scale) as i32;
+            continue;
+        }
+
+        let glyph = match font.glyph(c) {
+            Some(glyph) => glyph,
+            None => continue, // The font has nothing to draw for this character.
+        };
+
+        for row in 0..glyph.height {
+            for col in 0..glyph.width {
+                if !glyph.is_set(col, row) {
+                    continue;
+                }
+                let px = pen_x + (glyph.x_offset + col as i32) * scale as i32;
+                let py = pen_y + (glyph.y_offset + row as i32) * scale as i32;
+                if px >= 0 && py >= 0 {
+                    draw_rect(
+                        color_buffer,
+                        px as u32,
+                        py as u32,
+                        scale,
+                        scale,
+                        color,
+                        blend,
+                    );
+                }
+            }
+        }
+        pen_x += (glyph.advance * scale) as i32;
+    }
+}
+
+/// Measures a string as it would be drawn by `draw_text`.
+///
+/// # Arguments
+/// - `text`: The text to measure.
+/// - `scale`: The integer factor each font pixel is scaled by.
+///
+/// # Returns
+/// The `(width, height)` of the text in pixels.
+#[allow(dead_code)]
+pub fn measure_text(text: &str, scale: u32) -> (u32, u32) {
+    Font::builtin().measure(text, scale)
+}
+
 /// Draws a triangle by connecting its vertices with lines.
 ///
 /// # Arguments
 /// - `color_buffer`: A mutable reference to the color buffer.
 /// - `points`: An array of three 2D points (`Vec2`) representing the vertices of the triangle.
 /// - `color`: The color of the triangle (RGBA).
-pub fn draw_triangle(color_buffer: &mut Vec<u8>, points: [Vec2; 3], color: sdl2::pixels::Color) {
+/// - `blend`: How the color is combined with the pixels.
+/// - `stroke`: How the edges are drawn.
+pub fn draw_triangle(
+    color_buffer: &mut [u8],
+    points: [Vec2; 3],
+    color: Color,
+    blend: BlendMode,
+    stroke: Stroke,
+) {
     for i in 0..3 {
         let p0 = points[i];
         let p1 = points[(i + 1) % 3]; // Connect the last point to the first.
+        draw_stroke(color_buffer, p0, p1, color, blend, stroke);
+    }
+}
 
-        draw_line(
-            color_buffer,
-            p0.x as i32,
-            p0.y as i32,
-            p1.x as i32,
-            p1.y as i32,
-            color,
-        );
+/// Draws a small square marker centered on each vertex of a triangle.
+///
+/// # Arguments
+/// - `color_buffer`: A mutable reference to the color buffer.
+/// - `points`: An array of three 2D points (`Vec2`) representing the vertices of the triangle.
+/// - `color`: The color of the markers (RGBA).
+/// - `blend`: How the color is combined with the pixels.
+pub fn draw_triangle_vertices(
+    color_buffer: &mut [u8],
+    points: [Vec2; 3],
+    color: Color,
+    blend: BlendMode,
+) {
+    for point in points {
+        if point.x >= 2.0 && point.y >= 2.0 {
+            draw_rect(
+                color_buffer,
+                point.x as u32 - 2,
+                point.y as u32 - 2,
+                4,
+                4,
+                color,
+                blend,
+            );
+        }
+    }
+}
 
-        // Draw vertex points
-        draw_rect(
-            color_buffer,
-            p0.x as u32,
-            p0.y as u32,
-            4,
-            4,
-            sdl2::pixels::Color::RGBA(255, 0, 0, 255),
-        )
+/// Clips a line segment to a rectangle with the Liang–Barsky algorithm.
+///
+/// The segment is written as `origin + t * (p1 - p0)`, and each side of the rectangle
+/// narrows the range of `t` inside it. The origin is whichever of `p0`, `p1` and the
+/// middle of the segment lies nearest the rectangle, as the rounding of the clipped
+/// endpoints grows with their distance from it.
+///
+/// # Arguments
+/// - `p0`: The starting point.
+/// - `p1`: The ending point.
+/// - `min`: The corner of the rectangle with the smallest coordinates.
+/// - `max`: The corner of the rectangle with the largest coordinates.
+///
+/// # Returns
+/// The endpoints of the part of the segment inside the rectangle, in the same order, or
+/// `None` if no part of it is inside or an endpoint is not finite.
+fn clip_segment(
+    p0: (f64, f64),
+    p1: (f64, f64),
+    min: (f64, f64),
+    max: (f64, f64),
+) -> Option<((f64, f64), (f64, f64))> {
+    if ![p0.0, p0.1, p1.0, p1.1].iter().all(|v| v.is_finite()) {
+        return None;
     }
+    let center = ((min.0 + max.0) / 2.0, (min.1 + max.1) / 2.0);
+    let distance = |p: (f64, f64)| (p.0 - center.0).abs().max((p.1 - center.1).abs());
+    let middle = (p0.0 / 2.0 + p1.0 / 2.0, p0.1 / 2.0 + p1.1 / 2.0);
+    let (origin, start) = [(p0, 0.0), (middle, 0.5), (p1, 1.0)]
+        .into_iter()
+        .min_by(|a, b| distance(a.0).total_cmp(&distance(b.0)))
+        .unwrap();
+
+    let (dx, dy) = (p1.0 - p0.0, p1.1 - p0.1);
+    let (mut t0, mut t1) = (-start, 1.0 - start);
+    let (mut clipped0, mut clipped1) = (false, false);
+    // Each side as the rate the segment moves out through it, and how far inside it the
+    // origin lies.
+    let sides = [
+        (-dx, origin.0 - min.0),
+        (dx, max.0 - origin.0),
+        (-dy, origin.1 - min.1),
+        (dy, max.1 - origin.1),
+    ];
+    for (outward, inside) in sides {
+        if outward == 0.0 {
+            if inside < 0.0 {
+                return None; // Parallel to the side and outside it.
+            }
+        } else {
+            let t = inside / outward;
+            if outward < 0.0 && t > t0 {
+                (t0, clipped0) = (t, true); // Enters through this side.
+            } else if outward > 0.0 && t < t1 {
+                (t1, clipped1) = (t, true); // Leaves through this side.
+            }
+        }
+    }
+    if t0 > t1 {
+        return None;
+    }
+    // Clamping only removes rounding, as the exact clipped endpoints lie on the
+    // rectangle.
+    let point = |t: f64| {
+        (
+            (origin.0 + t * dx).clamp(min.0, max.0),
+            (origin.1 + t * dy).clamp(min.1, max.1),
+        )
+    };
+    Some((
+        if clipped0 { point(t0) } else { p0 },
+        if clipped1 { point(t1) } else { p1 },
+    ))
 }
 
 /// Draws a line using the Bresenham's line algorithm.
 ///
+/// The line is first clipped to the color buffer, so only the pixels inside it are
+/// walked however far away the endpoints are.
+///
 /// # Arguments
 /// - `color_buffer`: A mutable reference to the color buffer.
 /// - `x0`: The x-coordinate of the starting point.
@@ -169,14 +419,35 @@ pub fn draw_triangle(color_buffer: &mut Vec<u8>, points: [Vec2; 3], color: sdl2:
 /// - `x1`: The x-coordinate of the ending point.
 /// - `y1`: The y-coordinate of the ending point.
 /// - `color`: The color of the line (RGBA).
+/// - `blend`: How the color is combined with the pixels.
 pub fn draw_line(
-    color_buffer: &mut Vec<u8>,
-    mut x0: i32,
-    mut y0: i32,
+    color_buffer: &mut [u8],
+    x0: i32,
+    y0: i32,
     x1: i32,
     y1: i32,
-    color: sdl2::pixels::Color,
+    color: Color,
+    blend: BlendMode,
 ) {
+    let height = buffer_height(color_buffer);
+    if height == 0 {
+        return;
+    }
+    // Clip to the centers of the border pixels, and round the clipped endpoints to the
+    // pixels the line passes through there.
+    let clipped = clip_segment(
+        (x0 as f64, y0 as f64),
+        (x1 as f64, y1 as f64),
+        (0.0, 0.0),
+        ((WINDOW_WIDTH - 1) as f64, (height - 1) as f64),
+    );
+    let (start, end) = match clipped {
+        Some(segment) => segment,
+        None => return, // The line misses the color buffer.
+    };
+    let (mut x0, mut y0) = (start.0.round() as i32, start.1.round() as i32);
+    let (x1, y1) = (end.0.round() as i32, end.1.round() as i32);
+
     let dx = (x1 - x0).abs();
     let dy = -(y1 - y0).abs();
     let sx = if x0 < x1 { 1 } else { -1 };
@@ -185,7 +456,7 @@ pub fn draw_line(
     let mut e2;
 
     loop {
-        draw_pixel(color_buffer, x0 as u32, y0 as u32, color); // Draw the current point.
+        draw_pixel(color_buffer, x0 as u32, y0 as u32, color, blend); // Draw the current point.
 
         if x0 == x1 && y0 == y1 {
             break; // Stop when the end point is reached.
@@ -204,3 +475,541 @@ pub fn draw_line(
         }
     }
 }
+
+/// How the edges of lines are drawn.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LineStyle {
+    /// Pixels are either fully covered by the line or left alone.
+    Aliased,
+    /// Pixels the line partly covers are blended with it, which smooths its edges.
+    Smooth,
+}
+
+impl LineStyle {
+    /// Parses a line style from its name in a scene file.
+    ///
+    /// # Arguments
+    /// - `name`: Either `aliased` or `smooth`.
+    ///
+    /// # Returns
+    /// The matching `LineStyle`, or `None` if the name is unknown.
+    pub fn from_name(name: &str) -> Option<LineStyle> {
+        match name {
+            "aliased" => Some(LineStyle::Aliased),
+            "smooth" => Some(LineStyle::Smooth),
+            _ => None,
+        }
+    }
+
+    /// Returns the other style, for switching between them at runtime.
+    pub fn toggled(&self) -> LineStyle {
+        match self {
+            LineStyle::Aliased => LineStyle::Smooth,
+            LineStyle::Smooth => LineStyle::Aliased,
+        }
+    }
+}
+
+/// How the ends of lines wider than a pixel are shaped.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LineCap {
+    /// The line stops square at its endpoints.
+    Butt,
+    /// The line stops square half its width past its endpoints.
+    Square,
+    /// The line ends in half circles centered on its endpoints.
+    Round,
+}
+
+impl LineCap {
+    /// Parses a line cap from its name in a scene file.
+    ///
+    /// # Arguments
+    /// - `name`: One of `butt`, `square` or `round`.
+    ///
+    /// # Returns
+    /// The matching `LineCap`, or `None` if the name is unknown.
+    pub fn from_name(name: &str) -> Option<LineCap> {
+        match name {
+            "butt" => Some(LineCap::Butt),
+            "square" => Some(LineCap::Square),
+            "round" => Some(LineCap::Round),
+            _ => None,
+        }
+    }
+}
+
+/// How a line is drawn.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Stroke {
+    /// How the edges of the line are drawn.
+    pub style: LineStyle,
+    /// The width of the line in pixels.
+    pub width: f32,
+    /// How the ends of the line are shaped, when it is wider than a pixel.
+    pub cap: LineCap,
+}
+
+/// Draws a line between two points with a stroke.
+///
+/// Lines one pixel wide or less are drawn with `draw_line` or `draw_line_wu`, and wider
+/// ones with `draw_thick_line`. Nothing is drawn if an endpoint is not finite.
+///
+/// # Arguments
+/// - `color_buffer`: A mutable reference to the color buffer.
+/// - `p0`: The starting point, in pixels.
+/// - `p1`: The ending point, in pixels.
+/// - `color`: The color of the line (RGBA).
+/// - `blend`: How the color is combined with the pixels.
+/// - `stroke`: How the line is drawn.
+pub fn draw_stroke(
+    color_buffer: &mut [u8],
+    p0: Vec2,
+    p1: Vec2,
+    color: Color,
+    blend: BlendMode,
+    stroke: Stroke,
+) {
+    if ![p0.x, p0.y, p1.x, p1.y].iter().all(|v| v.is_finite()) {
+        return; // Casting to pixels would turn NaN into 0 and infinities into the edge.
+    }
+    match stroke.style {
+        _ if stroke.width > 1.0 => draw_thick_line(color_buffer, p0, p1, color, blend, stroke),
+        LineStyle::Aliased => draw_line(
+            color_buffer,
+            p0.x as i32,
+            p0.y as i32,
+            p1.x as i32,
+            p1.y as i32,
+            color,
+            blend,
+        ),
+        LineStyle::Smooth => draw_line_wu(color_buffer, p0, p1, color, blend),
+    }
+}
+
+/// Draws an anti-aliased line one pixel wide using Xiaolin Wu's algorithm.
+///
+/// The line steps one pixel at a time along its major axis and splits its color between
+/// the two pixels it passes between on the minor axis, in proportion to how close it
+/// passes to each. The endpoint pixels are also weighed by how much of them the line
+/// reaches into. Like `draw_line`, the line is clipped to the color buffer first.
+///
+/// # Arguments
+/// - `color_buffer`: A mutable reference to the color buffer.
+/// - `p0`: The starting point, in pixels.
+/// - `p1`: The ending point, in pixels.
+/// - `color`: The color of the line (RGBA).
+/// - `blend`: How the color is combined with the pixels.
+pub fn draw_line_wu(color_buffer: &mut [u8], p0: Vec2, p1: Vec2, color: Color, blend: BlendMode) {
+    // Clip a couple of pixels outside the color buffer, so the clipped endpoints' partial
+    // coverage falls on pixels that are not shown.
+    let height = buffer_height(color_buffer);
+    let clipped = clip_segment(
+        (p0.x as f64, p0.y as f64),
+        (p1.x as f64, p1.y as f64),
+        (-2.0, -2.0),
+        (WINDOW_WIDTH as f64 + 2.0, height as f64 + 2.0),
+    );
+    let (start, end) = match clipped {
+        Some(segment) => segment,
+        None => return, // The line misses the color buffer.
+    };
+
+    // The algorithm places pixel centers on whole coordinates, while they sit half a
+    // pixel in everywhere else.
+    let (mut x0, mut y0) = (start.0 as f32 - 0.5, start.1 as f32 - 0.5);
+    let (mut x1, mut y1) = (end.0 as f32 - 0.5, end.1 as f32 - 0.5);
+    let steep = (y1 - y0).abs() > (x1 - x0).abs();
+    if steep {
+        std::mem::swap(&mut x0, &mut y0);
+        std::mem::swap(&mut x1, &mut y1);
+    }
+    if x0 > x1 {
+        std::mem::swap(&mut x0, &mut x1);
+        std::mem::swap(&mut y0, &mut y1);
+    }
+    let gradient = if x1 == x0 { 1.0 } else { (y1 - y0) / (x1 - x0) };
+    let mut plot = |major: i32, minor: i32, coverage: f32| {
+        if steep {
+            blend_pixel(color_buffer, minor, major, color, blend, coverage);
+        } else {
+            blend_pixel(color_buffer, major, minor, color, blend, coverage);
+        }
+    };
+    let fract = |v: f32| v - v.floor();
+
+    // Each endpoint pixel is weighed by how much of it the line covers along the
+    // major axis.
+    let mut endpoint = |x: f32, y: f32, gap: f32| {
+        let x_end = x.round();
+        let y_end = y + gradient * (x_end - x);
+        let minor = y_end.floor() as i32;
+        plot(x_end as i32, minor, (1.0 - fract(y_end)) * gap);
+        plot(x_end as i32, minor + 1, fract(y_end) * gap);
+        (x_end as i32, y_end)
+    };
+    let (first, y_first) = endpoint(x0, y0, 1.0 - fract(x0 + 0.5));
+    let (last, _) = endpoint(x1, y1, fract(x1 + 0.5));
+
+    let mut y = y_first + gradient;
+    for x in first + 1..last {
+        let minor = y.floor() as i32;
+        plot(x, minor, 1.0 - fract(y));
+        plot(x, minor + 1, fract(y));
+        y += gradient;
+    }
+}
+
+/// Draws a line wider than a pixel, with its ends shaped by the stroke's cap.
+///
+/// Each pixel center near the line is measured against the line's outline: the pixel is
+/// covered when the center lies inside, and for a smooth stroke partly covered when it
+/// lies within half a pixel of the outline.
+///
+/// # Arguments
+/// - `color_buffer`: A mutable reference to the color buffer.
+/// - `p0`: The starting point, in pixels.
+/// - `p1`: The ending point, in pixels.
+/// - `color`: The color of the line (RGBA).
+/// - `blend`: How the color is combined with the pixels.
+/// - `stroke`: The width, cap and style of the line.
+pub fn draw_thick_line(
+    color_buffer: &mut [u8],
+    p0: Vec2,
+    p1: Vec2,
+    color: Color,
+    blend: BlendMode,
+    stroke: Stroke,
+) {
+    // Clip the line far enough outside the color buffer that its ends and caps there
+    // stay out of sight, which also keeps the distances below within `f32` precision.
+    let half_width = stroke.width / 2.0;
+    let height = buffer_height(color_buffer);
+    let margin = stroke.width as f64 + 2.0;
+    let clipped = clip_segment(
+        (p0.x as f64, p0.y as f64),
+        (p1.x as f64, p1.y as f64),
+        (-margin, -margin),
+        (WINDOW_WIDTH as f64 + margin, height as f64 + margin),
+    );
+    let (p0, p1) = match clipped {
+        Some((start, end)) => (
+            Vec2::new(start.0 as f32, start.1 as f32),
+            Vec2::new(end.0 as f32, end.1 as f32),
+        ),
+        None => return, // The line misses the color buffer.
+    };
+    let length = (p1 - p0).len();
+    // A line too short to have a direction is drawn as if it were horizontal.
+    let direction = if length > 0.0 {
+        (p1 - p0) / length
+    } else {
+        Vec2::new(1.0, 0.0)
+    };
+    let extension = match stroke.cap {
+        LineCap::Butt => 0.0,
+        LineCap::Square | LineCap::Round => half_width,
+    };
+
+    // Converts a signed distance from the outline, positive inside, into coverage.
+    let coverage = |distance: f32| match stroke.style {
+        LineStyle::Aliased => (distance >= 0.0) as u8 as f32,
+        LineStyle::Smooth => (distance + 0.5).clamp(0.0, 1.0),
+    };
+
+    // Only pixels within this distance of the line's axis can be touched.
+    let reach = half_width + 1.0;
+    let min_x = (p0.x.min(p1.x) - reach).floor().max(0.0) as i32;
+    let max_x = (p0.x.max(p1.x) + reach)
+        .ceil()
+        .min(WINDOW_WIDTH as f32 - 1.0) as i32;
+    let min_y = (p0.y.min(p1.y) - reach).floor().max(0.0) as i32;
+    let max_y = (p0.y.max(p1.y) + reach).ceil().min(height as f32 - 1.0) as i32;
+
+    for y in min_y..=max_y {
+        let py = y as f32 + 0.5;
+        // Narrow the row down to the columns within `reach` of the axis, where the
+        // distance from it `(px - p0.x) * dir.y - (py - p0.y) * dir.x` is small enough.
+        let (mut from, mut to) = (min_x, max_x);
+        if direction.y != 0.0 {
+            let row_term = (py - p0.y) * direction.x;
+            let a = p0.x + (row_term - reach) / direction.y;
+            let b = p0.x + (row_term + reach) / direction.y;
+            from = from.max((a.min(b) - 0.5).floor() as i32);
+            to = to.min((a.max(b) - 0.5).ceil() as i32);
+        }
+        for x in from..=to {
+            let offset = Vec2::new(x as f32 + 0.5, py) - p0;
+            let along = offset.dot(direction);
+            let across = (offset.x * direction.y - offset.y * direction.x).abs();
+            let inside = match stroke.cap {
+                LineCap::Round => {
+                    let nearest = direction * along.clamp(0.0, length);
+                    half_width - (offset - nearest).len()
+                }
+                LineCap::Butt | LineCap::Square => (half_width - across)
+                    .min(along + extension)
+                    .min(length + extension - along),
+            };
+            blend_pixel(color_buffer, x, y, color, blend, coverage(inside));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Height of the color buffers the tests draw into.
+    const HEIGHT: i32 = 12;
+
+    /// Bytes in a row of a color buffer.
+    const ROW: usize = WINDOW_WIDTH as usize * BYTES_PER_PIXEL;
+
+    /// Adds 1 to the red channel of every pixel fully covered, so it counts how many
+    /// times the pixel was drawn.
+    const COUNT: Color = Color::rgba(1, 0, 0, 255);
+
+    /// Draws into a color buffer `HEIGHT` rows tall, with a guard row above and below it
+    /// that must stay untouched.
+    ///
+    /// # Returns
+    /// The pixels drawn, in row order, with their red channel.
+    fn drawn(draw: impl FnOnce(&mut [u8])) -> Vec<(i32, i32, u8)> {
+        let mut buffer = vec![0; ROW * (HEIGHT as usize + 2)];
+        draw(&mut buffer[ROW..ROW * (HEIGHT as usize + 1)]);
+        assert!(
+            buffer[..ROW].iter().all(|&v| v == 0),
+            "drawn above the buffer"
+        );
+        assert!(
+            buffer[ROW * (HEIGHT as usize + 1)..]
+                .iter()
+                .all(|&v| v == 0),
+            "drawn below the buffer"
+        );
+        buffer[ROW..ROW * (HEIGHT as usize + 1)]
+            .chunks_exact(BYTES_PER_PIXEL)
+            .enumerate()
+            .filter(|(_, pixel)| pixel.iter().any(|&v| v != 0))
+            .map(|(i, pixel)| {
+                let i = i as i32;
+                (i % WINDOW_WIDTH as i32, i / WINDOW_WIDTH as i32, pixel[0])
+            })
+            .collect()
+    }
+
+    /// Lists every pixel of row `y`, drawn once.
+    fn row(y: i32) -> Vec<(i32, i32, u8)> {
+        (0..WINDOW_WIDTH as i32).map(|x| (x, y, 1)).collect()
+    }
+
+    /// Lists every pixel of column `x`, drawn once.
+    fn column(x: i32) -> Vec<(i32, i32, u8)> {
+        (0..HEIGHT).map(|y| (x, y, 1)).collect()
+    }
+
+    /// Lists the pixels of the diagonal from the top-left corner, drawn once.
+    fn diagonal() -> Vec<(i32, i32, u8)> {
+        (0..HEIGHT).map(|i| (i, i, 1)).collect()
+    }
+
+    /// Checks that two points are the same to within rounding.
+    fn assert_near(point: (f64, f64), expected: (f64, f64)) {
+        let error = (point.0 - expected.0)
+            .abs()
+            .max((point.1 - expected.1).abs());
+        assert!(error < 1e-6, "{:?} is not {:?}", point, expected);
+    }
+
+    #[test]
+    fn clip_segment_keeps_the_part_inside() {
+        let (min, max) = ((0.0, 0.0), (9.0, 5.0));
+        let clip = |p0, p1| clip_segment(p0, p1, min, max);
+
+        assert_eq!(clip((1.0, 2.0), (8.0, 4.0)), Some(((1.0, 2.0), (8.0, 4.0))));
+        // Along the border, and touching it from outside.
+        assert_eq!(clip((0.0, 0.0), (9.0, 0.0)), Some(((0.0, 0.0), (9.0, 0.0))));
+        assert_eq!(
+            clip((-3.0, 5.0), (12.0, 5.0)),
+            Some(((0.0, 5.0), (9.0, 5.0)))
+        );
+        assert_eq!(
+            clip((9.0, -4.0), (9.0, 1.0)),
+            Some(((9.0, 0.0), (9.0, 1.0)))
+        );
+        assert_eq!(
+            clip((-1.0, 1.0), (1.0, -1.0)),
+            Some(((0.0, 0.0), (0.0, 0.0)))
+        );
+        // Fully outside, on every side and past a corner.
+        assert_eq!(clip((-5.0, 1.0), (-0.5, 4.0)), None);
+        assert_eq!(clip((9.5, 1.0), (20.0, 4.0)), None);
+        assert_eq!(clip((1.0, 5.5), (8.0, 9.0)), None);
+        assert_eq!(clip((1.0, -1.0), (8.0, -0.1)), None);
+        assert_eq!(clip((-2.0, 1.0), (1.0, -2.0)), None);
+        // Endpoints that are not finite.
+        for bad in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            assert_eq!(clip((bad, 1.0), (8.0, 4.0)), None);
+            assert_eq!(clip((1.0, 2.0), (8.0, bad)), None);
+        }
+
+        // Endpoints far outside, which must not drown the clipped ends in rounding.
+        for far in [1e9, f32::MAX as f64, f64::MAX / 4.0] {
+            let (start, end) = clip((-far, 3.0), (far, 3.0)).unwrap();
+            assert_eq!((start, end), ((0.0, 3.0), (9.0, 3.0)));
+            let (start, end) = clip((-far, -far), (far, far)).unwrap();
+            assert_near(start, (0.0, 0.0));
+            assert_near(end, (5.0, 5.0));
+            let (start, end) = clip((4.0, far), (4.0, -far)).unwrap();
+            assert_eq!((start, end), ((4.0, 5.0), (4.0, 0.0)));
+            assert_eq!(clip((-far, -1.0), (far, -1.0)), None);
+        }
+    }
+
+    #[test]
+    fn draw_line_stays_inside_the_buffer() {
+        let line = |x0, y0, x1, y1| {
+            drawn(|buffer| draw_line(buffer, x0, y0, x1, y1, COUNT, BlendMode::Additive))
+        };
+        let (bottom, right) = (HEIGHT - 1, WINDOW_WIDTH as i32 - 1);
+
+        assert_eq!(
+            line(3, 4, 6, 4),
+            vec![(3, 4, 1), (4, 4, 1), (5, 4, 1), (6, 4, 1)]
+        );
+        for far in [1_000_000_000, i32::MAX] {
+            assert_eq!(line(-far, 3, far, 3), row(3));
+            assert_eq!(line(far, 3, -far, 3), row(3));
+            assert_eq!(line(5, -far, 5, far), column(5));
+            assert_eq!(line(-far, -far, far, far), diagonal());
+        }
+        assert_eq!(line(i32::MIN, i32::MIN, i32::MAX, i32::MAX), diagonal());
+        // Exactly on the border.
+        assert_eq!(line(-10, 0, 900, 0), row(0));
+        assert_eq!(line(-10, bottom, 900, bottom), row(bottom));
+        assert_eq!(line(0, -10, 0, 50), column(0));
+        assert_eq!(line(right, 50, right, -10), column(right));
+        assert_eq!(line(right, 0, right, 0), vec![(right, 0, 1)]);
+        // Fully off screen.
+        assert_eq!(line(-10, HEIGHT, 900, HEIGHT), vec![]);
+        assert_eq!(line(-1, -10, -1, 50), vec![]);
+        assert_eq!(line(right + 1, 0, right + 1, bottom), vec![]);
+        assert_eq!(line(-20, 5, 5, -20), vec![]);
+        assert_eq!(line(i32::MIN, -1, i32::MAX, -1), vec![]);
+    }
+
+    #[test]
+    fn draw_line_wu_stays_inside_the_buffer() {
+        // Lines through pixel centers cover whole pixels, so every pixel is exact.
+        let line = |x0, y0, x1, y1| {
+            drawn(|buffer| {
+                let (p0, p1) = (Vec2::new(x0, y0), Vec2::new(x1, y1));
+                draw_line_wu(buffer, p0, p1, COUNT, BlendMode::Additive)
+            })
+        };
+        let (bottom, right) = (HEIGHT as f32 - 0.5, WINDOW_WIDTH as f32 - 0.5);
+
+        assert_eq!(
+            line(3.0, 4.5, 6.0, 4.5),
+            vec![(3, 4, 1), (4, 4, 1), (5, 4, 1)]
+        );
+        for far in [1e9, f32::MAX] {
+            assert_eq!(line(-far, 3.5, far, 3.5), row(3));
+            assert_eq!(line(far, 3.5, -far, 3.5), row(3));
+            assert_eq!(line(5.5, -far, 5.5, far), column(5));
+            assert_eq!(line(-far, -far, far, far), diagonal());
+            assert_eq!(line(-far, -1.5, far, -1.5), vec![]);
+        }
+        // Exactly on the border.
+        assert_eq!(line(-10.0, 0.5, 900.0, 0.5), row(0));
+        assert_eq!(line(-10.0, bottom, 900.0, bottom), row(HEIGHT - 1));
+        assert_eq!(line(0.5, -10.0, 0.5, 50.0), column(0));
+        assert_eq!(
+            line(right, 50.0, right, -10.0),
+            column(WINDOW_WIDTH as i32 - 1)
+        );
+        // Fully off screen, inside the clipping margin and beyond it.
+        assert_eq!(line(-10.0, -0.5, 900.0, -0.5), vec![]);
+        assert_eq!(line(-10.0, bottom + 1.0, 900.0, bottom + 1.0), vec![]);
+        assert_eq!(line(-0.5, -10.0, -0.5, 50.0), vec![]);
+        assert_eq!(line(-10.0, -30.0, 900.0, -30.0), vec![]);
+        // Endpoints that are not finite.
+        for bad in [f32::NAN, f32::INFINITY] {
+            assert_eq!(line(bad, 3.5, 100.0, 3.5), vec![]);
+            assert_eq!(line(3.5, 3.5, 100.0, bad), vec![]);
+        }
+    }
+
+    #[test]
+    fn draw_thick_line_stays_inside_the_buffer() {
+        let line = |x0, y0, x1, y1, width| {
+            let stroke = Stroke {
+                style: LineStyle::Aliased,
+                width,
+                cap: LineCap::Butt,
+            };
+            drawn(|buffer| {
+                let (p0, p1) = (Vec2::new(x0, y0), Vec2::new(x1, y1));
+                draw_thick_line(buffer, p0, p1, COUNT, BlendMode::Additive, stroke)
+            })
+        };
+        let rows = |ys: std::ops::Range<i32>| ys.flat_map(row).collect::<Vec<_>>();
+        let columns = |xs: std::ops::Range<i32>| {
+            let mut pixels: Vec<_> = xs.flat_map(column).collect();
+            pixels.sort_by_key(|&(x, y, _)| (y, x));
+            pixels
+        };
+        let (bottom, right) = (HEIGHT as f32, WINDOW_WIDTH as f32);
+
+        assert_eq!(
+            line(3.0, 5.0, 5.0, 5.0, 2.0),
+            vec![(3, 4, 1), (4, 4, 1), (3, 5, 1), (4, 5, 1)]
+        );
+        for far in [1e9, f32::MAX] {
+            assert_eq!(line(-far, 5.5, far, 5.5, 3.0), rows(4..7));
+            assert_eq!(line(far, 5.5, -far, 5.5, 3.0), rows(4..7));
+            assert_eq!(line(6.0, -far, 6.0, far, 4.0), columns(4..8));
+            assert_eq!(line(-far, -20.0, far, -20.0, 8.0), vec![]);
+        }
+        // Centered on the border, with half the line outside the buffer.
+        assert_eq!(line(-10.0, 0.0, 900.0, 0.0, 4.0), rows(0..2));
+        assert_eq!(
+            line(-10.0, bottom, 900.0, bottom, 4.0),
+            rows(HEIGHT - 2..HEIGHT)
+        );
+        assert_eq!(line(0.0, -10.0, 0.0, 50.0, 4.0), columns(0..2));
+        assert_eq!(
+            line(right, 50.0, right, -10.0, 4.0),
+            columns(WINDOW_WIDTH as i32 - 2..WINDOW_WIDTH as i32)
+        );
+        // Fully off screen, inside the clipping margin and beyond it.
+        assert_eq!(line(-10.0, -2.0, 900.0, -2.0, 4.0), vec![]);
+        assert_eq!(line(-3.0, -10.0, -3.0, 50.0, 4.0), vec![]);
+        assert_eq!(line(-10.0, -100.0, 900.0, -100.0, 4.0), vec![]);
+        // Endpoints that are not finite.
+        for bad in [f32::NAN, f32::NEG_INFINITY] {
+            assert_eq!(line(bad, 3.5, 100.0, 3.5, 3.0), vec![]);
+            assert_eq!(line(3.5, 3.5, 100.0, bad, 3.0), vec![]);
+        }
+    }
+
+    #[test]
+    fn draw_stroke_skips_endpoints_that_are_not_finite() {
+        for style in [LineStyle::Aliased, LineStyle::Smooth] {
+            for width in [1.0, 3.0] {
+                let stroke = Stroke {
+                    style,
+                    width,
+                    cap: LineCap::Round,
+                };
+                let pixels = drawn(|buffer| {
+                    let (p0, p1) = (Vec2::new(f32::NAN, 3.5), Vec2::new(100.5, 3.5));
+                    draw_stroke(buffer, p0, p1, COUNT, BlendMode::Additive, stroke)
+                });
+                assert_eq!(pixels, vec![], "{:?} stroke {} wide", style, width);
+            }
+        }
+    }
+}
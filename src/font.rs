@@ -0,0 +1,506 @@
+// This file contains bitmap fonts used to draw text into the color buffer: a built-in
+// 8x8 ASCII font and a loader for fonts in the BDF format.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// Width of a glyph in the built-in font, in pixels.
+pub const GLYPH_WIDTH: u32 = 8;
+/// Height of a glyph in the built-in font, in pixels.
+pub const GLYPH_HEIGHT: u32 = 8;
+
+/// The first character covered by the built-in font.
+const FIRST_CHAR: char = ' ';
+
+/// An 8x8 bitmap font covering printable ASCII (`' '` to `'~'`).
+///
+/// Each glyph is eight rows from top to bottom. The least significant bit of a row
+/// is its leftmost pixel.
+#[rustfmt::skip]
+const FONT_8X8: [[u8; 8]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // U+0020 (space)
+    [0x18, 0x3C, 0x3C, 0x18, 0x18, 0x00, 0x18, 0x00], // U+0021 (!)
+    [0x36, 0x36, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // U+0022 (")
+    [0x36, 0x36, 0x7F, 0x36, 0x7F, 0x36, 0x36, 0x00], // U+0023 (#)
+    [0x0C, 0x3E, 0x03, 0x1E, 0x30, 0x1F, 0x0C, 0x00], // U+0024 ($)
+    [0x00, 0x63, 0x33, 0x18, 0x0C, 0x66, 0x63, 0x00], // U+0025 (%)
+    [0x1C, 0x36, 0x1C, 0x6E, 0x3B, 0x33, 0x6E, 0x00], // U+0026 (&)
+    [0x06, 0x06, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00], // U+0027 (')
+    [0x18, 0x0C, 0x06, 0x06, 0x06, 0x0C, 0x18, 0x00], // U+0028 (()
+    [0x06, 0x0C, 0x18, 0x18, 0x18, 0x0C, 0x06, 0x00], // U+0029 ())
+    [0x00, 0x66, 0x3C, 0xFF, 0x3C, 0x66, 0x00, 0x00], // U+002A (*)
+    [0x00, 0x0C, 0x0C, 0x3F, 0x0C, 0x0C, 0x00, 0x00], // U+002B (+)
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C, 0x06], // U+002C (,)
+    [0x00, 0x00, 0x00, 0x3F, 0x00, 0x00, 0x00, 0x00], // U+002D (-)
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C, 0x00], // U+002E (.)
+    [0x60, 0x30, 0x18, 0x0C, 0x06, 0x03, 0x01, 0x00], // U+002F (/)
+    [0x3E, 0x63, 0x73, 0x7B, 0x6F, 0x67, 0x3E, 0x00], // U+0030 (0)
+    [0x0C, 0x0E, 0x0C, 0x0C, 0x0C, 0x0C, 0x3F, 0x00], // U+0031 (1)
+    [0x1E, 0x33, 0x30, 0x1C, 0x06, 0x33, 0x3F, 0x00], // U+0032 (2)
+    [0x1E, 0x33, 0x30, 0x1C, 0x30, 0x33, 0x1E, 0x00], // U+0033 (3)
+    [0x38, 0x3C, 0x36, 0x33, 0x7F, 0x30, 0x78, 0x00], // U+0034 (4)
+    [0x3F, 0x03, 0x1F, 0x30, 0x30, 0x33, 0x1E, 0x00], // U+0035 (5)
+    [0x1C, 0x06, 0x03, 0x1F, 0x33, 0x33, 0x1E, 0x00], // U+0036 (6)
+    [0x3F, 0x33, 0x30, 0x18, 0x0C, 0x0C, 0x0C, 0x00], // U+0037 (7)
+    [0x1E, 0x33, 0x33, 0x1E, 0x33, 0x33, 0x1E, 0x00], // U+0038 (8)
+    [0x1E, 0x33, 0x33, 0x3E, 0x30, 0x18, 0x0E, 0x00], // U+0039 (9)
+    [0x00, 0x0C, 0x0C, 0x00, 0x00, 0x0C, 0x0C, 0x00], // U+003A (:)
+    [0x00, 0x0C, 0x0C, 0x00, 0x00, 0x0C, 0x0C, 0x06], // U+003B (;)
+    [0x18, 0x0C, 0x06, 0x03, 0x06, 0x0C, 0x18, 0x00], // U+003C (<)
+    [0x00, 0x00, 0x3F, 0x00, 0x00, 0x3F, 0x00, 0x00], // U+003D (=)
+    [0x06, 0x0C, 0x18, 0x30, 0x18, 0x0C, 0x06, 0x00], // U+003E (>)
+    [0x1E, 0x33, 0x30, 0x18, 0x0C, 0x00, 0x0C, 0x00], // U+003F (?)
+    [0x3E, 0x63, 0x7B, 0x7B, 0x7B, 0x03, 0x1E, 0x00], // U+0040 (@)
+    [0x0C, 0x1E, 0x33, 0x33, 0x3F, 0x33, 0x33, 0x00], // U+0041 (A)
+    [0x3F, 0x66, 0x66, 0x3E, 0x66, 0x66, 0x3F, 0x00], // U+0042 (B)
+    [0x3C, 0x66, 0x03, 0x03, 0x03, 0x66, 0x3C, 0x00], // U+0043 (C)
+    [0x1F, 0x36, 0x66, 0x66, 0x66, 0x36, 0x1F, 0x00], // U+0044 (D)
+    [0x7F, 0x46, 0x16, 0x1E, 0x16, 0x46, 0x7F, 0x00], // U+0045 (E)
+    [0x7F, 0x46, 0x16, 0x1E, 0x16, 0x06, 0x0F, 0x00], // U+0046 (F)
+    [0x3C, 0x66, 0x03, 0x03, 0x73, 0x66, 0x7C, 0x00], // U+0047 (G)
+    [0x33, 0x33, 0x33, 0x3F, 0x33, 0x33, 0x33, 0x00], // U+0048 (H)
+    [0x1E, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x1E, 0x00], // U+0049 (I)
+    [0x78, 0x30, 0x30, 0x30, 0x33, 0x33, 0x1E, 0x00], // U+004A (J)
+    [0x67, 0x66, 0x36, 0x1E, 0x36, 0x66, 0x67, 0x00], // U+004B (K)
+    [0x0F, 0x06, 0x06, 0x06, 0x46, 0x66, 0x7F, 0x00], // U+004C (L)
+    [0x63, 0x77, 0x7F, 0x7F, 0x6B, 0x63, 0x63, 0x00], // U+004D (M)
+    [0x63, 0x67, 0x6F, 0x7B, 0x73, 0x63, 0x63, 0x00], // U+004E (N)
+    [0x1C, 0x36, 0x63, 0x63, 0x63, 0x36, 0x1C, 0x00], // U+004F (O)
+    [0x3F, 0x66, 0x66, 0x3E, 0x06, 0x06, 0x0F, 0x00], // U+0050 (P)
+    [0x1E, 0x33, 0x33, 0x33, 0x3B, 0x1E, 0x38, 0x00], // U+0051 (Q)
+    [0x3F, 0x66, 0x66, 0x3E, 0x36, 0x66, 0x67, 0x00], // U+0052 (R)
+    [0x1E, 0x33, 0x07, 0x0E, 0x38, 0x33, 0x1E, 0x00], // U+0053 (S)
+    [0x3F, 0x2D, 0x0C, 0x0C, 0x0C, 0x0C, 0x1E, 0x00], // U+0054 (T)
+    [0x33, 0x33, 0x33, 0x33, 0x33, 0x33, 0x3F, 0x00], // U+0055 (U)
+    [0x33, 0x33, 0x33, 0x33, 0x33, 0x1E, 0x0C, 0x00], // U+0056 (V)
+    [0x63, 0x63, 0x63, 0x6B, 0x7F, 0x77, 0x63, 0x00], // U+0057 (W)
+    [0x63, 0x63, 0x36, 0x1C, 0x1C, 0x36, 0x63, 0x00], // U+0058 (X)
+    [0x33, 0x33, 0x33, 0x1E, 0x0C, 0x0C, 0x1E, 0x00], // U+0059 (Y)
+    [0x7F, 0x63, 0x31, 0x18, 0x4C, 0x66, 0x7F, 0x00], // U+005A (Z)
+    [0x1E, 0x06, 0x06, 0x06, 0x06, 0x06, 0x1E, 0x00], // U+005B ([)
+    [0x03, 0x06, 0x0C, 0x18, 0x30, 0x60, 0x40, 0x00], // U+005C (\)
+    [0x1E, 0x18, 0x18, 0x18, 0x18, 0x18, 0x1E, 0x00], // U+005D (])
+    [0x08, 0x1C, 0x36, 0x63, 0x00, 0x00, 0x00, 0x00], // U+005E (^)
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xFF], // U+005F (_)
+    [0x0C, 0x0C, 0x18, 0x00, 0x00, 0x00, 0x00, 0x00], // U+0060 (`)
+    [0x00, 0x00, 0x1E, 0x30, 0x3E, 0x33, 0x6E, 0x00], // U+0061 (a)
+    [0x07, 0x06, 0x06, 0x3E, 0x66, 0x66, 0x3B, 0x00], // U+0062 (b)
+    [0x00, 0x00, 0x1E, 0x33, 0x03, 0x33, 0x1E, 0x00], // U+0063 (c)
+    [0x38, 0x30, 0x30, 0x3E, 0x33, 0x33, 0x6E, 0x00], // U+0064 (d)
+    [0x00, 0x00, 0x1E, 0x33, 0x3F, 0x03, 0x1E, 0x00], // U+0065 (e)
+    [0x1C, 0x36, 0x06, 0x0F, 0x06, 0x06, 0x0F, 0x00], // U+0066 (f)
+    [0x00, 0x00, 0x6E, 0x33, 0x33, 0x3E, 0x30, 0x1F], // U+0067 (g)
+    [0x07, 0x06, 0x36, 0x6E, 0x66, 0x66, 0x67, 0x00], // U+0068 (h)
+    [0x0C, 0x00, 0x0E, 0x0C, 0x0C, 0x0C, 0x1E, 0x00], // U+0069 (i)
+    [0x30, 0x00, 0x30, 0x30, 0x30, 0x33, 0x33, 0x1E], // U+006A (j)
+    [0x07, 0x06, 0x66, 0x36, 0x1E, 0x36, 0x67, 0x00], // U+006B (k)
+    [0x0E, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x1E, 0x00], // U+006C (l)
+    [0x00, 0x00, 0x33, 0x7F, 0x7F, 0x6B, 0x63, 0x00], // U+006D (m)
+    [0x00, 0x00, 0x1F, 0x33, 0x33, 0x33, 0x33, 0x00], // U+006E (n)
+    [0x00, 0x00, 0x1E, 0x33, 0x33, 0x33, 0x1E, 0x00], // U+006F (o)
+    [0x00, 0x00, 0x3B, 0x66, 0x66, 0x3E, 0x06, 0x0F], // U+0070 (p)
+    [0x00, 0x00, 0x6E, 0x33, 0x33, 0x3E, 0x30, 0x78], // U+0071 (q)
+    [0x00, 0x00, 0x3B, 0x6E, 0x66, 0x06, 0x0F, 0x00], // U+0072 (r)
+    [0x00, 0x00, 0x3E, 0x03, 0x1E, 0x30, 0x1F, 0x00], // U+0073 (s)
+    [0x08, 0x0C, 0x3E, 0x0C, 0x0C, 0x2C, 0x18, 0x00], // U+0074 (t)
+    [0x00, 0x00, 0x33, 0x33, 0x33, 0x33, 0x6E, 0x00], // U+0075 (u)
+    [0x00, 0x00, 0x33, 0x33, 0x33, 0x1E, 0x0C, 0x00], // U+0076 (v)
+    [0x00, 0x00, 0x63, 0x6B, 0x7F, 0x7F, 0x36, 0x00], // U+0077 (w)
+    [0x00, 0x00, 0x63, 0x36, 0x1C, 0x36, 0x63, 0x00], // U+0078 (x)
+    [0x00, 0x00, 0x33, 0x33, 0x33, 0x3E, 0x30, 0x1F], // U+0079 (y)
+    [0x00, 0x00, 0x3F, 0x19, 0x0C, 0x26, 0x3F, 0x00], // U+007A (z)
+    [0x38, 0x0C, 0x0C, 0x07, 0x0C, 0x0C, 0x38, 0x00], // U+007B ({)
+    [0x18, 0x18, 0x18, 0x00, 0x18, 0x18, 0x18, 0x00], // U+007C (|)
+    [0x07, 0x0C, 0x0C, 0x38, 0x0C, 0x0C, 0x07, 0x00], // U+007D (})
+    [0x6E, 0x3B, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // U+007E (~)
+];
+
+/// A single character image in a bitmap font.
+#[derive(Debug, Clone)]
+pub struct Glyph {
+    /// Width of the bitmap in pixels.
+    pub width: u32,
+    /// Height of the bitmap in pixels.
+    pub height: u32,
+    /// Horizontal offset of the bitmap from the pen position.
+    pub x_offset: i32,
+    /// Vertical offset of the bitmap from the top of the line.
+    pub y_offset: i32,
+    /// Distance the pen moves to the right after drawing the glyph.
+    pub advance: u32,
+    /// Row-major pixel coverage, `true` where the glyph is set.
+    pub bitmap: Vec<bool>,
+}
+
+impl Glyph {
+    /// Checks whether a pixel of the glyph's bitmap is set.
+    ///
+    /// # Arguments
+    /// - `x`: The column inside the bitmap.
+    /// - `y`: The row inside the bitmap.
+    ///
+    /// # Returns
+    /// `true` if the pixel is set.
+    pub fn is_set(&self, x: u32, y: u32) -> bool {
+        self.bitmap[(y * self.width + x) as usize]
+    }
+}
+
+/// A bitmap font mapping characters to glyphs.
+#[derive(Debug, Clone)]
+pub struct Font {
+    /// Glyphs available in the font.
+    glyphs: HashMap<char, Glyph>,
+    /// Distance between the tops of two consecutive lines, in pixels.
+    pub line_height: u32,
+}
+
+impl Font {
+    /// Returns the built-in 8x8 ASCII font.
+    ///
+    /// # Returns
+    /// A reference to the font, which is built on first use.
+    pub fn builtin() -> &'static Font {
+        static BUILTIN: OnceLock<Font> = OnceLock::new();
+        BUILTIN.get_or_init(|| {
+            let mut glyphs = HashMap::new();
+            for (i, rows) in FONT_8X8.iter().enumerate() {
+                let mut bitmap = Vec::with_capacity((GLYPH_WIDTH * GLYPH_HEIGHT) as usize);
+                for bits in rows {
+                    for col in 0..GLYPH_WIDTH {
+                        bitmap.push(bits & (1 << col) != 0);
+                    }
+                }
+                let c = char::from(FIRST_CHAR as u8 + i as u8);
+                glyphs.insert(
+                    c,
+                    Glyph {
+                        width: GLYPH_WIDTH,
+                        height: GLYPH_HEIGHT,
+                        x_offset: 0,
+                        y_offset: 0,
+                        advance: GLYPH_WIDTH,
+                        bitmap,
+                    },
+                );
+            }
+            Font {
+                glyphs,
+                line_height: GLYPH_HEIGHT,
+            }
+        })
+    }
+
+    /// Loads a font from a file in the Glyph Bitmap Distribution Format (BDF).
+    ///
+    /// # Arguments
+    /// - `filename`: The path to the `.bdf` file to load.
+    ///
+    /// # Returns
+    /// The loaded `Font`, or a message describing why the file could not be read.
+    pub fn load_bdf(filename: &str) -> Result<Font, String> {
+        let contents = std::fs::read_to_string(filename).map_err(|e| e.to_string())?;
+        Font::parse_bdf(&contents)
+    }
+
+    /// Parses the contents of a BDF font.
+    ///
+    /// Only the parts needed to draw text are read: the font ascent and descent, and the
+    /// encoding, advance, bounding box and bitmap of every character.
+    ///
+    /// # Arguments
+    /// - `contents`: The text of the BDF file.
+    ///
+    /// # Returns
+    /// The parsed `Font`, or a message naming the offending line.
+    pub fn parse_bdf(contents: &str) -> Result<Font, String> {
+        let mut glyphs = HashMap::new();
+        let mut ascent: Option<i32> = None;
+        let mut descent: Option<i32> = None;
+        let mut bounding_box: Option<[i32; 4]> = None;
+
+        // State of the character currently being read.
+        let mut encoding: Option<i64> = None;
+        let mut advance: Option<i32> = None;
+        let mut glyph_box: Option<[i32; 4]> = None;
+        let mut bitmap_rows: Option<Vec<(usize, &str)>> = None;
+
+        for (number, line) in contents.lines().enumerate() {
+            let line_error =
+                |number: usize, message: &str| format!("line {}: {}", number + 1, message);
+            let error = |message: &str| line_error(number, message);
+            let mut words = line.split_whitespace();
+            let keyword = match words.next() {
+                Some(keyword) => keyword,
+                None => continue,
+            };
+
+            // Inside a BITMAP section every line is a row of hex digits.
+            if let Some(rows) = bitmap_rows.as_mut() {
+                if keyword != "ENDCHAR" {
+                    rows.push((number, keyword));
+                    continue;
+                }
+            }
+
+            let numbers = |words: std::str::SplitWhitespace| -> Result<Vec<i32>, String> {
+                words
+                    .map(|word| {
+                        word.parse()
+                            .map_err(|_| error(&format!("invalid number `{}`", word)))
+                    })
+                    .collect()
+            };
+
+            match keyword {
+                "FONTBOUNDINGBOX" => {
+                    bounding_box =
+                        Some(four(&numbers(words)?).ok_or_else(|| error("expected 4 values"))?)
+                }
+                "FONT_ASCENT" => ascent = numbers(words)?.first().copied(),
+                "FONT_DESCENT" => descent = numbers(words)?.first().copied(),
+                "STARTCHAR" => {
+                    encoding = None;
+                    advance = None;
+                    glyph_box = None;
+                }
+                "ENCODING" => {
+                    let value = words.next().ok_or_else(|| error("missing encoding"))?;
+                    encoding = Some(value.parse().map_err(|_| error("invalid encoding"))?);
+                }
+                "DWIDTH" => advance = numbers(words)?.first().copied(),
+                "BBX" => {
+                    glyph_box =
+                        Some(four(&numbers(words)?).ok_or_else(|| error("expected 4 values"))?)
+                }
+                "BITMAP" => bitmap_rows = Some(Vec::new()),
+                "ENDCHAR" => {
+                    let rows = bitmap_rows
+                        .take()
+                        .ok_or_else(|| error("ENDCHAR without BITMAP"))?;
+                    let [width, height, x_offset, y_offset] = glyph_box
+                        .or(bounding_box)
+                        .ok_or_else(|| error("character has no bounding box"))?;
+                    if width < 0 || height < 0 || rows.len() != height as usize {
+                        return Err(error("bitmap does not match the bounding box"));
+                    }
+
+                    // Every row holds whole bytes of hex digits, with the leftmost pixel
+                    // in the most significant bit.
+                    let mut bitmap = Vec::new();
+                    for &(row_number, row) in &rows {
+                        let row_error = |message: &str| line_error(row_number, message);
+                        let digits = row.as_bytes();
+                        if digits.len() % 2 != 0 || !digits.iter().all(u8::is_ascii_hexdigit) {
+                            return Err(row_error(&format!("invalid bitmap row `{}`", row)));
+                        }
+                        if width as usize > digits.len() * 4 {
+                            return Err(row_error(&format!(
+                                "bitmap row `{}` is narrower than the bounding box",
+                                row
+                            )));
+                        }
+                        let bytes: Vec<u8> = digits
+                            .chunks_exact(2)
+                            .map(|pair| (hex_value(pair[0]) << 4) | hex_value(pair[1]))
+                            .collect();
+                        for x in 0..width as usize {
+                            bitmap.push(bytes[x / 8] & (0x80 >> (x % 8)) != 0);
+                        }
+                    }
+                    // BDF measures offsets upwards from the baseline; store them
+                    // downwards from the top of the line instead.
+                    let y_offset = y_offset
+                        .checked_add(height)
+                        .and_then(i32::checked_neg)
+                        .ok_or_else(|| error("bounding box offset out of range"))?;
+
+                    // Glyphs with an encoding of -1 have no standard code point.
+                    let c = encoding
+                        .and_then(|code| u32::try_from(code).ok())
+                        .and_then(char::from_u32);
+                    if let Some(c) = c {
+                        glyphs.insert(
+                            c,
+                            Glyph {
+                                width: width as u32,
+                                height: height as u32,
+                                x_offset,
+                                y_offset,
+                                advance: advance.unwrap_or(width).max(0) as u32,
+                                bitmap,
+                            },
+                        );
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let bounding_box = bounding_box.ok_or("missing FONTBOUNDINGBOX")?;
+        let ascent = ascent.unwrap_or(bounding_box[1].saturating_add(bounding_box[3]));
+        let descent = descent.unwrap_or(bounding_box[3].saturating_neg());
+        for glyph in glyphs.values_mut() {
+            glyph.y_offset = glyph.y_offset.saturating_add(ascent);
+        }
+
+        Ok(Font {
+            glyphs,
+            line_height: ascent.saturating_add(descent).max(1) as u32,
+        })
+    }
+
+    /// Looks up the glyph of a character.
+    ///
+    /// # Arguments
+    /// - `c`: The character to look up.
+    ///
+    /// # Returns
+    /// The glyph, the font's `'?'` glyph if the character is missing, or `None` if
+    /// neither exists.
+    pub fn glyph(&self, c: char) -> Option<&Glyph> {
+        self.glyphs.get(&c).or_else(|| self.glyphs.get(&'?'))
+    }
+
+    /// Measures the size of a block of text.
+    ///
+    /// # Arguments
+    /// - `text`: The text to measure. `\n` starts a new line.
+    /// - `scale`: The integer factor each font pixel is scaled by.
+    ///
+    /// # Returns
+    /// The `(width, height)` of the text in pixels, saturating at `u32::MAX`.
+    pub fn measure(&self, text: &str, scale: u32) -> (u32, u32) {
+        let mut width = 0;
+        let mut lines = 0;
+        for line in text.split('\n') {
+            let line_width = line
+                .chars()
+                .filter_map(|c| self.glyph(c))
+                .fold(0u32, |sum, glyph| sum.saturating_add(glyph.advance));
+            width = width.max(line_width);
+            lines += 1;
+        }
+        let height = self.line_height.saturating_mul(lines);
+        (width.saturating_mul(scale), height.saturating_mul(scale))
+    }
+}
+
+/// Reads the value of an ASCII hex digit.
+fn hex_value(digit: u8) -> u8 {
+    (digit as char).to_digit(16).unwrap_or(0) as u8
+}
+
+/// Converts a list of numbers into a fixed array of four.
+fn four(values: &[i32]) -> Option<[i32; 4]> {
+    values.try_into().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A font with a 3x2 `A` and a 4x3 `?` that drops below the baseline.
+    const SAMPLE: &str = "STARTFONT 2.1
+FONT -sample
+FONTBOUNDINGBOX 4 3 0 -1
+FONT_ASCENT 3
+FONT_DESCENT 1
+CHARS 2
+STARTCHAR A
+ENCODING 65
+DWIDTH 4 0
+BBX 3 2 0 0
+BITMAP
+A0
+E0
+ENDCHAR
+STARTCHAR question
+ENCODING 63
+DWIDTH 5 0
+BBX 4 3 1 -1
+BITMAP
+f0
+10
+60
+ENDCHAR
+ENDFONT
+";
+
+    /// Replaces the bitmap of the sample's `A` with other rows.
+    fn with_rows(rows: &str) -> Result<Font, String> {
+        Font::parse_bdf(&SAMPLE.replace("A0\nE0\n", rows))
+    }
+
+    /// Draws a glyph's bitmap as rows of `#` and `.`.
+    fn pixels(glyph: &Glyph) -> Vec<String> {
+        (0..glyph.height)
+            .map(|y| {
+                (0..glyph.width)
+                    .map(|x| if glyph.is_set(x, y) { '#' } else { '.' })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parse_bdf_reads_glyphs() {
+        let font = Font::parse_bdf(SAMPLE).unwrap();
+        assert_eq!(font.line_height, 4);
+
+        let a = font.glyph('A').unwrap();
+        assert_eq!((a.width, a.height, a.advance), (3, 2, 4));
+        // The bitmap sits on the baseline, 3 pixels below the top of the line.
+        assert_eq!((a.x_offset, a.y_offset), (0, 1));
+        assert_eq!(pixels(a), ["#.#", "###"]);
+
+        let question = font.glyph('?').unwrap();
+        assert_eq!((question.x_offset, question.y_offset), (1, 1));
+        assert_eq!(pixels(question), ["####", "...#", ".##."]);
+        // Missing characters fall back to `?`.
+        assert_eq!(pixels(font.glyph('z').unwrap()), pixels(question));
+    }
+
+    #[test]
+    fn parse_bdf_rejects_invalid_rows() {
+        let cases = [
+            ("A0\nEG\n", "line 13: invalid bitmap row `EG`"),
+            ("A0\naé\n", "line 13: invalid bitmap row `aé`"),
+            ("A0\nE\n", "line 13: invalid bitmap row `E`"),
+            ("A0\n", "line 13: bitmap does not match the bounding box"),
+        ];
+        for (rows, message) in cases {
+            assert_eq!(with_rows(rows).unwrap_err(), message);
+        }
+
+        // A bounding box wider than its rows is rejected before anything is allocated.
+        let wide = SAMPLE.replace("BBX 3 2 0 0", "BBX 2000000000 2 0 0");
+        assert_eq!(
+            Font::parse_bdf(&wide).unwrap_err(),
+            "line 12: bitmap row `A0` is narrower than the bounding box"
+        );
+        let high = SAMPLE.replace("BBX 3 2 0 0", "BBX 3 2 0 2147483647");
+        assert_eq!(
+            Font::parse_bdf(&high).unwrap_err(),
+            "line 14: bounding box offset out of range"
+        );
+        assert_eq!(
+            Font::parse_bdf("STARTFONT 2.1\n").unwrap_err(),
+            "missing FONTBOUNDINGBOX"
+        );
+    }
+
+    #[test]
+    fn measure_sums_advances_per_line() {
+        let builtin = Font::builtin();
+        assert_eq!(builtin.measure("", 1), (0, 8));
+        assert_eq!(builtin.measure("abc", 1), (24, 8));
+        assert_eq!(builtin.measure("abc\nab\n", 2), (48, 48));
+
+        let font = Font::parse_bdf(SAMPLE).unwrap();
+        assert_eq!(font.measure("AA?", 1), (13, 4));
+        // `z` is drawn as `?`, so it takes its advance.
+        assert_eq!(font.measure("A\nzz", 3), (30, 24));
+        assert_eq!(font.measure("A", u32::MAX), (u32::MAX, u32::MAX));
+    }
+}
@@ -0,0 +1,100 @@
+// This file contains the view frustum, the region of camera space the projection can
+// show, used to skip whole objects that cannot appear on screen.
+//
+// The frustum is bounded by the near plane and four side planes through the camera.
+// There is no far plane, as nothing is clipped by distance.
+
+use crate::bounds::{Aabb, BoundingSphere};
+use crate::vector::Vec3;
+
+/// A plane facing into the frustum, holding the points where `normal · p + offset = 0`.
+#[derive(Debug, Copy, Clone)]
+struct Plane {
+    normal: Vec3,
+    offset: f32,
+}
+
+impl Plane {
+    /// Calculates how far a point is in front of the plane; negative values are behind.
+    fn distance(&self, point: Vec3) -> f32 {
+        self.normal.dot(point) + self.offset
+    }
+}
+
+/// The visible region of camera (view) space, where the camera sits at the origin looking
+/// down +Z.
+#[derive(Debug, Copy, Clone)]
+pub struct Frustum {
+    planes: [Plane; 5],
+}
+
+impl Frustum {
+    /// Creates the frustum of a perspective projection.
+    ///
+    /// # Arguments
+    /// - `fov_factor`: The projection's field of view factor, in pixels per unit at a
+    ///   depth of 1.
+    /// - `width`: The width of the screen in pixels.
+    /// - `height`: The height of the screen in pixels.
+    /// - `near`: The distance of the near plane.
+    ///
+    /// # Returns
+    /// A `Frustum` in view space.
+    pub fn new(fov_factor: f32, width: f32, height: f32, near: f32) -> Frustum {
+        // A point is on screen horizontally while |x| <= z * half_width / fov_factor,
+        // and likewise vertically.
+        let side = |x: f32, y: f32, slope: f32| Plane {
+            normal: Vec3::new(x, y, slope).normalize(),
+            offset: 0.0,
+        };
+        let (slope_x, slope_y) = (width / 2.0 / fov_factor, height / 2.0 / fov_factor);
+        Frustum {
+            planes: [
+                Plane {
+                    normal: Vec3::new(0.0, 0.0, 1.0),
+                    offset: -near,
+                },
+                side(1.0, 0.0, slope_x),
+                side(-1.0, 0.0, slope_x),
+                side(0.0, 1.0, slope_y),
+                side(0.0, -1.0, slope_y),
+            ],
+        }
+    }
+
+    /// Checks whether a sphere may be visible.
+    ///
+    /// # Arguments
+    /// - `sphere`: The sphere, in view space.
+    ///
+    /// # Returns
+    /// `false` if the sphere lies entirely outside the frustum.
+    pub fn intersects_sphere(&self, sphere: &BoundingSphere) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.distance(sphere.center) >= -sphere.radius)
+    }
+
+    /// Checks whether a box may be visible.
+    ///
+    /// The box is rejected only when it lies entirely behind one of the planes, so a few
+    /// boxes near the frustum's corners are kept even though they are outside.
+    ///
+    /// # Arguments
+    /// - `aabb`: The box, in view space.
+    ///
+    /// # Returns
+    /// `false` if the box lies entirely outside the frustum.
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        self.planes.iter().all(|plane| {
+            // The corner furthest along the plane's normal is the last to leave it.
+            let pick = |normal: f32, min: f32, max: f32| if normal >= 0.0 { max } else { min };
+            let corner = Vec3::new(
+                pick(plane.normal.x, aabb.min.x, aabb.max.x),
+                pick(plane.normal.y, aabb.min.y, aabb.max.y),
+                pick(plane.normal.z, aabb.min.z, aabb.max.z),
+            );
+            plane.distance(corner) >= 0.0
+        })
+    }
+}
@@ -0,0 +1,984 @@
+// This file contains the importer for glTF 2.0 files, in both their `.gltf` (JSON with
+// external or embedded buffers) and `.glb` (single binary container) forms.
+//
+// A glTF file describes a whole scene, so it is imported into a `Scene` rather than a
+// single `Mesh`. Every primitive of a glTF mesh becomes a `Mesh` of its own, since the
+// renderer assigns one material per node. Only local files are read.
+
+use std::path::{Path, PathBuf};
+
+use crate::color;
+use crate::json::Json;
+use crate::material::{Material, MaterialId};
+use crate::mesh::Mesh;
+use crate::scene::{MeshId, Node, NodeId, Scene, Transform};
+use crate::triangle::Face;
+use crate::vector::{Vec2, Vec3};
+
+/// Magic number at the start of a GLB file, "glTF" in ASCII.
+const GLB_MAGIC: u32 = 0x4654_6C67;
+/// Chunk type of the JSON chunk of a GLB file, "JSON" in ASCII.
+const GLB_CHUNK_JSON: u32 = 0x4E4F_534A;
+/// Chunk type of the binary buffer chunk of a GLB file, "BIN\0" in ASCII.
+const GLB_CHUNK_BIN: u32 = 0x004E_4942;
+
+/// Primitive mode for separate triangles, the default.
+const MODE_TRIANGLES: usize = 4;
+/// Primitive mode for a triangle strip.
+const MODE_TRIANGLE_STRIP: usize = 5;
+/// Primitive mode for a triangle fan.
+const MODE_TRIANGLE_FAN: usize = 6;
+
+/// Most elements an accessor without a buffer view may hold. Such accessors are all
+/// zeros, so nothing in the file bounds their size.
+const MAX_ZERO_ELEMENTS: usize = 1 << 24;
+
+/// The mesh and material of each triangle primitive of a glTF mesh.
+type Primitives = Vec<(MeshId, Option<MaterialId>)>;
+
+/// A scene imported from a glTF file.
+pub struct GltfImport {
+    /// The meshes, materials and node hierarchy of the file's default scene.
+    pub scene: Scene,
+    /// The external buffer and image files the glTF file references.
+    pub files: Vec<PathBuf>,
+}
+
+/// Loads a `.gltf` or `.glb` file, along with any external buffers it references.
+///
+/// # Arguments
+/// - `filename`: The path to the file to load.
+///
+/// # Returns
+/// The imported scene.
+///
+/// # Errors
+/// Returns a message if a file cannot be read or contains invalid data.
+pub fn load(filename: &str) -> Result<GltfImport, String> {
+    let bytes = std::fs::read(filename).map_err(|e| format!("{}: {}", filename, e))?;
+    let directory = Path::new(filename).parent().unwrap_or(Path::new(""));
+    parse(&bytes, directory).map_err(|e| format!("{}: {}", filename, e))
+}
+
+/// Parses the contents of a `.gltf` or `.glb` file.
+///
+/// # Arguments
+/// - `bytes`: The contents of the file.
+/// - `directory`: The directory external files are resolved against.
+///
+/// # Returns
+/// The imported scene.
+pub fn parse(bytes: &[u8], directory: &Path) -> Result<GltfImport, String> {
+    let (text, binary_chunk) = if read_u32(bytes, 0) == Some(GLB_MAGIC) {
+        read_glb(bytes)?
+    } else {
+        let text = std::str::from_utf8(bytes).map_err(|e| format!("invalid text: {}", e))?;
+        (text, None)
+    };
+    let document = Json::parse(text).map_err(|e| format!("invalid JSON at {}", e))?;
+
+    let version = document
+        .get("asset")
+        .and_then(|asset| asset.get("version"))
+        .and_then(Json::as_str)
+        .ok_or("missing `asset.version`")?;
+    if !version.starts_with("2.") {
+        return Err(format!("unsupported glTF version {}", version));
+    }
+
+    let mut importer = Importer {
+        document: &document,
+        directory,
+        buffers: Vec::new(),
+        files: Vec::new(),
+    };
+    importer.load_buffers(binary_chunk)?;
+
+    let mut scene = Scene::new();
+    scene.materials = importer.materials();
+    let meshes = importer.meshes(&mut scene)?;
+    importer.nodes(&mut scene, &meshes)?;
+
+    Ok(GltfImport {
+        scene,
+        files: importer.files,
+    })
+}
+
+/// Reads a little-endian `u32` at a byte offset.
+fn read_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    let b = bytes.get(offset..offset + 4)?;
+    Some(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+/// Splits a GLB container into its JSON text and optional binary chunk.
+fn read_glb(bytes: &[u8]) -> Result<(&str, Option<&[u8]>), String> {
+    let version = read_u32(bytes, 4).ok_or("truncated GLB header")?;
+    if version != 2 {
+        return Err(format!("unsupported GLB version {}", version));
+    }
+    let length = read_u32(bytes, 8).ok_or("truncated GLB header")? as usize;
+    let bytes = bytes
+        .get(..length)
+        .ok_or("GLB header declares more bytes than the file has")?;
+
+    let mut json = None;
+    let mut binary = None;
+    let mut offset = 12;
+    while offset < bytes.len() {
+        let chunk_length = read_u32(bytes, offset).ok_or("truncated GLB chunk")? as usize;
+        let chunk_type = read_u32(bytes, offset + 4).ok_or("truncated GLB chunk")?;
+        let data = bytes
+            .get(offset + 8..offset + 8 + chunk_length)
+            .ok_or("truncated GLB chunk")?;
+        match chunk_type {
+            GLB_CHUNK_JSON if json.is_none() => json = Some(data),
+            GLB_CHUNK_BIN if binary.is_none() => binary = Some(data),
+            _ => {} // Unknown chunks must be ignored.
+        }
+        offset += 8 + chunk_length;
+    }
+
+    let json = json.ok_or("GLB file has no JSON chunk")?;
+    let text = std::str::from_utf8(json).map_err(|e| format!("invalid JSON text: {}", e))?;
+    Ok((text, binary))
+}
+
+/// Decodes standard base64, ignoring padding.
+fn decode_base64(text: &str) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::with_capacity(text.len() / 4 * 3);
+    let mut bits: u32 = 0;
+    let mut bit_count = 0;
+    for c in text.bytes().take_while(|&c| c != b'=') {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return Err(format!("invalid base64 character `{}`", c as char)),
+        };
+        bits = (bits << 6) | value as u32;
+        bit_count += 6;
+        if bit_count >= 8 {
+            bit_count -= 8;
+            bytes.push((bits >> bit_count) as u8);
+        }
+    }
+    Ok(bytes)
+}
+
+/// Decodes the `%XX` escapes of a relative URI into a path.
+fn decode_uri(uri: &str) -> String {
+    let bytes = uri.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escape = bytes
+            .get(i + 1..i + 3)
+            .filter(|_| bytes[i] == b'%')
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escape {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Converts a rotation matrix into angles around X, Y and Z, applied in that order as
+/// `Transform` expects.
+fn rotation_from_matrix(r: [[f32; 3]; 3]) -> Vec3 {
+    // R = Rz(z) * Ry(y) * Rx(x), so r[2][0] = -sin(y).
+    let y = (-r[2][0]).clamp(-1.0, 1.0).asin();
+    if r[2][0].abs() < 0.9999 {
+        Vec3::new(r[2][1].atan2(r[2][2]), y, r[1][0].atan2(r[0][0]))
+    } else {
+        // Gimbal lock: only the sum of the X and Z angles matters, so keep it in Z.
+        Vec3::new(0.0, y, (-r[0][1]).atan2(r[1][1]))
+    }
+}
+
+/// Converts a unit quaternion `[x, y, z, w]` into angles around X, Y and Z.
+fn rotation_from_quaternion([x, y, z, w]: [f32; 4]) -> Vec3 {
+    rotation_from_matrix([
+        [
+            1.0 - 2.0 * (y * y + z * z),
+            2.0 * (x * y - z * w),
+            2.0 * (x * z + y * w),
+        ],
+        [
+            2.0 * (x * y + z * w),
+            1.0 - 2.0 * (x * x + z * z),
+            2.0 * (y * z - x * w),
+        ],
+        [
+            2.0 * (x * z - y * w),
+            2.0 * (y * z + x * w),
+            1.0 - 2.0 * (x * x + y * y),
+        ],
+    ])
+}
+
+/// Splits a column-major 4x4 matrix into a translation, rotation and scale.
+/// Shear cannot be represented and is lost.
+fn transform_from_matrix(m: &[f32]) -> Transform {
+    let column = |i: usize| Vec3::new(m[i * 4], m[i * 4 + 1], m[i * 4 + 2]);
+    let (x_axis, y_axis, z_axis) = (column(0), column(1), column(2));
+
+    let mut scale = Vec3::new(x_axis.len(), y_axis.len(), z_axis.len());
+    // A mirrored basis has a negative determinant; flip one axis to keep a rotation.
+    if x_axis.cross(y_axis).dot(z_axis) < 0.0 {
+        scale.x = -scale.x;
+    }
+    let axes = [
+        x_axis / if scale.x != 0.0 { scale.x } else { 1.0 },
+        y_axis / if scale.y != 0.0 { scale.y } else { 1.0 },
+        z_axis / if scale.z != 0.0 { scale.z } else { 1.0 },
+    ];
+
+    Transform {
+        translation: column(3),
+        rotation: rotation_from_matrix([
+            [axes[0].x, axes[1].x, axes[2].x],
+            [axes[0].y, axes[1].y, axes[2].y],
+            [axes[0].z, axes[1].z, axes[2].z],
+        ]),
+        scale,
+    }
+}
+
+/// Reads an array of numbers from a JSON value.
+fn numbers(value: Option<&Json>) -> Option<Vec<f32>> {
+    value?
+        .elements()
+        .iter()
+        .map(|n| n.as_f64().map(|n| n as f32))
+        .collect()
+}
+
+/// State shared while importing one glTF document.
+struct Importer<'a> {
+    document: &'a Json,
+    directory: &'a Path,
+    /// The contents of every buffer, indexed like the document's `buffers`.
+    buffers: Vec<Vec<u8>>,
+    /// External files read or referenced so far.
+    files: Vec<PathBuf>,
+}
+
+impl<'a> Importer<'a> {
+    /// Returns the elements of a top-level array of the document, such as `meshes`.
+    fn array(&self, name: &str) -> &'a [Json] {
+        self.document.get(name).map_or(&[], Json::elements)
+    }
+
+    /// Resolves a relative URI against the document's directory, refusing anything
+    /// that is not a local file.
+    fn local_path(&self, uri: &str) -> Result<PathBuf, String> {
+        if uri.contains("://") {
+            return Err(format!("`{}` is not a local file", uri));
+        }
+        Ok(self.directory.join(decode_uri(uri)))
+    }
+
+    /// Reads every buffer, from embedded base64 data, external files or the GLB binary
+    /// chunk.
+    fn load_buffers(&mut self, binary_chunk: Option<&[u8]>) -> Result<(), String> {
+        for (i, buffer) in self.array("buffers").iter().enumerate() {
+            let length = buffer
+                .get("byteLength")
+                .and_then(Json::as_usize)
+                .ok_or_else(|| format!("buffer {} has no `byteLength`", i))?;
+
+            let data = match buffer.get("uri").and_then(Json::as_str) {
+                Some(uri) if uri.starts_with("data:") => {
+                    let (_, encoded) = uri.split_once(";base64,").ok_or_else(|| {
+                        format!("buffer {}: only base64 data URIs are supported", i)
+                    })?;
+                    decode_base64(encoded).map_err(|e| format!("buffer {}: {}", i, e))?
+                }
+                Some(uri) => {
+                    let path = self.local_path(uri)?;
+                    let data = std::fs::read(&path)
+                        .map_err(|e| format!("buffer {}: {}: {}", i, path.display(), e))?;
+                    self.files.push(path);
+                    data
+                }
+                None if i == 0 => binary_chunk
+                    .ok_or("buffer 0 has no `uri` and there is no GLB binary chunk")?
+                    .to_vec(),
+                None => return Err(format!("buffer {} has no `uri`", i)),
+            };
+
+            if data.len() < length {
+                return Err(format!(
+                    "buffer {} holds {} bytes but declares {}",
+                    i,
+                    data.len(),
+                    length
+                ));
+            }
+            self.buffers.push(data);
+        }
+        Ok(())
+    }
+
+    /// Reads the elements of an accessor as numbers.
+    ///
+    /// # Returns
+    /// The values of every element, flattened, and the number of components per
+    /// element. Normalized integers are scaled into `0.0..=1.0` or `-1.0..=1.0`.
+    fn accessor(&self, index: usize) -> Result<(Vec<f64>, usize), String> {
+        let error = |message: &str| format!("accessor {}: {}", index, message);
+        let accessor = self
+            .array("accessors")
+            .get(index)
+            .ok_or_else(|| error("does not exist"))?;
+        if accessor.get("sparse").is_some() {
+            return Err(error("sparse accessors are not supported"));
+        }
+
+        let count = accessor
+            .get("count")
+            .and_then(Json::as_usize)
+            .ok_or_else(|| error("missing `count`"))?;
+        let components = match accessor.get("type").and_then(Json::as_str) {
+            Some("SCALAR") => 1,
+            Some("VEC2") => 2,
+            Some("VEC3") => 3,
+            Some("VEC4") => 4,
+            Some("MAT2") => 4,
+            Some("MAT3") => 9,
+            Some("MAT4") => 16,
+            _ => return Err(error("missing or unknown `type`")),
+        };
+        let component_type = accessor.get("componentType").and_then(Json::as_usize);
+        let component_size = match component_type {
+            Some(5120 | 5121) => 1,
+            Some(5122 | 5123) => 2,
+            Some(5125 | 5126) => 4,
+            _ => return Err(error("missing or unknown `componentType`")),
+        };
+        let normalized = accessor.get("normalized") == Some(&Json::Bool(true));
+
+        // Accessors without a buffer view are all zeros.
+        let view_index = match accessor.get("bufferView").and_then(Json::as_usize) {
+            Some(view) => view,
+            None if count <= MAX_ZERO_ELEMENTS => {
+                return Ok((vec![0.0; count * components], components))
+            }
+            None => return Err(error("too many elements without a buffer view")),
+        };
+        let view = self
+            .array("bufferViews")
+            .get(view_index)
+            .ok_or_else(|| error("refers to a missing buffer view"))?;
+        let buffer = view
+            .get("buffer")
+            .and_then(Json::as_usize)
+            .and_then(|buffer| self.buffers.get(buffer))
+            .ok_or_else(|| error("its buffer view refers to a missing buffer"))?;
+        let view_offset = view.get("byteOffset").and_then(Json::as_usize).unwrap_or(0);
+        let view_length = view
+            .get("byteLength")
+            .and_then(Json::as_usize)
+            .ok_or_else(|| error("its buffer view has no `byteLength`"))?;
+        let data = view_offset
+            .checked_add(view_length)
+            .and_then(|view_end| buffer.get(view_offset..view_end))
+            .ok_or_else(|| error("its buffer view lies outside its buffer"))?;
+
+        let element_size = components * component_size;
+        let stride = view
+            .get("byteStride")
+            .and_then(Json::as_usize)
+            .unwrap_or(element_size);
+        if stride < element_size {
+            return Err(error(
+                "its buffer view's `byteStride` is smaller than an element",
+            ));
+        }
+        let offset = accessor
+            .get("byteOffset")
+            .and_then(Json::as_usize)
+            .unwrap_or(0);
+        // Every element takes at least one byte, so a count within bounds is also at most
+        // the view's length, and the loop below cannot overflow.
+        let end = match count.checked_sub(1) {
+            Some(last) => last
+                .checked_mul(stride)
+                .and_then(|start| start.checked_add(offset))
+                .and_then(|start| start.checked_add(element_size)),
+            None => Some(0),
+        };
+        if end.is_none_or(|end| end > data.len()) {
+            return Err(error("reads past the end of its buffer view"));
+        }
+
+        let mut values = Vec::with_capacity(count * components);
+        for i in 0..count {
+            for c in 0..components {
+                let at = offset + i * stride + c * component_size;
+                let b = &data[at..at + component_size];
+                let (value, max) = match component_type {
+                    Some(5120) => (b[0] as i8 as f64, i8::MAX as f64),
+                    Some(5121) => (b[0] as f64, u8::MAX as f64),
+                    Some(5122) => (i16::from_le_bytes([b[0], b[1]]) as f64, i16::MAX as f64),
+                    Some(5123) => (u16::from_le_bytes([b[0], b[1]]) as f64, u16::MAX as f64),
+                    Some(5125) => (
+                        u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+                        u32::MAX as f64,
+                    ),
+                    _ => (f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64, 1.0),
+                };
+                values.push(if normalized {
+                    (value / max).max(-1.0)
+                } else {
+                    value
+                });
+            }
+        }
+        Ok((values, components))
+    }
+
+    /// Reads an attribute accessor that must have at least `components` components per
+    /// element, and returns that many of each.
+    fn attribute(&self, index: usize, components: usize) -> Result<Vec<Vec<f32>>, String> {
+        let (values, actual) = self.accessor(index)?;
+        if actual < components {
+            return Err(format!(
+                "accessor {} has {} components per element, expected {}",
+                index, actual, components
+            ));
+        }
+        Ok(values
+            .chunks_exact(actual)
+            .map(|element| element[..components].iter().map(|&v| v as f32).collect())
+            .collect())
+    }
+
+    /// Reads every material of the document.
+    fn materials(&mut self) -> Vec<Material> {
+        let mut materials = Vec::new();
+        for (i, source) in self.array("materials").iter().enumerate() {
+            let name = source.get("name").and_then(Json::as_str);
+            let mut material = Material::new(name.unwrap_or(&format!("material{}", i)));
+
+            let pbr = source.get("pbrMetallicRoughness");
+            if let Some([r, g, b, a]) = numbers(pbr.and_then(|pbr| pbr.get("baseColorFactor")))
+                .as_deref()
+                .and_then(|factor| <[f32; 4]>::try_from(factor).ok())
+            {
+                // glTF colors are in linear light; materials keep theirs in sRGB.
+                material.diffuse = color::linear_to_srgb_rgb(Vec3::new(r, g, b));
+                // Alpha only matters outside the default opaque mode.
+                if source
+                    .get("alphaMode")
+                    .and_then(Json::as_str)
+                    .unwrap_or("OPAQUE")
+                    != "OPAQUE"
+                {
+                    material.opacity = a;
+                }
+            }
+
+            // Only textures stored as separate image files can be referenced by path, and
+            // only PPM images can be decoded; materials with others are drawn untextured.
+            let uri = pbr
+                .and_then(|pbr| pbr.get("baseColorTexture"))
+                .and_then(|texture| texture.get("index"))
+                .and_then(Json::as_usize)
+                .and_then(|texture| self.array("textures").get(texture))
+                .and_then(|texture| texture.get("source"))
+                .and_then(Json::as_usize)
+                .and_then(|image| self.array("images").get(image))
+                .and_then(|image| image.get("uri"))
+                .and_then(Json::as_str)
+                .filter(|uri| !uri.starts_with("data:"))
+                .filter(|uri| uri.to_lowercase().ends_with(".ppm"));
+            if let Some(Ok(path)) = uri.map(|uri| self.local_path(uri)) {
+                material.diffuse_texture = Some(path.to_string_lossy().into_owned());
+                self.files.push(path);
+            }
+            materials.push(material);
+        }
+        materials
+    }
+
+    /// Converts every mesh of the document and adds the results to the scene.
+    ///
+    /// # Returns
+    /// For each glTF mesh, the mesh and material of each of its triangle primitives.
+    fn meshes(&self, scene: &mut Scene) -> Result<Vec<Primitives>, String> {
+        let mut meshes = Vec::new();
+        for (i, source) in self.array("meshes").iter().enumerate() {
+            let mut primitives = Vec::new();
+            for (j, primitive) in source
+                .get("primitives")
+                .map_or(&[][..], Json::elements)
+                .iter()
+                .enumerate()
+            {
+                let mesh = self
+                    .primitive(primitive)
+                    .map_err(|e| format!("mesh {} primitive {}: {}", i, j, e))?;
+                if let Some(mesh) = mesh {
+                    let material = primitive
+                        .get("material")
+                        .and_then(Json::as_usize)
+                        .filter(|&material| material < scene.materials.len());
+                    primitives.push((scene.add_mesh(mesh), material));
+                }
+            }
+            meshes.push(primitives);
+        }
+        Ok(meshes)
+    }
+
+    /// Converts one mesh primitive.
+    ///
+    /// # Returns
+    /// The mesh, or `None` for points and lines, which the renderer cannot draw.
+    fn primitive(&self, primitive: &Json) -> Result<Option<Mesh>, String> {
+        let mode = primitive
+            .get("mode")
+            .and_then(Json::as_usize)
+            .unwrap_or(MODE_TRIANGLES);
+        if !matches!(
+            mode,
+            MODE_TRIANGLES | MODE_TRIANGLE_STRIP | MODE_TRIANGLE_FAN
+        ) {
+            return Ok(None);
+        }
+
+        let attributes = primitive.get("attributes");
+        let attribute = |name: &str| {
+            attributes
+                .and_then(|attributes| attributes.get(name))
+                .and_then(Json::as_usize)
+        };
+        let positions = self.attribute(attribute("POSITION").ok_or("missing POSITION")?, 3)?;
+        let vertices: Vec<Vec3> = positions
+            .iter()
+            .map(|p| Vec3::new(p[0], p[1], p[2]))
+            .collect();
+
+        // Faces use 1-based indices.
+        let indices: Vec<usize> = match primitive.get("indices").and_then(Json::as_usize) {
+            Some(accessor) => self
+                .accessor(accessor)?
+                .0
+                .iter()
+                .map(|&index| {
+                    (index as usize)
+                        .checked_add(1)
+                        .ok_or_else(|| format!("index {} refers to a missing vertex", index))
+                })
+                .collect::<Result<_, String>>()?,
+            None => (1..=vertices.len()).collect(),
+        };
+        if let Some(index) = indices.iter().find(|&&index| index > vertices.len()) {
+            return Err(format!("index {} refers to a missing vertex", index - 1));
+        }
+
+        let corners: Vec<[usize; 3]> = match mode {
+            MODE_TRIANGLE_STRIP => (0..indices.len().saturating_sub(2))
+                .map(|i| match i % 2 {
+                    0 => [indices[i], indices[i + 1], indices[i + 2]],
+                    _ => [indices[i + 1], indices[i], indices[i + 2]],
+                })
+                .collect(),
+            MODE_TRIANGLE_FAN => (1..indices.len().saturating_sub(1))
+                .map(|i| [indices[i], indices[i + 1], indices[0]])
+                .collect(),
+            _ => indices
+                .chunks_exact(3)
+                .map(|c| [c[0], c[1], c[2]])
+                .collect(),
+        };
+        let faces = corners
+            .iter()
+            .map(|&[a, b, c]| Face::new(a, b, c))
+            .collect();
+
+        let vertex_count = vertices.len();
+        let mut mesh = Mesh::new(vertices, faces);
+        let matching = |values: &Vec<Vec<f32>>| values.len() == vertex_count;
+        if let Some(accessor) = attribute("NORMAL") {
+            let normals = self.attribute(accessor, 3)?;
+            if matching(&normals) {
+                mesh.vertex_normals = normals
+                    .iter()
+                    .map(|n| Vec3::new(n[0], n[1], n[2]))
+                    .collect();
+            }
+        }
+        if let Some(accessor) = attribute("TEXCOORD_0") {
+            let uvs = self.attribute(accessor, 2)?;
+            if matching(&uvs) {
+                // glTF measures `v` down from the top of the image, meshes up from the
+                // bottom.
+                mesh.vertex_uvs = uvs.iter().map(|uv| Vec2::new(uv[0], 1.0 - uv[1])).collect();
+            }
+        }
+        if let Some(accessor) = attribute("COLOR_0") {
+            let colors = self.attribute(accessor, 3)?;
+            if matching(&colors) {
+                mesh.vertex_colors = colors
+                    .iter()
+                    .map(|c| color::linear_to_srgb_rgb(Vec3::new(c[0], c[1], c[2])))
+                    .collect();
+            }
+        }
+        Ok(Some(mesh))
+    }
+
+    /// Reads the local transform of a node, from its `matrix` or its `translation`,
+    /// `rotation` and `scale`.
+    fn node_transform(node: &Json) -> Transform {
+        if let Some(matrix) = numbers(node.get("matrix")).filter(|m| m.len() == 16) {
+            return transform_from_matrix(&matrix);
+        }
+
+        let mut transform = Transform::identity();
+        if let Some([x, y, z]) =
+            numbers(node.get("translation")).and_then(|t| <[f32; 3]>::try_from(t).ok())
+        {
+            transform.translation = Vec3::new(x, y, z);
+        }
+        if let Some(quaternion) =
+            numbers(node.get("rotation")).and_then(|r| <[f32; 4]>::try_from(r).ok())
+        {
+            transform.rotation = rotation_from_quaternion(quaternion);
+        }
+        if let Some([x, y, z]) =
+            numbers(node.get("scale")).and_then(|s| <[f32; 3]>::try_from(s).ok())
+        {
+            transform.scale = Vec3::new(x, y, z);
+        }
+        transform
+    }
+
+    /// Adds the node hierarchy of the document's default scene.
+    fn nodes(&self, scene: &mut Scene, meshes: &[Primitives]) -> Result<(), String> {
+        let sources = self.array("nodes");
+
+        // Use the default scene, the first scene, or every node nothing refers to.
+        let scenes = self.array("scenes");
+        let chosen = self
+            .document
+            .get("scene")
+            .and_then(Json::as_usize)
+            .or(if scenes.is_empty() { None } else { Some(0) });
+        let roots: Vec<usize> = match chosen {
+            Some(index) => scenes
+                .get(index)
+                .ok_or_else(|| format!("scene {} does not exist", index))?
+                .get("nodes")
+                .map_or(&[][..], Json::elements)
+                .iter()
+                .filter_map(Json::as_usize)
+                .collect(),
+            None => {
+                let mut referenced = vec![false; sources.len()];
+                for node in sources {
+                    for child in node.get("children").map_or(&[][..], Json::elements) {
+                        if let Some(flag) = child.as_usize().and_then(|c| referenced.get_mut(c)) {
+                            *flag = true;
+                        }
+                    }
+                }
+                (0..sources.len()).filter(|&i| !referenced[i]).collect()
+            }
+        };
+
+        // Parents are always added before their children, as `Scene::add_node` requires.
+        let mut added: Vec<Option<NodeId>> = vec![None; sources.len()];
+        let mut stack: Vec<(usize, Option<NodeId>)> =
+            roots.iter().rev().map(|&root| (root, None)).collect();
+        while let Some((index, parent)) = stack.pop() {
+            let source = sources
+                .get(index)
+                .ok_or_else(|| format!("node {} does not exist", index))?;
+            if added[index].is_some() {
+                return Err(format!("node {} has more than one parent", index));
+            }
+
+            let name = source
+                .get("name")
+                .and_then(Json::as_str)
+                .map_or_else(|| format!("node{}", index), str::to_string);
+            let mut node = Node::new(&name, None);
+            node.transform = Importer::node_transform(source);
+
+            let primitives = match source.get("mesh").and_then(Json::as_usize) {
+                Some(mesh) => meshes
+                    .get(mesh)
+                    .ok_or_else(|| format!("node {} refers to missing mesh {}", index, mesh))?
+                    .as_slice(),
+                None => &[],
+            };
+            // A single primitive is drawn by the node itself; several each get a child.
+            if let [(mesh, material)] = primitives {
+                node.mesh = Some(*mesh);
+                node.material = *material;
+            }
+            let id = scene.add_node(node, parent);
+            added[index] = Some(id);
+            if primitives.len() > 1 {
+                for (i, (mesh, material)) in primitives.iter().enumerate() {
+                    let mut child = Node::new(&format!("{}/{}", name, i), Some(*mesh));
+                    child.material = *material;
+                    scene.add_node(child, Some(id));
+                }
+            }
+
+            for child in source
+                .get("children")
+                .map_or(&[][..], Json::elements)
+                .iter()
+                .rev()
+            {
+                let child = child
+                    .as_usize()
+                    .ok_or_else(|| format!("node {} has an invalid child", index))?;
+                stack.push((child, Some(id)));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A triangle whose buffer holds three `VEC3` float positions and three `u16` indices,
+    /// embedded as base64.
+    const TRIANGLE: &str = r#"{
+  "asset": {"version": "2.0"},
+  "buffers": [{"byteLength": 44, "uri": "data:application/octet-stream;base64,AAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAgD8AAAAAAAABAAIAAAA="}],
+  "bufferViews": [
+    {"buffer": 0, "byteOffset": 0, "byteLength": 36},
+    {"buffer": 0, "byteOffset": 36, "byteLength": 6}
+  ],
+  "accessors": [
+    {"bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3"},
+    {"bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR"}
+  ],
+  "meshes": [{"primitives": [{"attributes": {"POSITION": 0}, "indices": 1}]}],
+  "nodes": [{"name": "triangle", "mesh": 0}],
+  "scenes": [{"nodes": [0]}]
+}"#;
+
+    /// The buffer `TRIANGLE` embeds.
+    fn triangle_buffer() -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for value in [0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0] {
+            bytes.extend(value.to_le_bytes());
+        }
+        for index in [0u16, 1, 2, 0] {
+            bytes.extend(index.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Packs a JSON document and a binary buffer into a GLB container.
+    fn glb(json: &str, binary: &[u8]) -> Vec<u8> {
+        let mut json = json.as_bytes().to_vec();
+        json.resize(json.len().next_multiple_of(4), b' ');
+        let length = 12 + 8 + json.len() + 8 + binary.len();
+
+        let mut bytes = Vec::new();
+        for word in [GLB_MAGIC, 2, length as u32] {
+            bytes.extend(word.to_le_bytes());
+        }
+        for (chunk_type, data) in [(GLB_CHUNK_JSON, &json[..]), (GLB_CHUNK_BIN, binary)] {
+            bytes.extend((data.len() as u32).to_le_bytes());
+            bytes.extend(chunk_type.to_le_bytes());
+            bytes.extend(data);
+        }
+        bytes
+    }
+
+    /// Checks that a scene holds the single triangle of `TRIANGLE`.
+    fn assert_triangle(scene: &Scene) {
+        assert_eq!(scene.meshes.len(), 1);
+        let mesh = &scene.meshes[0];
+        assert_eq!(
+            mesh.vertices,
+            vec![
+                Vec3::new(0.0, 0.0, 0.0),
+                Vec3::new(1.0, 0.0, 0.0),
+                Vec3::new(0.0, 1.0, 0.0),
+            ]
+        );
+        assert_eq!(mesh.faces, vec![Face::new(1, 2, 3)]);
+        assert_eq!(scene.nodes.len(), 1);
+        assert_eq!(scene.nodes[0].name, "triangle");
+        assert_eq!(scene.nodes[0].mesh, Some(0));
+        assert_eq!(scene.roots, vec![0]);
+    }
+
+    /// Parses `TRIANGLE` with one piece of text replaced, and returns the error.
+    fn error(from: &str, to: &str) -> String {
+        let text = TRIANGLE.replace(from, to);
+        assert_ne!(text, TRIANGLE, "`{}` is not in the document", from);
+        parse(text.as_bytes(), Path::new("")).err().unwrap()
+    }
+
+    #[test]
+    fn base64_decodes_the_embedded_buffer() {
+        let encoded = TRIANGLE.split_once("base64,").unwrap().1;
+        let encoded = &encoded[..encoded.find('"').unwrap()];
+        assert_eq!(decode_base64(encoded).unwrap(), triangle_buffer());
+        assert_eq!(decode_base64("TWE=").unwrap(), b"Ma");
+        assert_eq!(
+            decode_base64("TW!=").err().unwrap(),
+            "invalid base64 character `!`"
+        );
+    }
+
+    #[test]
+    fn embedded_triangle_is_imported() {
+        let import = parse(TRIANGLE.as_bytes(), Path::new("")).unwrap();
+        assert_triangle(&import.scene);
+        assert!(import.files.is_empty());
+    }
+
+    #[test]
+    fn glb_container_is_imported() {
+        let json = TRIANGLE.replace(
+            r#", "uri": "data:application/octet-stream;base64,AAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAgD8AAAAAAAABAAIAAAA=""#,
+            "",
+        );
+        let bytes = glb(&json, &triangle_buffer());
+        assert_triangle(&parse(&bytes, Path::new("")).unwrap().scene);
+
+        assert_eq!(
+            parse(&bytes[..bytes.len() - 4], Path::new(""))
+                .err()
+                .unwrap(),
+            "GLB header declares more bytes than the file has"
+        );
+        assert_eq!(
+            parse(&glb(&json, &[]), Path::new("")).err().unwrap(),
+            "buffer 0 holds 0 bytes but declares 44"
+        );
+    }
+
+    #[test]
+    fn node_hierarchy_is_imported() {
+        let primitive = r#"{"attributes": {"POSITION": 0}, "indices": 1}"#;
+        let text = TRIANGLE
+            .replace(
+                r#""meshes": [{"primitives": [{"attributes": {"POSITION": 0}, "indices": 1}]}],"#,
+                &format!(
+                    r#""meshes": [{{"primitives": [{0}]}}, {{"primitives": [{0}, {0}]}}],"#,
+                    primitive
+                ),
+            )
+            .replace(
+                r#""nodes": [{"name": "triangle", "mesh": 0}],"#,
+                r#""nodes": [
+    {"name": "root", "children": [1, 2], "translation": [1, 2, 3]},
+    {"name": "arm", "mesh": 0, "scale": [2, 2, 2]},
+    {"mesh": 1, "matrix": [1, 0, 0, 0, 0, 1, 0, 0, 0, 0, 1, 0, 4, 5, 6, 1]}
+  ],"#,
+            );
+        let scene = parse(text.as_bytes(), Path::new("")).unwrap().scene;
+
+        // Every primitive becomes a mesh of its own.
+        assert_eq!(scene.meshes.len(), 3);
+        let names: Vec<&str> = scene.nodes.iter().map(|node| node.name.as_str()).collect();
+        assert_eq!(names, ["root", "arm", "node2", "node2/0", "node2/1"]);
+        assert_eq!(scene.roots, vec![0]);
+        assert_eq!(scene.nodes[0].children, vec![1, 2]);
+        assert_eq!(
+            scene.nodes[0].transform.translation,
+            Vec3::new(1.0, 2.0, 3.0)
+        );
+        assert_eq!(scene.nodes[1].mesh, Some(0));
+        assert_eq!(scene.nodes[1].transform.scale, Vec3::new(2.0, 2.0, 2.0));
+        // A node with several primitives draws them through one child each.
+        assert_eq!(scene.nodes[2].mesh, None);
+        assert_eq!(scene.nodes[2].children, vec![3, 4]);
+        assert_eq!(
+            scene.nodes[2].transform.translation,
+            Vec3::new(4.0, 5.0, 6.0)
+        );
+        assert_eq!(scene.nodes[2].transform.scale, Vec3::new(1.0, 1.0, 1.0));
+        assert_eq!(scene.nodes[3].mesh, Some(1));
+        assert_eq!(scene.nodes[4].mesh, Some(2));
+    }
+
+    #[test]
+    fn out_of_range_accessors_are_errors() {
+        let positions = r#""count": 3, "type": "VEC3""#;
+        assert_eq!(
+            error(positions, r#""count": 4, "type": "VEC3""#),
+            "mesh 0 primitive 0: accessor 0: reads past the end of its buffer view"
+        );
+        assert_eq!(
+            error(positions, r#""count": 1152921504606846976, "type": "VEC3""#),
+            "mesh 0 primitive 0: accessor 0: reads past the end of its buffer view"
+        );
+        assert_eq!(
+            error(positions, r#""count": 1e30, "type": "VEC3""#),
+            "mesh 0 primitive 0: accessor 0: missing `count`"
+        );
+        assert_eq!(
+            error(
+                r#""byteOffset": 0, "byteLength": 36"#,
+                r#""byteOffset": 0, "byteLength": 36, "byteStride": 0"#
+            ),
+            "mesh 0 primitive 0: accessor 0: its buffer view's `byteStride` is smaller than \
+             an element"
+        );
+        assert_eq!(
+            error(
+                r#""byteOffset": 36"#,
+                r#""byteOffset": 18446744073709549568"#
+            ),
+            "mesh 0 primitive 0: accessor 1: its buffer view lies outside its buffer"
+        );
+        assert_eq!(
+            error(
+                r#""bufferView": 0, "componentType": 5126, "count": 3"#,
+                r#""componentType": 5126, "count": 1099511627776"#
+            ),
+            "mesh 0 primitive 0: accessor 0: too many elements without a buffer view"
+        );
+        assert_eq!(
+            error(r#""indices": 1"#, r#""indices": 7"#),
+            "mesh 0 primitive 0: accessor 7: does not exist"
+        );
+    }
+
+    #[test]
+    fn out_of_range_indices_are_errors() {
+        assert_eq!(
+            error(
+                r#""count": 3, "type": "VEC3""#,
+                r#""count": 2, "type": "VEC3""#
+            ),
+            "mesh 0 primitive 0: index 2 refers to a missing vertex"
+        );
+        assert_eq!(
+            error(r#""mesh": 0"#, r#""mesh": 3"#),
+            "node 0 refers to missing mesh 3"
+        );
+        assert_eq!(
+            error(r#""nodes": [0]"#, r#""nodes": [5]"#),
+            "node 5 does not exist"
+        );
+    }
+}
@@ -0,0 +1,130 @@
+// This file contains the writers for HDR image files, which save the frame in linear
+// light before it is tone mapped, so it can be graded or measured elsewhere.
+//
+// Radiance `.hdr` files store each pixel as RGBE: three 8-bit mantissas sharing an 8-bit
+// exponent. Portable float maps (`.pfm`) store three 32-bit floats per pixel, exactly,
+// with the rows from the bottom of the image up.
+
+use std::path::Path;
+
+use crate::blend::CHANNELS;
+
+/// Saves an HDR frame, choosing the format from the file's extension.
+///
+/// # Arguments
+/// - `path`: Where to write the image; it must end in `.hdr` or `.pfm`.
+/// - `hdr_buffer`: The frame, `CHANNELS` floats in linear light per pixel, row by row
+///   from the top.
+/// - `width`: The width of the frame in pixels.
+/// - `height`: The height of the frame in pixels.
+///
+/// # Errors
+/// Returns an error if the extension is not recognized or the file cannot be written.
+pub fn save(path: &str, hdr_buffer: &[f32], width: u32, height: u32) -> Result<(), String> {
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_ascii_lowercase());
+    let bytes = match extension.as_deref() {
+        Some("hdr") => to_radiance(hdr_buffer, width, height),
+        Some("pfm") => to_pfm(hdr_buffer, width, height),
+        _ => return Err(format!("{}: expected a .hdr or .pfm file", path)),
+    };
+    std::fs::write(path, bytes).map_err(|e| format!("{}: {}", path, e))
+}
+
+/// Encodes a frame as a Radiance RGBE image, with flat scanlines.
+fn to_radiance(hdr_buffer: &[f32], width: u32, height: u32) -> Vec<u8> {
+    let mut bytes = format!(
+        "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n",
+        height, width
+    )
+    .into_bytes();
+    bytes.reserve((width * height) as usize * 4);
+    for pixel in hdr_buffer.chunks_exact(CHANNELS) {
+        bytes.extend_from_slice(&to_rgbe([pixel[0], pixel[1], pixel[2]]));
+    }
+    bytes
+}
+
+/// Encodes a color as three mantissas and an exponent shared by the brightest channel,
+/// as Greg Ward's `float2rgbe` does. Negative and non-finite channels are stored as 0.
+fn to_rgbe(rgb: [f32; 3]) -> [u8; 4] {
+    let rgb = rgb.map(|c| if c.is_finite() { c.max(0.0) } else { 0.0 });
+    let brightest = rgb[0].max(rgb[1]).max(rgb[2]);
+    if brightest < 1e-32 {
+        return [0; 4];
+    }
+    // `brightest` is `fraction * 2^exponent`, with `fraction` from 0.5 to 1. Channels
+    // past the largest exponent saturate.
+    let exponent = (brightest.log2().floor() as i32 + 1).min(127);
+    let scale = 256.0 / (exponent as f32).exp2();
+    let [r, g, b] = rgb.map(|c| (c * scale).min(255.0) as u8);
+    [r, g, b, (exponent + 128) as u8]
+}
+
+/// Encodes a frame as a little-endian color portable float map.
+fn to_pfm(hdr_buffer: &[f32], width: u32, height: u32) -> Vec<u8> {
+    // A negative scale marks the floats as little-endian.
+    let mut bytes = format!("PF\n{} {}\n-1.0\n", width, height).into_bytes();
+    bytes.reserve(hdr_buffer.len() * 4);
+    let row_length = width as usize * CHANNELS;
+    for row in hdr_buffer.chunks_exact(row_length).rev() {
+        for value in row {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A frame two pixels wide and two tall, every channel distinct.
+    const FRAME: [f32; 12] = [
+        1.0, 2.0, 3.0, 4.0, 5.0, 6.0, // Top row.
+        -7.5, 0.125, 1e9, 0.0, 0.5, 0.25, // Bottom row.
+    ];
+
+    #[test]
+    fn pfm_stores_little_endian_rows_from_the_bottom() {
+        let bytes = to_pfm(&FRAME, 2, 2);
+        let header = b"PF\n2 2\n-1.0\n";
+        assert_eq!(&bytes[..header.len()], header);
+
+        let floats: Vec<f32> = bytes[header.len()..]
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect();
+        let mut expected = FRAME[6..].to_vec();
+        expected.extend_from_slice(&FRAME[..6]);
+        assert_eq!(floats, expected);
+        assert_eq!(&bytes[header.len()..header.len() + 4], &[0, 0, 0xF0, 0xC0]);
+    }
+
+    #[test]
+    fn rgbe_shares_the_brightest_exponent() {
+        // 1.0 is 0.5 * 2^1, so every channel is scaled by 2^(8 - 1).
+        assert_eq!(to_rgbe([1.0, 0.5, 0.25]), [128, 64, 32, 129]);
+        // 3.0 is 0.75 * 2^2.
+        assert_eq!(to_rgbe([3.0, 1.5, 0.1]), [192, 96, 6, 130]);
+        assert_eq!(to_rgbe([0.0, 0.0, 0.0]), [0; 4]);
+        assert_eq!(to_rgbe([-1.0, f32::NAN, f32::INFINITY]), [0; 4]);
+        assert_eq!(to_rgbe([f32::MAX, 0.0, 0.0]), [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn radiance_stores_rows_from_the_top() {
+        let bytes = to_radiance(&FRAME, 2, 2);
+        let header = b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 2 +X 2\n";
+        assert_eq!(&bytes[..header.len()], header);
+
+        let pixels: Vec<[u8; 4]> = FRAME
+            .chunks_exact(CHANNELS)
+            .map(|pixel| to_rgbe([pixel[0], pixel[1], pixel[2]]))
+            .collect();
+        assert_eq!(bytes[header.len()..], *pixels.concat());
+        assert_eq!(pixels[0], [64, 128, 192, 130]);
+    }
+}
@@ -0,0 +1,122 @@
+// This file contains the performance overlay drawn on top of each frame.
+
+use std::time::Duration;
+
+use crate::blend::BlendMode;
+use crate::color::Color;
+use crate::display;
+use crate::font::Font;
+use crate::stats::RenderStats;
+
+/// Distance of the overlay from the top-left corner of the window, in pixels.
+const HUD_MARGIN: u32 = 8;
+/// Space around the text inside the overlay background, in pixels.
+const HUD_PADDING: u32 = 4;
+/// Integer factor the overlay text is scaled by.
+const HUD_SCALE: u32 = 1;
+/// Vertical space between lines of text, in pixels.
+const LINE_SPACING: u32 = 2;
+
+/// Converts a duration into fractional milliseconds for display.
+fn millis(duration: Duration) -> f32 {
+    duration.as_secs_f32() * 1000.0
+}
+
+/// Draws the frame statistics overlay in the top-left corner of the color buffer.
+///
+/// # Arguments
+/// - `color_buffer`: A mutable reference to the color buffer.
+/// - `font`: The font to draw the text with.
+/// - `stats`: The statistics to display.
+pub fn draw_hud(color_buffer: &mut [u8], font: &Font, stats: &RenderStats) {
+    let lines = [
+        format!("{:5.1} fps {:6.2} ms", stats.fps, millis(stats.frame_time)),
+        format!("transform {:6.2} ms", millis(stats.transform_time)),
+        format!("clip      {:6.2} ms", millis(stats.clip_time)),
+        format!("raster    {:6.2} ms", millis(stats.raster_time)),
+        format!("resolve   {:6.2} ms", millis(stats.resolve_time)),
+        format!("tonemap   {:6.2} ms", millis(stats.tonemap_time)),
+        format!("present   {:6.2} ms", millis(stats.present_time)),
+        format!("objects   {:6}", stats.objects_submitted),
+        format!("offscreen {:6}", stats.objects_culled),
+        format!("submitted {:6}", stats.triangles_submitted),
+        format!("culled    {:6}", stats.triangles_culled),
+        format!("clipped   {:6}", stats.triangles_clipped),
+        format!("drawn     {:6}", stats.triangles_drawn),
+        format!("see-thru  {:6}", stats.triangles_transparent),
+        format!(
+            "aa   {:>5} {:>4}",
+            stats.antialiasing, stats.downsample_filter
+        ),
+        format!("alpha {:>10}", stats.transparency),
+        format!("tone {:>8} {:+5.1}", stats.tonemapper, stats.exposure),
+    ];
+
+    // Darken the area behind the text so it stays readable over the mesh, which still
+    // shows through faintly.
+    let width = lines
+        .iter()
+        .map(|line| font.measure(line, HUD_SCALE).0)
+        .max()
+        .unwrap_or(0);
+    let line_height = font.line_height * HUD_SCALE + LINE_SPACING;
+    display::draw_rect(
+        color_buffer,
+        HUD_MARGIN,
+        HUD_MARGIN,
+        width + HUD_PADDING * 2,
+        lines.len() as u32 * line_height + HUD_PADDING * 2 - LINE_SPACING,
+        Color::rgba(20, 20, 20, 208),
+        BlendMode::AlphaOver,
+    );
+
+    for (i, line) in lines.iter().enumerate() {
+        display::draw_text_with_font(
+            color_buffer,
+            font,
+            HUD_MARGIN + HUD_PADDING,
+            HUD_MARGIN + HUD_PADDING + i as u32 * line_height,
+            line,
+            Color::YELLOW,
+            BlendMode::Replace,
+            HUD_SCALE,
+        );
+    }
+}
+
+/// Draws an error message along the bottom edge of the color buffer.
+///
+/// # Arguments
+/// - `color_buffer`: A mutable reference to the color buffer.
+/// - `font`: The font to draw the text with.
+/// - `message`: The message to display; each line of it is drawn on its own row.
+pub fn draw_error(color_buffer: &mut [u8], font: &Font, message: &str) {
+    let lines: Vec<&str> = message.lines().collect();
+    let line_height = font.line_height * HUD_SCALE + LINE_SPACING;
+    let height = lines.len() as u32 * line_height + HUD_PADDING * 2 - LINE_SPACING;
+    let top = display::WINDOW_HEIGHT.saturating_sub(HUD_MARGIN + height);
+
+    // The background spans the whole width so long paths stay on a readable backdrop.
+    display::draw_rect(
+        color_buffer,
+        0,
+        top,
+        display::WINDOW_WIDTH,
+        height,
+        Color::rgba(60, 0, 0, 255),
+        BlendMode::Replace,
+    );
+
+    for (i, line) in lines.iter().enumerate() {
+        display::draw_text_with_font(
+            color_buffer,
+            font,
+            HUD_MARGIN + HUD_PADDING,
+            top + HUD_PADDING + i as u32 * line_height,
+            line,
+            Color::rgba(255, 120, 120, 255),
+            BlendMode::Replace,
+            HUD_SCALE,
+        );
+    }
+}
@@ -0,0 +1,387 @@
+// This file contains a small JSON reader, enough for the asset formats the renderer
+// imports.
+//
+// Values are parsed into a `Json` tree in one go. Objects keep their members in file
+// order and look them up linearly, which is fast enough for the handful of keys asset
+// files use.
+
+use std::fmt;
+
+/// A parsed JSON value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    /// Members in the order they appear in the text.
+    Object(Vec<(String, Json)>),
+}
+
+/// An error found while parsing JSON, with the byte offset it was found at.
+#[derive(Debug, Clone)]
+pub struct JsonError {
+    /// The 0-based byte offset of the error.
+    pub offset: usize,
+    /// A description of the problem.
+    pub message: String,
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "byte {}: {}", self.offset, self.message)
+    }
+}
+
+impl Json {
+    /// Parses a JSON document.
+    ///
+    /// # Arguments
+    /// - `text`: The JSON text.
+    ///
+    /// # Returns
+    /// The value of the document.
+    ///
+    /// # Errors
+    /// Returns a `JsonError` if the text is not valid JSON.
+    pub fn parse(text: &str) -> Result<Json, JsonError> {
+        let mut parser = Parser {
+            bytes: text.as_bytes(),
+            position: 0,
+        };
+        let value = parser.value(0)?;
+        parser.skip_whitespace();
+        if parser.position < parser.bytes.len() {
+            return Err(parser.error("unexpected data after the document"));
+        }
+        Ok(value)
+    }
+
+    /// Looks up a member of an object.
+    ///
+    /// # Returns
+    /// The member's value, or `None` if this is not an object or has no such member.
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(members) => members
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    /// Returns the value as a number, if it is one.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Json::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// Returns the value as a non-negative integer index, if it is one and fits in a
+    /// `usize`.
+    pub fn as_usize(&self) -> Option<usize> {
+        match self {
+            Json::Number(n) if *n >= 0.0 && n.fract() == 0.0 && *n < usize::MAX as f64 => {
+                Some(*n as usize)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the value as a string, if it is one.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Returns the elements of an array, or an empty slice for any other value.
+    pub fn elements(&self) -> &[Json] {
+        match self {
+            Json::Array(elements) => elements,
+            _ => &[],
+        }
+    }
+}
+
+/// Deepest nesting of arrays and objects accepted, so malicious files cannot overflow
+/// the stack.
+const MAX_DEPTH: usize = 256;
+
+/// Reads JSON values from text.
+struct Parser<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> JsonError {
+        JsonError {
+            offset: self.position,
+            message: message.to_string(),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self
+            .bytes
+            .get(self.position)
+            .is_some_and(|b| matches!(b, b' ' | b'\t' | b'\n' | b'\r'))
+        {
+            self.position += 1;
+        }
+    }
+
+    /// Consumes `expected` if it comes next, ignoring whitespace.
+    fn eat(&mut self, expected: u8) -> bool {
+        self.skip_whitespace();
+        if self.bytes.get(self.position) == Some(&expected) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, expected: u8) -> Result<(), JsonError> {
+        if self.eat(expected) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected `{}`", expected as char)))
+        }
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Json, JsonError> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("too deeply nested"));
+        }
+
+        self.skip_whitespace();
+        match self.bytes.get(self.position) {
+            Some(b'{') => {
+                self.position += 1;
+                let mut members = Vec::new();
+                if !self.eat(b'}') {
+                    loop {
+                        self.skip_whitespace();
+                        let key = self.string()?;
+                        self.expect(b':')?;
+                        members.push((key, self.value(depth + 1)?));
+                        if self.eat(b'}') {
+                            break;
+                        }
+                        self.expect(b',')?;
+                    }
+                }
+                Ok(Json::Object(members))
+            }
+            Some(b'[') => {
+                self.position += 1;
+                let mut elements = Vec::new();
+                if !self.eat(b']') {
+                    loop {
+                        elements.push(self.value(depth + 1)?);
+                        if self.eat(b']') {
+                            break;
+                        }
+                        self.expect(b',')?;
+                    }
+                }
+                Ok(Json::Array(elements))
+            }
+            Some(b'"') => Ok(Json::String(self.string()?)),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            Some(_) => {
+                for (word, value) in [
+                    ("true", Json::Bool(true)),
+                    ("false", Json::Bool(false)),
+                    ("null", Json::Null),
+                ] {
+                    if self.bytes[self.position..].starts_with(word.as_bytes()) {
+                        self.position += word.len();
+                        return Ok(value);
+                    }
+                }
+                Err(self.error("expected a value"))
+            }
+            None => Err(self.error("unexpected end of data")),
+        }
+    }
+
+    fn number(&mut self) -> Result<Json, JsonError> {
+        let start = self.position;
+        while self
+            .bytes
+            .get(self.position)
+            .is_some_and(|b| matches!(b, b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9'))
+        {
+            self.position += 1;
+        }
+        // The slice only holds ASCII characters, so it is valid UTF-8.
+        let text = std::str::from_utf8(&self.bytes[start..self.position]).unwrap_or("");
+        text.parse().map(Json::Number).map_err(|_| JsonError {
+            offset: start,
+            message: format!("invalid number `{}`", text),
+        })
+    }
+
+    /// Reads a quoted string, decoding its escape sequences.
+    fn string(&mut self) -> Result<String, JsonError> {
+        if self.bytes.get(self.position) != Some(&b'"') {
+            return Err(self.error("expected a string"));
+        }
+        self.position += 1;
+
+        let mut bytes = Vec::new();
+        loop {
+            let byte = *self
+                .bytes
+                .get(self.position)
+                .ok_or_else(|| self.error("unterminated string"))?;
+            self.position += 1;
+            match byte {
+                b'"' => break,
+                b'\\' => {
+                    let escape = *self
+                        .bytes
+                        .get(self.position)
+                        .ok_or_else(|| self.error("unterminated string"))?;
+                    self.position += 1;
+                    let decoded = match escape {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => self.unicode_escape()?,
+                        _ => return Err(self.error("invalid escape sequence")),
+                    };
+                    let mut buffer = [0u8; 4];
+                    bytes.extend_from_slice(decoded.encode_utf8(&mut buffer).as_bytes());
+                }
+                _ => bytes.push(byte),
+            }
+        }
+        // The input was a `&str`, and escapes decode to whole characters.
+        String::from_utf8(bytes).map_err(|_| self.error("invalid UTF-8 in string"))
+    }
+
+    /// Decodes the `XXXX` of a `\uXXXX` escape, combining surrogate pairs.
+    fn unicode_escape(&mut self) -> Result<char, JsonError> {
+        let high = self.hex4()?;
+        let code = if (0xD800..0xDC00).contains(&high) {
+            if !self.bytes[self.position..].starts_with(b"\\u") {
+                return Err(self.error("unpaired surrogate in string"));
+            }
+            self.position += 2;
+            let low = self.hex4()?;
+            if !(0xDC00..0xE000).contains(&low) {
+                return Err(self.error("unpaired surrogate in string"));
+            }
+            0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+        } else {
+            high
+        };
+        char::from_u32(code).ok_or_else(|| self.error("invalid character escape"))
+    }
+
+    fn hex4(&mut self) -> Result<u32, JsonError> {
+        let digits = self
+            .bytes
+            .get(self.position..self.position + 4)
+            .and_then(|digits| std::str::from_utf8(digits).ok())
+            .and_then(|digits| u32::from_str_radix(digits, 16).ok())
+            .ok_or_else(|| self.error("expected four hex digits"))?;
+        self.position += 4;
+        Ok(digits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parses invalid JSON and returns the error's offset and message.
+    fn error(text: &str) -> (usize, String) {
+        let error = Json::parse(text).unwrap_err();
+        (error.offset, error.message)
+    }
+
+    #[test]
+    fn parse_reads_nested_values() {
+        let document =
+            Json::parse(r#" {"a": [1, -2.5e1, true, null], "b": {"c": "d"}, "a": false} "#)
+                .unwrap();
+
+        assert_eq!(
+            document.get("a").unwrap().elements(),
+            [
+                Json::Number(1.0),
+                Json::Number(-25.0),
+                Json::Bool(true),
+                Json::Null,
+            ]
+        );
+        assert_eq!(
+            document.get("b").and_then(|b| b.get("c")),
+            Some(&Json::String("d".into()))
+        );
+        // Lookups find the first of repeated keys.
+        assert_eq!(document.get("a").unwrap().elements().len(), 4);
+        assert_eq!(document.get("missing"), None);
+        assert!(Json::Number(1.0).elements().is_empty());
+    }
+
+    #[test]
+    fn strings_decode_escapes() {
+        let value = Json::parse(r#""a\"\\\/\b\f\n\r\té😀""#).unwrap();
+        assert_eq!(value.as_str(), Some("a\"\\/\u{8}\u{c}\n\r\té😀"));
+
+        assert_eq!(error(r#""\x""#), (3, "invalid escape sequence".to_string()));
+        assert_eq!(
+            error(r#""\ud83d""#),
+            (7, "unpaired surrogate in string".to_string())
+        );
+        assert_eq!(
+            error(r#""\u12"#),
+            (3, "expected four hex digits".to_string())
+        );
+        assert_eq!(error(r#""abc"#), (4, "unterminated string".to_string()));
+    }
+
+    #[test]
+    fn as_usize_accepts_only_indices() {
+        assert_eq!(Json::Number(7.0).as_usize(), Some(7));
+        assert_eq!(Json::Number(0.0).as_usize(), Some(0));
+        assert_eq!(Json::Number(-1.0).as_usize(), None);
+        assert_eq!(Json::Number(1.5).as_usize(), None);
+        assert_eq!(Json::Number(1e30).as_usize(), None);
+        assert_eq!(Json::Number(f64::NAN).as_usize(), None);
+        assert_eq!(Json::String("7".into()).as_usize(), None);
+    }
+
+    #[test]
+    fn errors_report_their_offset() {
+        assert_eq!(error("[1, 2"), (5, "expected `,`".to_string()));
+        assert_eq!(
+            error("[1] x"),
+            (4, "unexpected data after the document".to_string())
+        );
+        assert_eq!(error("{1: 2}"), (1, "expected a string".to_string()));
+        assert_eq!(error("[1-2]"), (1, "invalid number `1-2`".to_string()));
+        assert_eq!(error("[tru]"), (1, "expected a value".to_string()));
+        assert_eq!(error(""), (0, "unexpected end of data".to_string()));
+        let deep = "[".repeat(MAX_DEPTH + 2);
+        assert_eq!(
+            error(&deep),
+            (MAX_DEPTH + 1, "too deeply nested".to_string())
+        );
+    }
+}
@@ -0,0 +1,45 @@
+// This file contains the lights a scene file can place, which shade faces by the angle
+// they meet the light at.
+
+use crate::vector::Vec3;
+
+/// A light infinitely far away, such as the sun, whose rays all travel in the same
+/// direction.
+#[derive(Debug, Copy, Clone)]
+pub struct DirectionalLight {
+    /// Direction the light travels in, in world space.
+    pub direction: Vec3,
+    /// Color of the light, sRGB-encoded with each channel from 0.0 to 1.0.
+    pub color: Vec3,
+    /// Brightness multiplier of the light.
+    pub intensity: f32,
+}
+
+impl DirectionalLight {
+    /// Creates a new white `DirectionalLight`.
+    ///
+    /// # Arguments
+    /// - `direction`: Direction the light travels in.
+    ///
+    /// # Returns
+    /// A new `DirectionalLight` with full intensity.
+    pub fn new(direction: Vec3) -> DirectionalLight {
+        DirectionalLight {
+            direction: direction.normalize(),
+            color: Vec3::new(1.0, 1.0, 1.0),
+            intensity: 1.0,
+        }
+    }
+
+    /// Calculates how much light reaches a surface facing a given direction.
+    ///
+    /// # Arguments
+    /// - `normal`: The unit normal of the surface.
+    ///
+    /// # Returns
+    /// The light's color scaled by its intensity and the angle of incidence.
+    pub fn illuminate(&self, normal: Vec3) -> Vec3 {
+        let facing = normal.dot(-self.direction).max(0.0);
+        self.color * (self.intensity * facing)
+    }
+}
@@ -1,18 +1,94 @@
 extern crate sdl2;
 
+use antialias::{AntiAliasing, SampleBuffers};
+use blend::{BlendMode, BYTES_PER_PIXEL, CHANNELS};
+use camera::OrbitCamera;
+use clock::{FixedTimestep, FrameClock, FIXED_TIMESTEP};
+use color::Color;
 use display::FRAMES_PER_SECOND;
+use font::Font;
+use frustum::Frustum;
+use light::DirectionalLight;
+use material::Material;
+use mesh::Mesh;
+use scene::{Scene, Transform};
+use scene_file::SceneDescription;
 use sdl2::event::Event;
 use sdl2::keyboard::Keycode;
 use sdl2::render::Canvas;
 use sdl2::video::Window;
 use sdl2::Sdl;
-use std::time::Duration;
+use settings::RenderSettings;
+use stats::RenderStats;
+use std::borrow::Cow;
+use std::time::Instant;
+use transparency::TransparencyBuffers;
+use triangle::Triangle;
 use vector::Vec3;
+use watch::FileWatcher;
 
+mod antialias;
+mod bench;
+mod blend;
+mod bounds;
+mod camera;
+mod clock;
+mod color;
 mod display;
+mod font;
+mod frustum;
+mod gltf;
+mod hdr_image;
+mod hud;
+mod json;
+mod light;
+mod material;
+mod matrix;
 mod mesh;
+mod pipeline;
+mod ply;
+mod primitives;
+mod raster;
+mod scene;
+mod scene_file;
+mod settings;
+mod simd;
+mod stats;
+mod stl;
+mod texture;
+mod tonemap;
+mod transparency;
 mod triangle;
 mod vector;
+mod watch;
+
+/// Scene loaded when no scene file is given on the command line.
+const DEFAULT_SCENE: &str = "./assets/default.scene";
+
+/// Focal length of the projection used when the scene does not set a field of view,
+/// in pixels.
+const DEFAULT_FOV_FACTOR: f32 = 700.0;
+
+/// File `X` saves the HDR frame to when `--hdr` does not name one.
+const DEFAULT_HDR_PATH: &str = "frame.hdr";
+
+/// Stops the exposure changes by for each press of `-` or `=`.
+const EXPOSURE_STEP: f32 = 0.5;
+
+/// Converts a vertical field of view into the scale factor of the projection.
+///
+/// # Arguments
+/// - `fov`: The vertical field of view in radians, or `None` for the default.
+///
+/// # Returns
+/// The distance from the eye to the projection plane, in pixels.
+fn fov_factor(fov: Option<f32>) -> f32 {
+    // The field of view is vertical, so it spans half the window's height.
+    match fov {
+        Some(fov) => (display::WINDOW_HEIGHT as f32 / 2.0) / (fov / 2.0).tan(),
+        None => DEFAULT_FOV_FACTOR,
+    }
+}
 
 /// The `Renderer` struct is responsible for managing the rendering process,
 /// including initializing the SDL context, projecting 3D points to 2D,
@@ -24,16 +100,60 @@ struct Renderer {
     canvas: Canvas<Window>,
     /// Color buffer used for rendering pixel data.
     color_buffer: Vec<u8>,
+    /// The frame in linear light, before it is tone mapped into `color_buffer`.
+    hdr_buffer: Vec<f32>,
+    /// Inverse depth of the closest surface drawn at each pixel.
+    depth_buffer: Vec<f32>,
+    /// Samples of the frame while it is drawn with anti-aliasing.
+    sample_buffers: SampleBuffers,
+    /// Fragments of transparent triangles gathered by the order-independent modes.
+    transparency_buffers: TransparencyBuffers,
     /// Flag indicating whether the application is running.
     is_running: bool,
     /// Field of view factor for projecting 3D points onto a 2D plane.
     fov_factor: f32,
-    /// Camera position in 3D space.
-    camera_position: Vec3,
+    /// Orbit camera used to inspect the scene.
+    camera: OrbitCamera,
+    /// Whether nodes spin by their angular velocity every step.
+    auto_rotate: bool,
+    /// Measures frame deltas and caps the frame rate.
+    clock: FrameClock,
+    /// Splits frame deltas into fixed simulation steps.
+    timestep: FixedTimestep,
+    /// Whether the simulation advances in fixed steps instead of once per frame.
+    use_fixed_timestep: bool,
+    /// Whether the simulation is paused.
+    paused: bool,
+    /// Whether a single step was requested while paused.
+    step_requested: bool,
+    /// Node transforms before the last simulation step, used for interpolation.
+    previous_transforms: Vec<Transform>,
+    /// Timings and counters of the current frame.
+    stats: RenderStats,
+    /// Where `X` saves the HDR frame; see `hdr_image::save`.
+    hdr_path: String,
+    /// Whether the HDR frame is saved once the next frame is drawn.
+    export_requested: bool,
+    /// Whether the performance overlay is drawn.
+    show_hud: bool,
+    /// Font used to draw the performance overlay.
+    hud_font: Font,
     /// List of triangles to render in the current frame.
-    triangles_to_render: Vec<triangle::Triangle>,
-    /// The 3D mesh being rendered.
-    mesh: mesh::Mesh,
+    triangles_to_render: Vec<Triangle>,
+    /// The meshes and node hierarchy being rendered.
+    scene: Scene,
+    /// Options controlling how the scene is drawn.
+    settings: RenderSettings,
+    /// The options as the scene file last set them, before any runtime changes.
+    loaded_settings: RenderSettings,
+    /// Path of the scene file being rendered.
+    scene_path: String,
+    /// Watches the files the scene was built from for changes.
+    watcher: FileWatcher,
+    /// Why the last reload failed, shown until a reload succeeds.
+    reload_error: Option<String>,
+    /// Seconds of simulated time the nodes have spun for.
+    spin_time: f32,
 }
 
 impl Renderer {
@@ -42,10 +162,18 @@ impl Renderer {
     /// # Arguments
     /// - `window`: The SDL2 window for rendering.
     /// - `sdl_context`: The SDL2 context for managing SDL systems.
+    /// - `scene_path`: The path of the scene file, watched for changes.
+    /// - `description`: The scene loaded from `scene_path`, with its camera and render
+    ///   settings.
     ///
     /// # Returns
     /// A fully initialized `Renderer`.
-    pub fn new(window: Window, sdl_context: Sdl) -> Renderer {
+    pub fn new(
+        window: Window,
+        sdl_context: Sdl,
+        scene_path: &str,
+        description: SceneDescription,
+    ) -> Renderer {
         let canvas = window
             .into_canvas()
             .present_vsync()
@@ -53,153 +181,590 @@ impl Renderer {
             .map_err(|e| e.to_string())
             .unwrap();
 
-        let color_buffer = vec![0; (display::WINDOW_WIDTH * display::WINDOW_HEIGHT * 3) as usize];
-        let mesh = mesh::Mesh::load_from_file("./assets/f22.obj");
+        let color_buffer =
+            vec![0; (display::WINDOW_WIDTH * display::WINDOW_HEIGHT) as usize * BYTES_PER_PIXEL];
+        let hdr_buffer =
+            vec![0.0; (display::WINDOW_WIDTH * display::WINDOW_HEIGHT) as usize * CHANNELS];
+        let depth_buffer = vec![0.0; (display::WINDOW_WIDTH * display::WINDOW_HEIGHT) as usize];
+        let SceneDescription {
+            scene,
+            camera: camera_description,
+            settings,
+            files,
+        } = description;
 
-        Renderer {
+        let fov_factor = fov_factor(camera_description.fov);
+        let mut renderer = Renderer {
             sdl_context,
             canvas,
             color_buffer,
+            hdr_buffer,
+            depth_buffer,
+            sample_buffers: SampleBuffers::new(),
+            transparency_buffers: TransparencyBuffers::new(),
             is_running: true,
-            fov_factor: 700.0,
-            camera_position: Vec3::new(0.0, 0.0, 0.0),
+            fov_factor,
+            camera: OrbitCamera::new(Vec3::new(0.0, 0.0, 0.0), 5.0),
+            auto_rotate: true,
+            clock: FrameClock::new(FRAMES_PER_SECOND),
+            timestep: FixedTimestep::new(FIXED_TIMESTEP),
+            use_fixed_timestep: true,
+            paused: false,
+            step_requested: false,
+            previous_transforms: scene.transforms(),
+            stats: RenderStats::default(),
+            hdr_path: DEFAULT_HDR_PATH.to_string(),
+            export_requested: false,
+            show_hud: true,
+            hud_font: Font::builtin().clone(),
             triangles_to_render: Vec::new(),
-            mesh,
+            scene,
+            settings,
+            loaded_settings: settings,
+            scene_path: scene_path.to_string(),
+            watcher: FileWatcher::new(files),
+            reload_error: None,
+            spin_time: 0.0,
+        };
+
+        // Frame the scene, then apply whatever the scene file chose explicitly.
+        renderer.frame_scene();
+        if let Some(target) = camera_description.target {
+            renderer.camera.target = target;
+        }
+        if let Some(distance) = camera_description.distance {
+            renderer.camera.distance = distance;
         }
+        renderer.camera.yaw = camera_description.yaw;
+        renderer.camera.pitch = camera_description.pitch;
+        renderer
     }
 
-    /// Projects a 3D point onto a 2D plane using perspective projection.
-    ///
-    /// # Arguments
-    /// - `point`: A 3D point (`Vec3`) to project.
+    /// Calculates half of the narrowest field of view angle of the projection.
     ///
     /// # Returns
-    /// A 2D point (`Vec2`) representing the projected coordinates.    
-    pub fn project(&mut self, point: vector::Vec3) -> vector::Vec2 {
-        let projected_point = vector::Vec2 {
-            x: (self.fov_factor * point.x) / point.z,
-            y: (self.fov_factor * point.y) / point.z,
-        };
-        projected_point
+    /// The half angle in radians.
+    pub fn half_fov(&self) -> f32 {
+        let half_extent = display::WINDOW_WIDTH.min(display::WINDOW_HEIGHT) as f32 / 2.0;
+        (half_extent / self.fov_factor).atan()
+    }
+
+    /// Points the camera at the scene's bounding-box center and backs it off until
+    /// every visible mesh fits in view.
+    pub fn frame_scene(&mut self) {
+        if let Some(aabb) = self.scene.bounds(&self.scene.transforms()) {
+            let sphere = aabb.bounding_sphere();
+            let half_fov = self.half_fov();
+            self.camera.frame(sphere.center, sphere.radius, half_fov);
+        }
     }
 
     /// Processes user input and handles events such as quitting or camera movement.
+    ///
+    /// Left-drag orbits the camera, middle-drag pans it, the wheel dollies it,
+    /// `F` frames the whole scene and `R` toggles the automatic rotation. `P` pauses the
+    /// simulation, `N` advances a paused simulation by one step and `T` switches between
+    /// fixed and variable timesteps. `H` toggles the performance overlay. `A` cycles
+    /// through the anti-aliasing modes and `B` switches the supersampling filter. `L`
+    /// switches between aliased and smooth edges, and `[` and `]` narrow and widen them.
+    /// `O` cycles through the ways transparent triangles are drawn. `M` cycles through the
+    /// tone mapping curves, `-` and `=` lower and raise the exposure by half a stop, and
+    /// `X` saves the next frame before tone mapping as an HDR image.
     pub fn process_input(&mut self) {
         let mut events = self.sdl_context.event_pump().unwrap();
         for event in events.poll_iter() {
             match event {
                 Event::Quit { .. } => self.is_running = false, // Exit the application.
-                Event::MouseWheel { y, .. } => {
-                    self.camera_position.z += y as f32; // Adjust camera zoom.
+                Event::MouseMotion {
+                    mousestate,
+                    xrel,
+                    yrel,
+                    ..
+                } => {
+                    if mousestate.left() {
+                        self.camera.orbit(xrel as f32, yrel as f32);
+                    } else if mousestate.middle() {
+                        self.camera.pan(xrel as f32, yrel as f32, self.fov_factor);
+                    }
                 }
+                Event::MouseWheel { y, .. } => self.camera.dolly(y as f32),
                 Event::KeyDown {
                     keycode: Some(Keycode::Escape),
                     ..
                 } => self.is_running = false, // Exit on Escape key.
+                Event::KeyDown {
+                    keycode: Some(Keycode::F),
+                    ..
+                } => self.frame_scene(),
+                Event::KeyDown {
+                    keycode: Some(Keycode::R),
+                    ..
+                } => self.auto_rotate = !self.auto_rotate,
+                Event::KeyDown {
+                    keycode: Some(Keycode::P),
+                    ..
+                } => self.paused = !self.paused,
+                Event::KeyDown {
+                    keycode: Some(Keycode::N),
+                    ..
+                } => self.step_requested = true,
+                Event::KeyDown {
+                    keycode: Some(Keycode::T),
+                    ..
+                } => self.use_fixed_timestep = !self.use_fixed_timestep,
+                Event::KeyDown {
+                    keycode: Some(Keycode::H),
+                    ..
+                } => self.show_hud = !self.show_hud,
+                Event::KeyDown {
+                    keycode: Some(Keycode::A),
+                    ..
+                } => self.settings.antialiasing = self.settings.antialiasing.next(),
+                Event::KeyDown {
+                    keycode: Some(Keycode::B),
+                    ..
+                } => self.settings.downsample_filter = self.settings.downsample_filter.toggled(),
+                Event::KeyDown {
+                    keycode: Some(Keycode::L),
+                    ..
+                } => self.settings.line_style = self.settings.line_style.toggled(),
+                Event::KeyDown {
+                    keycode: Some(Keycode::O),
+                    ..
+                } => self.settings.transparency = self.settings.transparency.next(),
+                Event::KeyDown {
+                    keycode: Some(Keycode::M),
+                    ..
+                } => self.settings.tonemapper = self.settings.tonemapper.next(),
+                Event::KeyDown {
+                    keycode: Some(Keycode::Minus),
+                    ..
+                } => self.settings.exposure -= EXPOSURE_STEP,
+                Event::KeyDown {
+                    keycode: Some(Keycode::Equals),
+                    ..
+                } => self.settings.exposure += EXPOSURE_STEP,
+                Event::KeyDown {
+                    keycode: Some(Keycode::X),
+                    ..
+                } => self.export_requested = true,
+                Event::KeyDown {
+                    keycode: Some(Keycode::LeftBracket),
+                    ..
+                } => self.settings.line_width = (self.settings.line_width - 1.0).max(1.0),
+                Event::KeyDown {
+                    keycode: Some(Keycode::RightBracket),
+                    ..
+                } => {
+                    self.settings.line_width =
+                        (self.settings.line_width + 1.0).min(display::MAX_LINE_WIDTH)
+                }
                 _ => {}
             }
         }
     }
-    
-    /// Updates the state of the mesh and prepares triangles for rendering.
-    pub fn update(&mut self) {
-        // Rotate the mesh slightly in each axis.
-        self.mesh.rotation.x += 0.02;
-        self.mesh.rotation.y += 0.02;
-        self.mesh.rotation.z += 0.02;
-
-        let num_faces = self.mesh.faces.len();
-        for i in 0..num_faces {
-            let cube_face = self.mesh.faces[i];
-
-            // Get the vertices of the current face.
-            let mut face_vertices: [Vec3; 3] = [Vec3::new(0.0, 0.0, 0.0); 3];
-            face_vertices[0] = self.mesh.vertices[cube_face.a - 1];
-            face_vertices[1] = self.mesh.vertices[cube_face.b - 1];
-            face_vertices[2] = self.mesh.vertices[cube_face.c - 1];
-
-            // Initialize a triangle for the projected points.
-            let mut projected_triangle: triangle::Triangle = triangle::Triangle {
-                points: [vector::Vec2 { x: 0.0, y: 0.0 }; 3],
-            };
-            let mut transformed_vertices: [Vec3; 3] = [Vec3::new(0.0, 0.0, 0.0); 3];
 
-            // Transforming vertices
-            for j in 0..3 {
-                let mut transformed_vertex = face_vertices[j];
-                transformed_vertex = transformed_vertex.rotate_x(self.mesh.rotation.x);
-                transformed_vertex = transformed_vertex.rotate_y(self.mesh.rotation.y);
-                transformed_vertex = transformed_vertex.rotate_z(self.mesh.rotation.z);
+    /// Advances the simulation by a slice of time.
+    ///
+    /// # Arguments
+    /// - `delta`: The simulated time in seconds.
+    pub fn simulate(&mut self, delta: f32) {
+        self.previous_transforms = self.scene.transforms();
 
-                transformed_vertex.z += 5.0;
-                transformed_vertices[j] = transformed_vertex;
+        // Rotate every spinning node in each axis.
+        if self.auto_rotate {
+            self.spin_time += delta;
+            for node in &mut self.scene.nodes {
+                node.transform.rotation = node.transform.rotation + node.angular_velocity * delta;
             }
+        }
+    }
 
-            // Applying backface culling
-            // Getting vectors
-            let vector_a = transformed_vertices[0]; //     A
-            let vector_b = transformed_vertices[1]; //   /   \
-            let vector_c = transformed_vertices[2]; //  C-----B
+    /// Advances the simulation by the time elapsed since the last frame.
+    ///
+    /// # Returns
+    /// How far between the previous and current simulated states the frame should be
+    /// drawn, from `0.0` to `1.0`.
+    pub fn advance_time(&mut self) -> f32 {
+        let mut delta = self.clock.tick();
+        self.stats.begin_frame(delta);
+        if self.paused {
+            if !self.step_requested {
+                return if self.use_fixed_timestep {
+                    self.timestep.alpha()
+                } else {
+                    1.0
+                };
+            }
+            delta = self.timestep.step;
+            self.step_requested = false;
+        }
 
-            // Calculate Normal
-            let vector_ab = vector_b - vector_a;
-            let vector_ac = vector_c - vector_a;
-            let normal = vector_ab.cross(vector_ac);
+        if self.use_fixed_timestep {
+            self.timestep.accumulate(delta);
+            while self.timestep.consume() {
+                self.simulate(self.timestep.step);
+            }
+            self.timestep.alpha()
+        } else {
+            self.simulate(delta);
+            1.0
+        }
+    }
 
-            // Calculate Camera Ray
-            let camera_ray = self.camera_position - vector_a;
+    /// Reloads the scene if the scene file or any file it references changed.
+    ///
+    /// The camera is left where it is, but takes the file's new field of view. Spinning
+    /// nodes keep their progress, and render options changed with the keyboard keep
+    /// their new values. If the new version cannot be loaded, the current scene stays
+    /// and the error is shown until a later change loads successfully; the files the
+    /// scene file now names are watched, so creating a missing one retries the load.
+    pub fn reload_changed_files(&mut self) {
+        if !self.watcher.poll() {
+            return;
+        }
 
-            //  Calculate Camera Ray Dot Normal
-            let dot_camera = normal.dot(camera_ray);
-            if dot_camera < 0.0 {
-                continue;
+        match scene_file::load(&self.scene_path) {
+            Ok(description) => {
+                self.scene = description.scene;
+                for node in &mut self.scene.nodes {
+                    node.transform.rotation =
+                        node.transform.rotation + node.angular_velocity * self.spin_time;
+                }
+                self.previous_transforms = self.scene.transforms();
+                self.settings = description
+                    .settings
+                    .keep_runtime_changes(&self.settings, &self.loaded_settings);
+                self.loaded_settings = description.settings;
+                self.fov_factor = fov_factor(description.camera.fov);
+                self.watcher = FileWatcher::new(description.files);
+                self.reload_error = None;
+            }
+            Err(e) => {
+                eprintln!("Could not reload scene: {}", e);
+                // Keep watching the files of the current scene too, such as the material
+                // libraries its meshes name, which the scene file does not list.
+                let mut files = scene_file::referenced_files(&self.scene_path);
+                for file in self.watcher.paths() {
+                    if !files.contains(file) {
+                        files.push(file.clone());
+                    }
+                }
+                self.watcher = FileWatcher::new(files);
+                self.reload_error = Some(e);
             }
+        }
+    }
+
+    /// Updates the state of the scene and prepares triangles for rendering.
+    pub fn update(&mut self) {
+        self.reload_changed_files();
+        let alpha = self.advance_time();
 
-            // Projecting 3D points to 2D
-            for j in 0..3 {
-                let mut projected_point = self.project(transformed_vertices[j]);
+        // Draw the nodes part way between their previous and current transforms.
+        let transforms: Vec<Transform> = self
+            .previous_transforms
+            .iter()
+            .zip(&self.scene.nodes)
+            .map(|(previous, node)| previous.lerp(&node.transform, alpha))
+            .collect();
 
-                // Center the projected point on the screen.
-                projected_point.x += display::WINDOW_WIDTH as f32 / 2.0;
-                projected_point.y += display::WINDOW_HEIGHT as f32 / 2.0;
-                projected_triangle.points[j] = projected_point;
+        // Vertex stage: transform the vertices of every visible instance into camera
+        // space, once each.
+        let transform_start = Instant::now();
+        let threads = self.settings.thread_count();
+        let view = self.camera.view_matrix();
+        let frustum = Frustum::new(
+            self.fov_factor,
+            display::WINDOW_WIDTH as f32,
+            display::WINDOW_HEIGHT as f32,
+            camera::NEAR_PLANE,
+        );
+        let instances = self.scene.instances(&transforms);
+        self.stats.objects_submitted = instances.len();
+        let mut visible_instances = Vec::new();
+        for instance in instances {
+            let model_view = view * instance.world;
+
+            // Skip the whole instance when its bounds are off-screen. The sphere test is
+            // cheapest; the box is tighter for long, thin meshes.
+            if self.settings.frustum_culling {
+                if let Some((aabb, sphere)) = self.scene.mesh_bounds[instance.mesh] {
+                    if !frustum.intersects_sphere(&sphere.transformed(&model_view))
+                        || !frustum.intersects_aabb(&aabb.transformed(&model_view))
+                    {
+                        self.stats.objects_culled += 1;
+                        continue;
+                    }
+                }
             }
-            // Add the projected triangle to the render list.
-            self.triangles_to_render.push(projected_triangle);
+
+            let mesh = &self.scene.meshes[instance.mesh];
+            let view_vertices = pipeline::transform_vertices(&mesh.vertices, &model_view, threads);
+            self.stats.triangles_submitted += mesh.faces.len();
+            visible_instances.push((instance, view_vertices));
+        }
+        self.stats.transform_time = transform_start.elapsed();
+
+        // Primitive assembly: clip, cull, light and project the faces of each instance.
+        let clip_start = Instant::now();
+        let default_material = Material::new("default");
+        let view_lights: Vec<DirectionalLight> = self
+            .scene
+            .lights
+            .iter()
+            .map(|light| DirectionalLight {
+                direction: self.camera.direction_to_view(light.direction),
+                color: color::srgb_to_linear_rgb(light.color),
+                ..*light
+            })
+            .collect();
+        for (instance, view_vertices) in visible_instances {
+            let material = match instance.material {
+                Some(material) => &self.scene.materials[material],
+                None => &default_material,
+            };
+            let shading = pipeline::Shading {
+                diffuse: color::srgb_to_linear_rgb(material.diffuse),
+                vertex_colors: self.settings.vertex_colors,
+                opacity: material.opacity,
+                blend: material.blend,
+                texture: material.texture,
+                ambient: self.settings.ambient,
+                lights: &view_lights,
+            };
+            let assembly = pipeline::assemble_triangles(
+                &self.scene.meshes[instance.mesh],
+                &view_vertices,
+                &shading,
+                self.settings.backface_culling,
+                self.fov_factor,
+                threads,
+            );
+            self.stats.triangles_culled += assembly.culled;
+            self.stats.triangles_clipped += assembly.clipped;
+            self.triangles_to_render.extend(assembly.triangles);
         }
+        self.stats.clip_time = clip_start.elapsed();
+    }
+
+    /// Returns the timings and counters gathered for the most recent frame.
+    pub fn stats(&self) -> &RenderStats {
+        &self.stats
     }
 
     /// Renders all triangles to the screen and updates the display.
     pub fn render(&mut self) {
         // Draw each triangle onto the color buffer.
+        let raster_start = Instant::now();
         let num_triangles = self.triangles_to_render.len();
+        let threads = self.settings.thread_count();
+        let antialiasing = self.settings.antialiasing;
+        let transparency = self.settings.transparency;
+        let mut resolve_time = std::time::Duration::ZERO;
+        let mut export_time = std::time::Duration::ZERO;
+        let background = self.settings.background.to_linear();
 
-        for i in 0..num_triangles {
-            let triangle = &self.triangles_to_render[i];
-            display::draw_triangle(
-                &mut self.color_buffer,
-                triangle.points,
-                sdl2::pixels::Color::RGBA(0, 150, 0, 255),
+        // Opaque triangles are filled first, and transparent ones blended over them, in
+        // linear light.
+        let draws_faces = self.settings.mode.draws_faces();
+        if !draws_faces {
+            raster::clear_color_buffer(&mut self.hdr_buffer, background);
+        } else if antialiasing == AntiAliasing::Off {
+            raster::clear_color_buffer(&mut self.hdr_buffer, background);
+            raster::clear_depth_buffer(&mut self.depth_buffer);
+            self.stats.triangles_transparent = transparency::fill_scene(
+                raster::RasterTarget::frame(&mut self.hdr_buffer, &mut self.depth_buffer),
+                &self.triangles_to_render,
+                &self.scene.textures,
+                transparency,
+                &mut self.transparency_buffers,
+                threads,
+                self.settings.kernel(),
             );
+        } else {
+            // Fill the samples, scaling the triangles up to a supersampled frame, then
+            // average them into the window's pixels.
+            self.sample_buffers.prepare(antialiasing, background);
+            let scale = antialiasing.scale() as f32;
+            let triangles: Cow<[Triangle]> = if scale == 1.0 {
+                Cow::Borrowed(&self.triangles_to_render)
+            } else {
+                self.triangles_to_render
+                    .iter()
+                    .map(|triangle| Triangle {
+                        points: triangle.points.map(|point| point * scale),
+                        ..*triangle
+                    })
+                    .collect()
+            };
+            self.stats.triangles_transparent = transparency::fill_scene(
+                self.sample_buffers.target(),
+                &triangles,
+                &self.scene.textures,
+                transparency,
+                &mut self.transparency_buffers,
+                threads,
+                self.settings.kernel(),
+            );
+
+            let resolve_start = Instant::now();
+            self.sample_buffers
+                .resolve(self.settings.downsample_filter, &mut self.hdr_buffer);
+            resolve_time = resolve_start.elapsed();
+        }
+
+        // Saving is timed on its own and left out of the raster time.
+        if self.export_requested {
+            self.export_requested = false;
+            let export_start = Instant::now();
+            let saved = hdr_image::save(
+                &self.hdr_path,
+                &self.hdr_buffer,
+                display::WINDOW_WIDTH,
+                display::WINDOW_HEIGHT,
+            );
+            export_time = export_start.elapsed();
+            match saved {
+                Ok(()) => println!(
+                    "Saved the frame to {} in {:.1} ms",
+                    self.hdr_path,
+                    export_time.as_secs_f64() * 1000.0
+                ),
+                Err(e) => eprintln!("Could not save the frame: {}", e),
+            }
+        }
+
+        // Bring the frame into the range of the screen, and encode it for display.
+        let tonemap_start = Instant::now();
+        tonemap::resolve(
+            &self.hdr_buffer,
+            self.settings.exposure,
+            self.settings.tonemapper,
+            &mut self.color_buffer,
+            threads,
+        );
+        let tonemap_time = tonemap_start.elapsed();
+
+        // Edges and vertex markers are drawn on top of the filled faces, at the window's
+        // resolution and in its colors, after tone mapping.
+        for triangle in &self.triangles_to_render {
+            if self.settings.mode.draws_edges() {
+                display::draw_triangle(
+                    &mut self.color_buffer,
+                    triangle.points,
+                    self.settings.wireframe_color,
+                    BlendMode::AlphaOver,
+                    self.settings.stroke(),
+                );
+            }
+            if self.settings.show_vertices {
+                display::draw_triangle_vertices(
+                    &mut self.color_buffer,
+                    triangle.points,
+                    Color::RED,
+                    BlendMode::Replace,
+                );
+            }
+        }
+
+        self.stats.raster_time = raster_start.elapsed() - resolve_time - export_time - tonemap_time;
+        self.stats.resolve_time = resolve_time;
+        self.stats.tonemap_time = tonemap_time;
+        self.stats.triangles_drawn = num_triangles;
+        self.stats.antialiasing = antialiasing.name();
+        self.stats.transparency = transparency.name();
+        self.stats.tonemapper = self.settings.tonemapper.name();
+        self.stats.exposure = self.settings.exposure;
+        self.stats.downsample_filter = match antialiasing.scale() {
+            1 => "",
+            _ => self.settings.downsample_filter.name(),
+        };
+
+        // Draw the performance overlay on top of the scene.
+        if self.show_hud {
+            let stats = *self.stats();
+            hud::draw_hud(&mut self.color_buffer, &self.hud_font, &stats);
+        }
+        if let Some(error) = &self.reload_error {
+            hud::draw_error(&mut self.color_buffer, &self.hud_font, error);
         }
 
         // Clear the triangle list and update the canvas.
+        let present_start = Instant::now();
         self.triangles_to_render.clear();
-        display::render_color_buffer(&mut self.canvas, &mut self.color_buffer);
-        display::clear_color_buffer(&mut self.color_buffer);
+        display::render_color_buffer(&mut self.canvas, &self.color_buffer);
         self.canvas.present();
+        self.stats.present_time = present_start.elapsed();
 
-        // Cap the frame rate.
-        ::std::thread::sleep(Duration::new(0, 1_000_000_000u32 / FRAMES_PER_SECOND));
+        // Cap the frame rate, accounting for the time the frame already took.
+        self.clock.limit();
     }
 }
 
 /// Entry point of the application. Initializes SDL2, the window, and the renderer,
 /// and starts the main render loop.
 pub fn main() {
+    // The first argument that is not an option names the scene file to render.
+    let args: Vec<String> = std::env::args().collect();
+    if args.iter().any(|arg| arg == "--bench") {
+        bench::run(); // Benchmarks run without a window.
+        return;
+    }
+
+    // `--convert <mesh> <file.obj|file.stl>` writes a mesh out in another format, without
+    // a window.
+    if let Some(i) = args.iter().position(|arg| arg == "--convert") {
+        let (Some(input), Some(output)) = (args.get(i + 1), args.get(i + 2)) else {
+            eprintln!("--convert expects the mesh to read and the file to write");
+            std::process::exit(1);
+        };
+        match Mesh::load(input).and_then(|mesh| mesh.save(output)) {
+            Ok(()) => println!("Saved the mesh to {}", output),
+            Err(e) => {
+                eprintln!("Could not convert the mesh: {}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    let scene_path = args
+        .iter()
+        .enumerate()
+        .skip(1)
+        .find(|(i, arg)| {
+            !arg.starts_with("--") && !["--font", "--hdr"].contains(&args[i - 1].as_str())
+        })
+        .map_or(DEFAULT_SCENE, |(_, arg)| arg.as_str());
+    let description = match scene_file::load(scene_path) {
+        Ok(description) => description,
+        Err(e) => {
+            eprintln!("Could not load scene: {}", e);
+            std::process::exit(1);
+        }
+    };
+
     let sdl_context = sdl2::init().unwrap(); // Initialize SDL2.
     let window = display::initialize_window(&sdl_context); // Create a window.
-    let mut renderer = Renderer::new(window.unwrap(), sdl_context); // Create the renderer.
+    let mut renderer = Renderer::new(window.unwrap(), sdl_context, scene_path, description); // Create the renderer.
+
+    // An optional `--font <file.bdf>` argument replaces the overlay's built-in font.
+    if let Some(i) = args.iter().position(|arg| arg == "--font") {
+        match args.get(i + 1).map(|path| Font::load_bdf(path)) {
+            Some(Ok(font)) => renderer.hud_font = font,
+            Some(Err(e)) => eprintln!("Could not load font: {}", e),
+            None => eprintln!("--font expects the path of a BDF file"),
+        }
+    }
+
+    // An optional `--hdr <file.hdr|file.pfm>` argument names the file `X` saves to.
+    if let Some(i) = args.iter().position(|arg| arg == "--hdr") {
+        match args.get(i + 1) {
+            Some(path) => renderer.hdr_path = path.clone(),
+            None => eprintln!("--hdr expects the path of a .hdr or .pfm file"),
+        }
+    }
 
     // Main application loop.
     while renderer.is_running {
@@ -0,0 +1,148 @@
+// This file contains the materials that describe how meshes look, and the reading and
+// writing of Wavefront MTL files that store them.
+
+use std::path::Path;
+
+use crate::blend::BlendMode;
+use crate::texture::TextureId;
+use crate::vector::Vec3;
+
+/// Index of a material stored in a `Scene`.
+pub type MaterialId = usize;
+
+/// Describes how the surface of a mesh looks.
+#[derive(Debug, Clone)]
+pub struct Material {
+    /// Name used to reference the material.
+    pub name: String,
+    /// Diffuse color, sRGB-encoded with each channel from 0.0 to 1.0.
+    pub diffuse: Vec3,
+    /// Opacity, from 0.0 (invisible) to 1.0 (opaque). It becomes the alpha of the
+    /// mesh's colors.
+    pub opacity: f32,
+    /// How the mesh's colors are combined with the frame.
+    pub blend: BlendMode,
+    /// Path of the diffuse color texture, if any.
+    pub diffuse_texture: Option<String>,
+    /// The scene's texture loaded from `diffuse_texture` by `Scene::load_textures`, which
+    /// tints the diffuse color.
+    pub texture: Option<TextureId>,
+}
+
+impl Material {
+    /// Creates a new opaque, light grey `Material`.
+    ///
+    /// # Arguments
+    /// - `name`: Name used to reference the material.
+    ///
+    /// # Returns
+    /// A new `Material` with default properties.
+    pub fn new(name: &str) -> Material {
+        Material {
+            name: name.to_string(),
+            diffuse: Vec3::new(0.8, 0.8, 0.8),
+            opacity: 1.0,
+            blend: BlendMode::AlphaOver,
+            diffuse_texture: None,
+            texture: None,
+        }
+    }
+
+    /// Loads the materials of a Wavefront MTL file.
+    ///
+    /// Only the properties the renderer uses are read:
+    /// - `newmtl name` starts a material.
+    /// - `Kd r g b` sets the diffuse color.
+    /// - `d opacity` or `Tr transparency` set how opaque the material is.
+    /// - `map_Kd path` sets the diffuse texture, relative to the MTL file.
+    ///
+    /// # Arguments
+    /// - `filename`: The path to the `.mtl` file to load.
+    ///
+    /// # Returns
+    /// The materials in the order they are declared.
+    ///
+    /// # Errors
+    /// Returns a message if the file cannot be read or contains invalid data.
+    pub fn load_mtl(filename: &str) -> Result<Vec<Material>, String> {
+        let contents =
+            std::fs::read_to_string(filename).map_err(|e| format!("{}: {}", filename, e))?;
+        let mut materials: Vec<Material> = Vec::new();
+
+        for (number, line) in contents.lines().enumerate() {
+            let error = |message: &str| format!("{}:{}: {}", filename, number + 1, message);
+            let mut words = line.split_whitespace();
+            let keyword = match words.next() {
+                Some(keyword) => keyword,
+                None => continue,
+            };
+
+            if keyword == "newmtl" {
+                let name = words.next().ok_or_else(|| error("missing material name"))?;
+                materials.push(Material::new(name));
+                continue;
+            }
+
+            let material = match materials.last_mut() {
+                Some(material) => material,
+                None if keyword.starts_with('#') => continue,
+                None => return Err(error("property before the first `newmtl`")),
+            };
+            let mut number = || -> Result<f32, String> {
+                words
+                    .next()
+                    .and_then(|word| word.parse().ok())
+                    .ok_or_else(|| error(&format!("expected a number after `{}`", keyword)))
+            };
+
+            match keyword {
+                "Kd" => material.diffuse = Vec3::new(number()?, number()?, number()?),
+                "d" => material.opacity = number()?,
+                "Tr" => material.opacity = 1.0 - number()?,
+                "map_Kd" => {
+                    // Options such as `-s` may precede the path, which always comes last.
+                    // It is relative to the MTL file.
+                    let path = words.last().ok_or_else(|| error("missing texture path"))?;
+                    let directory = Path::new(filename).parent().unwrap_or(Path::new(""));
+                    material.diffuse_texture =
+                        Some(directory.join(path).to_string_lossy().into_owned());
+                }
+                _ => {}
+            }
+        }
+        Ok(materials)
+    }
+
+    /// Saves materials as a Wavefront MTL file, readable by `load_mtl`.
+    ///
+    /// # Arguments
+    /// - `filename`: The path of the `.mtl` file to write.
+    /// - `materials`: The materials to write.
+    /// - `precision`: The number of decimal places written for every number.
+    ///
+    /// # Errors
+    /// Returns a message if the file cannot be written.
+    pub fn save_mtl(
+        filename: &str,
+        materials: &[Material],
+        precision: usize,
+    ) -> Result<(), String> {
+        let directory = Path::new(filename).parent().unwrap_or(Path::new(""));
+        let p = precision;
+        let mut text = String::new();
+        for material in materials {
+            let kd = material.diffuse;
+            text += &format!("newmtl {}\n", material.name);
+            text += &format!("Kd {:.*} {:.*} {:.*}\n", p, kd.x, p, kd.y, p, kd.z);
+            text += &format!("d {:.*}\n", p, material.opacity);
+            if let Some(texture) = &material.diffuse_texture {
+                // Texture paths are stored resolved, but MTL files expect them relative.
+                let texture = Path::new(texture);
+                let relative = texture.strip_prefix(directory).unwrap_or(texture);
+                text += &format!("map_Kd {}\n", relative.display());
+            }
+            text += "\n";
+        }
+        std::fs::write(filename, text).map_err(|e| format!("{}: {}", filename, e))
+    }
+}
@@ -0,0 +1,177 @@
+// This file contains the 4x4 matrices that place meshes and scene nodes in the world.
+//
+// Only affine transformations are needed, so points and directions are transformed
+// without a projective divide.
+
+use std::ops::Mul;
+
+use crate::vector::Vec3;
+
+/// A 4x4 matrix used for affine transformations in 3D space.
+///
+/// The matrix is stored in row-major order and transforms column vectors, so
+/// `a * b` applies `b` first and `a` second.
+#[derive(Debug, Copy, Clone)]
+pub struct Mat4 {
+    /// The matrix elements, indexed as `m[row][column]`.
+    pub m: [[f32; 4]; 4],
+}
+
+#[allow(dead_code)]
+impl Mat4 {
+    /// Creates an identity matrix.
+    ///
+    /// # Returns
+    /// A `Mat4` that leaves points unchanged.
+    pub fn identity() -> Mat4 {
+        Mat4 {
+            m: [
+                [1.0, 0.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0, 0.0],
+                [0.0, 0.0, 1.0, 0.0],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+        }
+    }
+
+    /// Creates a translation matrix.
+    ///
+    /// # Arguments
+    /// - `offset`: The distance to move points along each axis.
+    ///
+    /// # Returns
+    /// A `Mat4` that moves points by `offset`.
+    pub fn translation(offset: Vec3) -> Mat4 {
+        let mut matrix = Mat4::identity();
+        matrix.m[0][3] = offset.x;
+        matrix.m[1][3] = offset.y;
+        matrix.m[2][3] = offset.z;
+        matrix
+    }
+
+    /// Creates a scale matrix.
+    ///
+    /// # Arguments
+    /// - `factors`: The scale factor along each axis.
+    ///
+    /// # Returns
+    /// A `Mat4` that scales points around the origin.
+    pub fn scale(factors: Vec3) -> Mat4 {
+        let mut matrix = Mat4::identity();
+        matrix.m[0][0] = factors.x;
+        matrix.m[1][1] = factors.y;
+        matrix.m[2][2] = factors.z;
+        matrix
+    }
+
+    /// Creates a rotation matrix around the X-axis, matching `Vec3::rotate_x`.
+    ///
+    /// # Arguments
+    /// - `angle`: The rotation angle in radians.
+    ///
+    /// # Returns
+    /// A `Mat4` that rotates points around the X-axis.
+    pub fn rotation_x(angle: f32) -> Mat4 {
+        let (sin, cos) = angle.sin_cos();
+        let mut matrix = Mat4::identity();
+        matrix.m[1][1] = cos;
+        matrix.m[1][2] = -sin;
+        matrix.m[2][1] = sin;
+        matrix.m[2][2] = cos;
+        matrix
+    }
+
+    /// Creates a rotation matrix around the Y-axis, matching `Vec3::rotate_y`.
+    ///
+    /// # Arguments
+    /// - `angle`: The rotation angle in radians.
+    ///
+    /// # Returns
+    /// A `Mat4` that rotates points around the Y-axis.
+    pub fn rotation_y(angle: f32) -> Mat4 {
+        let (sin, cos) = angle.sin_cos();
+        let mut matrix = Mat4::identity();
+        matrix.m[0][0] = cos;
+        matrix.m[0][2] = sin;
+        matrix.m[2][0] = -sin;
+        matrix.m[2][2] = cos;
+        matrix
+    }
+
+    /// Creates a rotation matrix around the Z-axis, matching `Vec3::rotate_z`.
+    ///
+    /// # Arguments
+    /// - `angle`: The rotation angle in radians.
+    ///
+    /// # Returns
+    /// A `Mat4` that rotates points around the Z-axis.
+    pub fn rotation_z(angle: f32) -> Mat4 {
+        let (sin, cos) = angle.sin_cos();
+        let mut matrix = Mat4::identity();
+        matrix.m[0][0] = cos;
+        matrix.m[0][1] = -sin;
+        matrix.m[1][0] = sin;
+        matrix.m[1][1] = cos;
+        matrix
+    }
+
+    /// Transforms a point, applying rotation, scale and translation.
+    ///
+    /// # Arguments
+    /// - `point`: The point to transform.
+    ///
+    /// # Returns
+    /// The transformed point.
+    pub fn transform_point(&self, point: Vec3) -> Vec3 {
+        let m = &self.m;
+        Vec3 {
+            x: m[0][0] * point.x + m[0][1] * point.y + m[0][2] * point.z + m[0][3],
+            y: m[1][0] * point.x + m[1][1] * point.y + m[1][2] * point.z + m[1][3],
+            z: m[2][0] * point.x + m[2][1] * point.y + m[2][2] * point.z + m[2][3],
+        }
+    }
+
+    /// Transforms a direction, applying rotation and scale but not translation.
+    ///
+    /// # Arguments
+    /// - `direction`: The direction to transform.
+    ///
+    /// # Returns
+    /// The transformed direction.
+    pub fn transform_direction(&self, direction: Vec3) -> Vec3 {
+        let m = &self.m;
+        Vec3 {
+            x: m[0][0] * direction.x + m[0][1] * direction.y + m[0][2] * direction.z,
+            y: m[1][0] * direction.x + m[1][1] * direction.y + m[1][2] * direction.z,
+            z: m[2][0] * direction.x + m[2][1] * direction.y + m[2][2] * direction.z,
+        }
+    }
+}
+
+/// Implements matrix multiplication for `Mat4`.
+///
+/// # Arguments
+/// - `self`: The transformation applied second.
+/// - `other`: The transformation applied first.
+///
+/// # Returns
+/// A new `Mat4` combining both transformations.
+///
+/// # Example
+/// ```
+/// let model = Mat4::translation(offset) * Mat4::rotation_y(angle);
+/// let world_point = model.transform_point(point); // Rotated, then moved.
+/// ```
+impl Mul for Mat4 {
+    type Output = Mat4;
+
+    fn mul(self, other: Mat4) -> Mat4 {
+        let mut result = Mat4 { m: [[0.0; 4]; 4] };
+        for row in 0..4 {
+            for col in 0..4 {
+                result.m[row][col] = (0..4).map(|k| self.m[row][k] * other.m[k][col]).sum();
+            }
+        }
+        result
+    }
+}
@@ -1,23 +1,52 @@
+use std::collections::HashMap;
 use std::io::Read;
 
+use crate::bounds::{Aabb, BoundingSphere};
+use crate::material::Material;
 use crate::triangle::Face;
 use crate::vector;
 
-/// Represents a 3D mesh, composed of vertices and faces, along with
-/// attributes for rotation, scale, and translation.
+/// Represents a 3D mesh, composed of vertices and faces.
+///
+/// A mesh only holds geometry; where it is drawn is decided by the scene nodes that
+/// reference it.
 pub struct Mesh {
     /// List of vertices (`Vec3`) that define the 3D geometry of the mesh.
     pub vertices: Vec<vector::Vec3>,
     /// List of faces (`Face`) that define how the vertices are connected into triangles.
     pub faces: Vec<Face>,
-    /// Rotation of the mesh in 3D space (around x, y, and z axes).
-    pub rotation: vector::Vec3,
-    /// Scale of the mesh in 3D space (along x, y, and z axes).
-    pub scale: vector::Vec3,
-    /// Translation (position) of the mesh in 3D space.
-    pub translation: vector::Vec3,
+    /// Normal of each face as stored in the source file, in the same order as `faces`.
+    /// Empty when the file does not provide them.
+    pub face_normals: Vec<vector::Vec3>,
+    /// Normal of each vertex, in the same order as `vertices`. Empty when the file does
+    /// not provide them.
+    pub vertex_normals: Vec<vector::Vec3>,
+    /// Texture coordinates of each vertex, in the same order as `vertices`. Empty when
+    /// the file does not provide them.
+    pub vertex_uvs: Vec<vector::Vec2>,
+    /// Color of each vertex, sRGB-encoded with each channel from 0.0 to 1.0, in the same
+    /// order as `vertices`. Empty when the file does not provide them.
+    pub vertex_colors: Vec<vector::Vec3>,
+    /// Named runs of faces, in the order of their first face. Faces before the first
+    /// group belong to no group.
+    pub groups: Vec<FaceGroup>,
 }
 
+/// A run of consecutive faces sharing a name and material, as declared by `g` and
+/// `usemtl` lines in OBJ files. A group extends up to the first face of the next one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FaceGroup {
+    /// Name of the group, empty when only a material was given.
+    pub name: String,
+    /// Name of the material the faces use, if any.
+    pub material: Option<String>,
+    /// Index of the group's first face in `Mesh::faces`.
+    pub first_face: usize,
+}
+
+/// Number of decimal places `Mesh::save` writes in text formats.
+pub const SAVE_PRECISION: usize = 6;
+
 /// Number of vertices in a cube.
 pub const N_CUBE_VERTICES: usize = 8;
 /// Number of faces in a cube (6 sides, 2 triangles per side).
@@ -59,34 +88,89 @@ pub const CUBE_FACES: [Face; N_CUBE_FACES] = [
 ];
 
 impl Mesh {
+    /// Creates a new `Mesh` from its geometry, without any per-face or per-vertex
+    /// attributes.
+    ///
+    /// # Arguments
+    /// - `vertices`: The positions of the vertices.
+    /// - `faces`: The triangles, as 1-based indices into `vertices`.
+    ///
+    /// # Returns
+    /// A new `Mesh`.
+    pub fn new(vertices: Vec<vector::Vec3>, faces: Vec<Face>) -> Mesh {
+        Mesh {
+            vertices,
+            faces,
+            face_normals: Vec::new(),
+            vertex_normals: Vec::new(),
+            vertex_uvs: Vec::new(),
+            vertex_colors: Vec::new(),
+            groups: Vec::new(),
+        }
+    }
+
     /// Creates a new cube mesh with predefined vertices and faces.
     ///
     /// # Returns
     /// A `Mesh` instance representing a cube.
     pub fn new_cube() -> Mesh {
-        let mut vertices: Vec<vector::Vec3> = Vec::new();
-        let mut faces: Vec<Face> = Vec::new();
+        let vertices: Vec<vector::Vec3> = CUBE_VERTICES.to_vec();
+        let faces: Vec<Face> = CUBE_FACES.to_vec();
+
+        Mesh::new(vertices, faces)
+    }
 
-        for i in 0..N_CUBE_VERTICES {
-            vertices.push(CUBE_VERTICES[i]);
+    /// Calculates the axis-aligned bounding box of the mesh's vertices.
+    ///
+    /// # Returns
+    /// The box in the mesh's own coordinates, or `None` if the mesh has no vertices.
+    pub fn aabb(&self) -> Option<Aabb> {
+        Aabb::from_points(&self.vertices)
+    }
+
+    /// Calculates a sphere enclosing the mesh's vertices.
+    ///
+    /// # Returns
+    /// The sphere in the mesh's own coordinates, or `None` if the mesh has no vertices.
+    pub fn bounding_sphere(&self) -> Option<BoundingSphere> {
+        BoundingSphere::from_points(&self.vertices)
+    }
+
+    /// Moves the vertices so the center of the bounding box is at the origin.
+    pub fn center(&mut self) {
+        if let Some(aabb) = self.aabb() {
+            let center = aabb.center();
+            for vertex in &mut self.vertices {
+                *vertex = *vertex - center;
+            }
         }
+    }
 
-        for i in 0..N_CUBE_FACES {
-            faces.push(CUBE_FACES[i]);
+    /// Scales the mesh uniformly around the origin so the largest side of its bounding
+    /// box is `size` long, whatever units the source file used.
+    ///
+    /// # Arguments
+    /// - `size`: The length of the largest side after scaling.
+    pub fn normalize(&mut self, size: f32) {
+        let extent = match self.aabb() {
+            Some(aabb) => aabb.size(),
+            None => return,
+        };
+        let largest = extent.x.max(extent.y).max(extent.z);
+        if largest <= 0.0 {
+            return; // A single point cannot be scaled to any size.
         }
 
-        Mesh {
-            vertices,
-            faces,
-            rotation: vector::Vec3::new(0.0, 0.0, 0.0), // No rotation by default.
-            scale: vector::Vec3::new(1.0, 1.0, 1.0),    // Default scale is 1.
-            translation: vector::Vec3::new(0.0, 0.0, 0.0), // Default position is the origin.
+        let factor = size / largest;
+        for vertex in &mut self.vertices {
+            *vertex = *vertex * factor; // Uniform scaling leaves the normals unchanged.
         }
     }
 
-    /// Loads a mesh from a file in a simple format:
-    /// - Lines starting with "v" define a vertex: `v x y z`.
-    /// - Lines starting with "f" define a face: `f a/b/c`.
+    /// Loads a mesh from a file, choosing the format from its extension.
+    ///
+    /// Files ending in `.stl` are read as STL and files ending in `.ply` as PLY; anything
+    /// else is read as Wavefront OBJ.
     ///
     /// # Arguments
     /// - `filename`: The path to the file to load.
@@ -94,60 +178,494 @@ impl Mesh {
     /// # Returns
     /// A `Mesh` instance loaded from the file.
     ///
-    /// # Panics
-    /// This function panics if the file cannot be read or contains invalid data.
-    pub fn load_from_file(filename: &str) -> Mesh {
-        let mut vertices: Vec<vector::Vec3> = Vec::new();
-        let mut faces: Vec<Face> = Vec::new();
+    /// # Errors
+    /// Returns a message if the file cannot be read or contains invalid data.
+    pub fn load(filename: &str) -> Result<Mesh, String> {
+        let extension = std::path::Path::new(filename)
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase());
+        match extension.as_deref() {
+            Some("stl") => Mesh::load_stl(filename),
+            Some("ply") => Mesh::load_ply(filename),
+            _ => Mesh::load_from_file(filename),
+        }
+    }
+
+    /// Saves the mesh, choosing the format from the file's extension.
+    ///
+    /// # Arguments
+    /// - `filename`: The path of the file to write; it must end in `.obj` or `.stl`. OBJ
+    ///   files are written with `SAVE_PRECISION` decimal places, and the materials of
+    ///   their groups with default properties.
+    ///
+    /// # Errors
+    /// Returns a message if the extension is not recognized or the file cannot be
+    /// written.
+    pub fn save(&self, filename: &str) -> Result<(), String> {
+        let extension = std::path::Path::new(filename)
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase());
+        match extension.as_deref() {
+            Some("obj") => self.save_obj(filename, &[], SAVE_PRECISION),
+            Some("stl") => self.save_stl(filename),
+            _ => Err(format!("{}: expected a .obj or .stl file", filename)),
+        }
+    }
 
-        let mut file = std::fs::File::open(filename).unwrap();
+    /// Calculates the unit normal of a face from its vertices, following the
+    /// counter-clockwise winding convention used by mesh files.
+    ///
+    /// # Arguments
+    /// - `face`: The face, whose indices must refer to vertices of this mesh.
+    ///
+    /// # Returns
+    /// The normal of the face, or a zero vector if the face is degenerate.
+    pub fn compute_face_normal(&self, face: &Face) -> vector::Vec3 {
+        let a = self.vertices[face.a - 1];
+        let b = self.vertices[face.b - 1];
+        let c = self.vertices[face.c - 1];
+        let normal = (b - a).cross(c - a);
+        if normal.len() == 0.0 {
+            normal
+        } else {
+            normal.normalize()
+        }
+    }
+
+    /// Loads a mesh from a Wavefront OBJ file:
+    /// - `v x y z` defines a vertex position.
+    /// - `vt u v` and `vn x y z` define texture coordinates and normals.
+    /// - `f a b c ...` defines a face, where each corner is `v`, `v/vt`, `v//vn` or
+    ///   `v/vt/vn`. Polygons are split into fans of triangles, and negative indices
+    ///   count back from the last element read.
+    /// - `g name`, `o name` and `usemtl name` start a new face group.
+    ///
+    /// When faces reference texture coordinates or normals, every distinct combination
+    /// of position, texture coordinate and normal becomes a vertex of its own, so they
+    /// can be stored per vertex.
+    ///
+    /// # Arguments
+    /// - `filename`: The path to the file to load.
+    ///
+    /// # Returns
+    /// A `Mesh` instance loaded from the file.
+    ///
+    /// # Errors
+    /// Returns a message if the file cannot be read or contains invalid data.
+    pub fn load_from_file(filename: &str) -> Result<Mesh, String> {
+        let mut positions: Vec<vector::Vec3> = Vec::new();
+        let mut uvs: Vec<vector::Vec2> = Vec::new();
+        let mut normals: Vec<vector::Vec3> = Vec::new();
+        // Corners of every triangle as 1-based (position, uv, normal) indices, where 0
+        // means the attribute is missing.
+        let mut corners: Vec<[usize; 3]> = Vec::new();
+        let mut groups: Vec<FaceGroup> = Vec::new();
+
+        let mut file = std::fs::File::open(filename).map_err(|e| format!("{}: {}", filename, e))?;
         let mut contents = String::new();
-        file.read_to_string(&mut contents).unwrap();
+        file.read_to_string(&mut contents)
+            .map_err(|e| format!("{}: {}", filename, e))?;
 
         let lines = contents.lines();
 
-        for line in lines {
+        for (number, line) in lines.enumerate() {
+            let error = |message: &str| format!("{}:{}: {}", filename, number + 1, message);
             let mut words = line.split_whitespace();
             let result = words.next();
             if result.is_none() {
                 continue;
             }
 
+            let mut number = |count: usize| -> Result<Vec<f32>, String> {
+                (0..count)
+                    .map(|_| words.next().and_then(|word| word.parse().ok()))
+                    .collect::<Option<Vec<f32>>>()
+                    .ok_or_else(|| error(&format!("expected {} numbers", count)))
+            };
             match result.unwrap() {
                 "v" => {
                     // Parse vertex line: v x y z
-                    let x: f32 = words.next().unwrap().parse().unwrap();
-                    let y: f32 = words.next().unwrap().parse().unwrap();
-                    let z: f32 = words.next().unwrap().parse().unwrap();
-                    vertices.push(vector::Vec3::new(x, y, z));
+                    let v = number(3)?;
+                    positions.push(vector::Vec3::new(v[0], v[1], v[2]));
+                }
+                "vt" => {
+                    let uv = number(2)?;
+                    uvs.push(vector::Vec2::new(uv[0], uv[1]));
+                }
+                "vn" => {
+                    let n = number(3)?;
+                    normals.push(vector::Vec3::new(n[0], n[1], n[2]));
                 }
                 "f" => {
-                    // Parse face line: f a/b/c
-                    let mut face = Face::new(0, 0, 0);
-                    let mut i = 0;
-                    for word in words {
-                        let mut indices = word.split('/');
-                        let index: usize = indices.next().unwrap().parse().unwrap();
-                        match i {
-                            0 => face.a = index,
-                            1 => face.b = index,
-                            2 => face.c = index,
-                            _ => {}
+                    // Parse face line: f v/vt/vn v/vt/vn v/vt/vn ...
+                    let mut polygon: Vec<[usize; 3]> = Vec::new();
+                    for word in line.split_whitespace().skip(1) {
+                        let invalid = || error(&format!("invalid face index `{}`", word));
+                        let mut corner = [0; 3];
+                        let counts = [positions.len(), uvs.len(), normals.len()];
+                        for (i, index) in word.split('/').enumerate().take(3) {
+                            if index.is_empty() && i > 0 {
+                                continue; // `v//vn` leaves the texture coordinate out.
+                            }
+                            let index: i64 = index.parse().map_err(|_| invalid())?;
+                            corner[i] = if index < 0 {
+                                (counts[i] as i64 + 1 + index).max(0) as usize
+                            } else {
+                                index as usize
+                            };
+                            if corner[i] == 0 {
+                                return Err(invalid());
+                            }
                         }
-                        i += 1;
+                        polygon.push(corner);
+                    }
+                    if polygon.len() < 3 {
+                        return Err(error("a face needs at least three vertices"));
+                    }
+                    for i in 1..polygon.len() - 1 {
+                        corners.extend([polygon[0], polygon[i], polygon[i + 1]]);
+                    }
+                }
+                keyword @ ("g" | "o" | "usemtl") => {
+                    let name = line
+                        .split_whitespace()
+                        .skip(1)
+                        .collect::<Vec<_>>()
+                        .join(" ");
+                    let (mut group_name, mut material) = match groups.last() {
+                        Some(group) => (group.name.clone(), group.material.clone()),
+                        None => (String::new(), None),
+                    };
+                    if keyword == "usemtl" {
+                        material = Some(name);
+                    } else {
+                        group_name = name;
+                    }
+                    let group = FaceGroup {
+                        name: group_name,
+                        material,
+                        first_face: corners.len() / 3,
+                    };
+                    // A group without faces is replaced rather than kept empty.
+                    match groups.last_mut() {
+                        Some(last) if last.first_face == group.first_face => *last = group,
+                        _ => groups.push(group),
                     }
-                    faces.push(face);
                 }
                 _ => {}
             }
         }
 
-        Mesh {
-            vertices,
-            faces,
-            rotation: vector::Vec3::new(0.0, 0.0, 0.0), // Default rotation.
-            scale: vector::Vec3::new(1.0, 1.0, 1.0),    // Default scale.
-            translation: vector::Vec3::new(0.0, 0.0, 0.0), // Default translation.
+        // Indices are 1-based and must refer to an element of the file.
+        let counts = [positions.len(), uvs.len(), normals.len()];
+        let names = ["vertex", "texture coordinate", "normal"];
+        for corner in &corners {
+            for i in 0..3 {
+                if corner[i] > counts[i] {
+                    return Err(format!(
+                        "{}: face refers to missing {} {}",
+                        filename, names[i], corner[i]
+                    ));
+                }
+            }
+        }
+
+        let has_uvs = corners.iter().any(|corner| corner[1] != 0);
+        let has_normals = corners.iter().any(|corner| corner[2] != 0);
+        let triangles = |corners: &[usize]| -> Vec<Face> {
+            corners
+                .chunks_exact(3)
+                .map(|c| Face::new(c[0], c[1], c[2]))
+                .collect()
+        };
+        if !has_uvs && !has_normals {
+            let indices: Vec<usize> = corners.iter().map(|corner| corner[0]).collect();
+            let mut mesh = Mesh::new(positions, triangles(&indices));
+            mesh.groups = groups;
+            return Ok(mesh);
+        }
+
+        // Give every distinct corner its own vertex, with its own attributes.
+        let mut vertices: Vec<vector::Vec3> = Vec::new();
+        let mut vertex_uvs: Vec<vector::Vec2> = Vec::new();
+        let mut vertex_normals: Vec<vector::Vec3> = Vec::new();
+        let mut indices_of: HashMap<[usize; 3], usize> = HashMap::new();
+        let mut indices: Vec<usize> = Vec::with_capacity(corners.len());
+        for corner in &corners {
+            let index = *indices_of.entry(*corner).or_insert_with(|| {
+                vertices.push(positions[corner[0] - 1]);
+                if has_uvs {
+                    vertex_uvs.push(match corner[1] {
+                        0 => vector::Vec2::new(0.0, 0.0),
+                        uv => uvs[uv - 1],
+                    });
+                }
+                if has_normals {
+                    vertex_normals.push(match corner[2] {
+                        0 => vector::Vec3::new(0.0, 0.0, 0.0),
+                        normal => normals[normal - 1],
+                    });
+                }
+                vertices.len()
+            });
+            indices.push(index);
+        }
+
+        let mut mesh = Mesh::new(vertices, triangles(&indices));
+        mesh.vertex_uvs = vertex_uvs;
+        mesh.vertex_normals = vertex_normals;
+        mesh.groups = groups;
+        Ok(mesh)
+    }
+
+    /// Saves the mesh as a Wavefront OBJ file, readable by `load_from_file`.
+    ///
+    /// Texture coordinates and normals are written when the mesh has them, and face
+    /// groups keep their names and materials. When any group uses a material, the
+    /// materials are written to an `.mtl` file next to the OBJ file, with the same name.
+    ///
+    /// # Arguments
+    /// - `filename`: The path of the `.obj` file to write.
+    /// - `materials`: Where the properties of the groups' materials are looked up.
+    ///   Materials that are not found are written with default properties.
+    /// - `precision`: The number of decimal places written for every number.
+    ///
+    /// # Errors
+    /// Returns a message if a file cannot be written.
+    pub fn save_obj(
+        &self,
+        filename: &str,
+        materials: &[Material],
+        precision: usize,
+    ) -> Result<(), String> {
+        let path = std::path::Path::new(filename);
+        let mut used: Vec<Material> = Vec::new();
+        for name in self
+            .groups
+            .iter()
+            .filter_map(|group| group.material.as_ref())
+        {
+            if used.iter().all(|material| &material.name != name) {
+                used.push(
+                    match materials.iter().find(|material| &material.name == name) {
+                        Some(material) => material.clone(),
+                        None => Material::new(name),
+                    },
+                );
+            }
+        }
+
+        let mtl_path = path.with_extension("mtl");
+        let mtl_name = mtl_path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned());
+        let library = match (used.is_empty(), mtl_name) {
+            (false, Some(name)) => {
+                Material::save_mtl(&mtl_path.to_string_lossy(), &used, precision)?;
+                Some(name)
+            }
+            _ => None,
+        };
+
+        std::fs::write(path, self.to_obj(library.as_deref(), precision))
+            .map_err(|e| format!("{}: {}", filename, e))
+    }
+
+    /// Encodes the mesh as the contents of a Wavefront OBJ file.
+    ///
+    /// # Arguments
+    /// - `material_library`: The `.mtl` file to reference with `mtllib`, if any.
+    /// - `precision`: The number of decimal places written for every number.
+    ///
+    /// # Returns
+    /// The text of the file.
+    pub fn to_obj(&self, material_library: Option<&str>, precision: usize) -> String {
+        let mut text = String::new();
+        if let Some(library) = material_library {
+            text += &format!("mtllib {}\n", library);
+        }
+
+        let p = precision;
+        for v in &self.vertices {
+            text += &format!("v {:.*} {:.*} {:.*}\n", p, v.x, p, v.y, p, v.z);
+        }
+        for uv in &self.vertex_uvs {
+            text += &format!("vt {:.*} {:.*}\n", p, uv.x, p, uv.y);
+        }
+        for n in &self.vertex_normals {
+            text += &format!("vn {:.*} {:.*} {:.*}\n", p, n.x, p, n.y, p, n.z);
+        }
+
+        // Attributes are stored per vertex, so every index of a corner is the same.
+        let has_uvs = !self.vertex_uvs.is_empty();
+        let has_normals = !self.vertex_normals.is_empty();
+        let corner = |index: usize| match (has_uvs, has_normals) {
+            (false, false) => format!("{}", index),
+            (true, false) => format!("{}/{}", index, index),
+            (false, true) => format!("{}//{}", index, index),
+            (true, true) => format!("{}/{}/{}", index, index, index),
+        };
+
+        let mut groups = self.groups.iter().peekable();
+        for (i, face) in self.faces.iter().enumerate() {
+            while let Some(group) = groups.next_if(|group| group.first_face <= i) {
+                if !group.name.is_empty() {
+                    text += &format!("g {}\n", group.name);
+                }
+                if let Some(material) = &group.material {
+                    text += &format!("usemtl {}\n", material);
+                }
+            }
+            text += &format!(
+                "f {} {} {}\n",
+                corner(face.a),
+                corner(face.b),
+                corner(face.c)
+            );
         }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks that a value read back from a file is the one written, rounded to
+    /// `precision` decimal places.
+    fn assert_written(read: f32, written: f32, precision: usize) {
+        let tolerance = 0.5 * 10f32.powi(-(precision as i32)) + 1e-6;
+        assert!(
+            (read - written).abs() <= tolerance,
+            "read {} for {} written with {} decimal places",
+            read,
+            written,
+            precision
+        );
+    }
+
+    #[test]
+    fn save_obj_round_trips_with_load_from_file() {
+        let directory =
+            std::env::temp_dir().join(format!("rusty-renderer-obj-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let obj_path = directory.join("quad.obj");
+        let mtl_path = directory.join("quad.mtl");
+        let texture = directory.join("red.ppm").to_string_lossy().into_owned();
+
+        let mut mesh = Mesh::new(
+            vec![
+                vector::Vec3::new(-1.23456, 0.0, 0.5),
+                vector::Vec3::new(1.0, 0.000449, 0.5),
+                vector::Vec3::new(1.0, 2.5, -0.5),
+                vector::Vec3::new(-1.0, 1.0, -0.33333),
+            ],
+            vec![Face::new(1, 2, 3), Face::new(1, 3, 4)],
+        );
+        mesh.vertex_uvs = vec![
+            vector::Vec2::new(0.0, 0.0),
+            vector::Vec2::new(0.9996, 0.0),
+            vector::Vec2::new(1.0, 1.0),
+            vector::Vec2::new(0.125, 0.875),
+        ];
+        mesh.vertex_normals = vec![vector::Vec3::new(0.0, 0.0, 1.0); 4];
+        mesh.vertex_normals[2] = vector::Vec3::new(0.0, 0.6, 0.8);
+        mesh.groups = vec![
+            FaceGroup {
+                name: "front".to_string(),
+                material: Some("red".to_string()),
+                first_face: 0,
+            },
+            FaceGroup {
+                name: "back".to_string(),
+                material: Some("plain".to_string()),
+                first_face: 1,
+            },
+        ];
+        let red = Material {
+            diffuse: vector::Vec3::new(0.8, 0.1234, 0.0),
+            opacity: 0.5,
+            diffuse_texture: Some(texture.clone()),
+            ..Material::new("red")
+        };
+
+        let precision = 3;
+        mesh.save_obj(&obj_path.to_string_lossy(), &[red], precision)
+            .unwrap();
+        let obj = std::fs::read_to_string(&obj_path).unwrap();
+        let loaded = Mesh::load_from_file(&obj_path.to_string_lossy());
+        let materials = Material::load_mtl(&mtl_path.to_string_lossy());
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        assert!(obj.starts_with("mtllib quad.mtl\n"));
+        let loaded = loaded.unwrap();
+        assert_eq!(loaded.faces, mesh.faces);
+        assert_eq!(loaded.groups, mesh.groups);
+        assert_eq!(loaded.vertices.len(), mesh.vertices.len());
+        for (read, written) in loaded.vertices.iter().zip(&mesh.vertices) {
+            assert_written(read.x, written.x, precision);
+            assert_written(read.y, written.y, precision);
+            assert_written(read.z, written.z, precision);
+        }
+        for (read, written) in loaded.vertex_uvs.iter().zip(&mesh.vertex_uvs) {
+            assert_written(read.x, written.x, precision);
+            assert_written(read.y, written.y, precision);
+        }
+        for (read, written) in loaded.vertex_normals.iter().zip(&mesh.vertex_normals) {
+            assert_written(read.x, written.x, precision);
+            assert_written(read.y, written.y, precision);
+            assert_written(read.z, written.z, precision);
+        }
+        assert_eq!(loaded.vertex_uvs.len(), 4);
+        assert_eq!(loaded.vertex_normals.len(), 4);
+
+        // Only the materials the groups use are written, in the order they are used;
+        // `plain` is not among the given materials and gets default properties.
+        let materials = materials.unwrap();
+        assert_eq!(materials.len(), 2);
+        assert_eq!(materials[0].name, "red");
+        assert_eq!(materials[0].diffuse, vector::Vec3::new(0.8, 0.123, 0.0));
+        assert_eq!(materials[0].opacity, 0.5);
+        assert_eq!(materials[0].diffuse_texture, Some(texture));
+        assert_eq!(materials[1].name, "plain");
+        assert_eq!(materials[1].diffuse, Material::new("plain").diffuse);
+        assert_eq!(materials[1].diffuse_texture, None);
+    }
+
+    #[test]
+    fn to_obj_writes_the_requested_precision() {
+        let mut mesh = Mesh::new(
+            vec![
+                vector::Vec3::new(0.0, 0.0, 0.0),
+                vector::Vec3::new(1.0, 0.0, 0.0),
+                vector::Vec3::new(0.0, 1.0 / 3.0, 0.0),
+            ],
+            vec![Face::new(1, 2, 3)],
+        );
+        mesh.groups.push(FaceGroup {
+            name: "tri".to_string(),
+            material: None,
+            first_face: 0,
+        });
+
+        assert_eq!(
+            mesh.to_obj(None, 2),
+            "v 0.00 0.00 0.00\nv 1.00 0.00 0.00\nv 0.00 0.33 0.00\ng tri\nf 1 2 3\n"
+        );
+    }
+
+    #[test]
+    fn save_obj_skips_the_library_without_materials() {
+        let path = std::env::temp_dir().join(format!("rusty-renderer-{}.obj", std::process::id()));
+        let filename = path.to_string_lossy();
+        Mesh::new_cube().save(&filename).unwrap();
+        let mtl_exists = path.with_extension("mtl").exists();
+        let loaded = Mesh::load(&filename);
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(!mtl_exists);
+        let loaded = loaded.unwrap();
+        assert_eq!(loaded.vertices, Mesh::new_cube().vertices);
+        assert_eq!(loaded.faces, Mesh::new_cube().faces);
     }
 }
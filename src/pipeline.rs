@@ -0,0 +1,367 @@
+// This file contains the geometry stages of the pipeline, which turn mesh instances into
+// screen-space triangles for the rasterizer.
+//
+// The vertex stage transforms each vertex of a mesh exactly once into view space, into a
+// post-transform cache. Primitive assembly then gathers the cached vertices of every face
+// to cull, light, clip and project it. Both stages split their work into chunks run on
+// separate threads, and keep the faces in order so the frame does not depend on the
+// number of threads.
+
+use crate::blend::BlendMode;
+use crate::camera::NEAR_PLANE;
+use crate::color::{self, LinearColor};
+use crate::display::{WINDOW_HEIGHT, WINDOW_WIDTH};
+use crate::light::DirectionalLight;
+use crate::matrix::Mat4;
+use crate::mesh::Mesh;
+use crate::texture::TextureId;
+use crate::triangle::Triangle;
+use crate::vector::{Vec2, Vec3};
+
+/// Fewest vertices or faces worth handing to another thread; smaller meshes are
+/// processed on the calling thread, where starting threads would cost more than it saves.
+const MIN_CHUNK: usize = 4096;
+
+/// How a mesh instance is shaded during primitive assembly.
+pub struct Shading<'a> {
+    /// The color of the whole mesh in linear light, used when it has no vertex colors.
+    pub diffuse: Vec3,
+    /// Whether the mesh's vertex colors are used instead of `diffuse`, if it has any.
+    /// They are stored as authored, in sRGB, and decoded here.
+    pub vertex_colors: bool,
+    /// The alpha of the mesh's colors, from 0.0 to 1.0.
+    pub opacity: f32,
+    /// How the mesh's colors are combined with the frame.
+    pub blend: BlendMode,
+    /// The texture multiplying the mesh's colors, used if the mesh has texture
+    /// coordinates.
+    pub texture: Option<TextureId>,
+    /// Light reaching every surface, from 0.0 to 1.0.
+    pub ambient: f32,
+    /// The lights of the scene, with their directions in view space and their colors in
+    /// linear light.
+    pub lights: &'a [DirectionalLight],
+}
+
+/// Triangles produced by primitive assembly, with counts of the faces left out.
+#[derive(Default)]
+pub struct Assembly {
+    /// The projected triangles, in the order of the mesh's faces.
+    pub triangles: Vec<Triangle>,
+    /// Number of faces discarded because they face away from the camera.
+    pub culled: usize,
+    /// Number of faces reaching behind the near plane, which are cut at it, or
+    /// discarded if they lie wholly behind it.
+    pub clipped: usize,
+}
+
+/// A corner of a face being clipped against the near plane.
+#[derive(Copy, Clone)]
+struct ClipVertex {
+    /// The position in view space.
+    position: Vec3,
+    /// The color in linear light, before lighting.
+    color: Vec3,
+    /// The texture coordinates.
+    uv: Vec2,
+}
+
+impl ClipVertex {
+    /// Interpolates between two corners. Attributes vary linearly across a face in view
+    /// space, as the rasterizer's perspective-correct blending assumes.
+    fn lerp(&self, other: ClipVertex, t: f32) -> ClipVertex {
+        ClipVertex {
+            position: self.position.lerp(other.position, t),
+            color: self.color.lerp(other.color, t),
+            uv: self.uv + (other.uv - self.uv) * t,
+        }
+    }
+}
+
+/// Splits `count` items into chunks for up to `threads` threads.
+///
+/// # Returns
+/// The number of items in each chunk, at least 1.
+fn chunk_size(count: usize, threads: usize) -> usize {
+    count.div_ceil(threads.max(1)).max(MIN_CHUNK)
+}
+
+/// Projects a view-space point onto the screen.
+///
+/// # Arguments
+/// - `point`: The point in view space, in front of the camera.
+/// - `fov_factor`: The projection's field of view factor, in pixels per unit at a depth
+///   of 1.
+///
+/// # Returns
+/// The position of the point in the window, in pixels.
+pub fn project(point: Vec3, fov_factor: f32) -> Vec2 {
+    Vec2::new(
+        fov_factor * point.x / point.z + WINDOW_WIDTH as f32 / 2.0,
+        fov_factor * point.y / point.z + WINDOW_HEIGHT as f32 / 2.0,
+    )
+}
+
+/// Clips a triangle against the near plane, keeping the part in front of it.
+///
+/// # Arguments
+/// - `corners`: The triangle's corners in view space.
+///
+/// # Returns
+/// The corners of the part in front, in the triangle's winding order, and how many of
+/// them there are: 0 if the triangle lies wholly behind the plane, 4 if exactly one
+/// corner does, and 3 otherwise.
+fn clip_near(corners: [ClipVertex; 3]) -> ([ClipVertex; 4], usize) {
+    let mut clipped = [corners[0]; 4];
+    let mut count = 0;
+    for (i, &current) in corners.iter().enumerate() {
+        let next = corners[(i + 1) % 3];
+        let current_inside = current.position.z >= NEAR_PLANE;
+        if current_inside {
+            clipped[count] = current;
+            count += 1;
+        }
+        if current_inside != (next.position.z >= NEAR_PLANE) {
+            let t = (NEAR_PLANE - current.position.z) / (next.position.z - current.position.z);
+            clipped[count] = current.lerp(next, t);
+            // Exactly on the plane, whatever the rounding, so it can be projected.
+            clipped[count].position.z = NEAR_PLANE;
+            count += 1;
+        }
+    }
+    (clipped, count)
+}
+
+/// Transforms every vertex of a mesh into view space.
+///
+/// # Arguments
+/// - `vertices`: The vertices in the mesh's own coordinates.
+/// - `model_view`: The transformation from the mesh's coordinates into view space.
+/// - `threads`: The number of threads to split the vertices between.
+///
+/// # Returns
+/// The transformed vertices, in the same order.
+pub fn transform_vertices(vertices: &[Vec3], model_view: &Mat4, threads: usize) -> Vec<Vec3> {
+    let mut transformed = vec![Vec3::new(0.0, 0.0, 0.0); vertices.len()];
+    let chunk = chunk_size(vertices.len(), threads);
+    let transform = |source: &[Vec3], target: &mut [Vec3]| {
+        for (vertex, view_vertex) in source.iter().zip(target) {
+            *view_vertex = model_view.transform_point(*vertex);
+        }
+    };
+
+    if chunk >= vertices.len() {
+        transform(vertices, &mut transformed);
+    } else {
+        std::thread::scope(|scope| {
+            for (source, target) in vertices.chunks(chunk).zip(transformed.chunks_mut(chunk)) {
+                scope.spawn(move || transform(source, target));
+            }
+        });
+    }
+    transformed
+}
+
+/// Culls, lights, clips and projects the faces of a mesh.
+///
+/// # Arguments
+/// - `mesh`: The mesh whose faces to assemble.
+/// - `view_vertices`: The mesh's vertices in view space, from `transform_vertices`.
+/// - `shading`: How to color the faces.
+/// - `backface_culling`: Whether faces pointing away from the camera are discarded.
+/// - `fov_factor`: The projection's field of view factor.
+/// - `threads`: The number of threads to split the faces between.
+///
+/// # Returns
+/// The triangles to draw, more than one for faces cut at the near plane, and the
+/// number of faces culled or clipped.
+pub fn assemble_triangles(
+    mesh: &Mesh,
+    view_vertices: &[Vec3],
+    shading: &Shading,
+    backface_culling: bool,
+    fov_factor: f32,
+    threads: usize,
+) -> Assembly {
+    let use_vertex_colors = shading.vertex_colors && !mesh.vertex_colors.is_empty();
+    let alpha = shading.opacity.clamp(0.0, 1.0);
+    let texture = shading.texture.filter(|_| !mesh.vertex_uvs.is_empty());
+    // Decode each vertex color once, rather than once for every face sharing it.
+    let vertex_colors: Vec<Vec3> = if use_vertex_colors {
+        mesh.vertex_colors
+            .iter()
+            .map(|&rgb| color::srgb_to_linear_rgb(rgb))
+            .collect()
+    } else {
+        Vec::new()
+    };
+    let assemble = |faces: &[crate::triangle::Face]| {
+        let mut assembly = Assembly::default();
+        for face in faces {
+            let indices = [face.a - 1, face.b - 1, face.c - 1];
+            let vertices = indices.map(|index| view_vertices[index]);
+            let colors = if use_vertex_colors {
+                indices.map(|index| vertex_colors[index])
+            } else {
+                [shading.diffuse; 3]
+            };
+
+            // The camera sits at the origin of view space, so the triangle faces away
+            // from it when its normal points the same way as the ray to a vertex.
+            let [a, b, c] = vertices;
+            let normal = (b - a).cross(c - a);
+            let dot_camera = normal.dot(-a);
+            if backface_culling && dot_camera < 0.0 {
+                assembly.culled += 1;
+                continue;
+            }
+
+            // Flat shading: light the whole face by the ambient light and every light
+            // shining on its side facing the camera. Vertex colors are still blended
+            // across the face by the rasterizer. Lit colors may exceed 1.0; the frame is
+            // tone mapped once it is drawn.
+            let facing_normal = if dot_camera < 0.0 { -normal } else { normal }.normalize();
+            let light = shading.lights.iter().fold(
+                Vec3::new(shading.ambient, shading.ambient, shading.ambient),
+                |light, scene_light| light + scene_light.illuminate(facing_normal),
+            );
+
+            // Cut off the part behind the near plane, which cannot be projected, and
+            // split what is left into a fan of triangles.
+            let corners = [0, 1, 2].map(|i| ClipVertex {
+                position: vertices[i],
+                color: colors[i],
+                uv: match texture {
+                    Some(_) => mesh.vertex_uvs[indices[i]],
+                    None => Vec2::new(0.0, 0.0),
+                },
+            });
+            let (corners, count) = if vertices.iter().any(|v| v.z < NEAR_PLANE) {
+                assembly.clipped += 1;
+                clip_near(corners)
+            } else {
+                ([corners[0], corners[1], corners[2], corners[0]], 3)
+            };
+            for i in 1..count.saturating_sub(1) {
+                let fan = [corners[0], corners[i], corners[i + 1]];
+                let triangle = Triangle::new(
+                    fan.map(|corner| project(corner.position, fov_factor)),
+                    fan.map(|corner| 1.0 / corner.position.z),
+                    fan.map(|corner| {
+                        LinearColor::new(
+                            corner.color.x * light.x,
+                            corner.color.y * light.y,
+                            corner.color.z * light.z,
+                            alpha,
+                        )
+                    }),
+                    shading.blend,
+                );
+                assembly.triangles.push(match texture {
+                    Some(texture) => triangle.with_texture(texture, fan.map(|corner| corner.uv)),
+                    None => triangle,
+                });
+            }
+        }
+        assembly
+    };
+
+    let chunk = chunk_size(mesh.faces.len(), threads);
+    if chunk >= mesh.faces.len() {
+        return assemble(&mesh.faces);
+    }
+    let chunks: Vec<Assembly> = std::thread::scope(|scope| {
+        let workers: Vec<_> = mesh
+            .faces
+            .chunks(chunk)
+            .map(|faces| scope.spawn(move || assemble(faces)))
+            .collect();
+        workers
+            .into_iter()
+            .map(|worker| worker.join().expect("primitive assembly thread panicked"))
+            .collect()
+    });
+
+    // Join the chunks in order, so triangles are drawn in the order of the faces.
+    chunks
+        .into_iter()
+        .fold(Assembly::default(), |mut assembly, chunk| {
+            assembly.triangles.extend(chunk.triangles);
+            assembly.culled += chunk.culled;
+            assembly.clipped += chunk.clipped;
+            assembly
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds the corners of a triangle with the given view-space positions, each
+    /// colored by its index and with its index as its `v` coordinate.
+    fn triangle(positions: [(f32, f32, f32); 3]) -> [ClipVertex; 3] {
+        [0, 1, 2].map(|i| ClipVertex {
+            position: Vec3::new(positions[i].0, positions[i].1, positions[i].2),
+            color: Vec3::new(i as f32, 0.0, 0.0),
+            uv: Vec2::new(0.0, i as f32),
+        })
+    }
+
+    /// Lists the positions and red channels of the first `count` corners, checking that
+    /// their texture coordinates were interpolated like their colors.
+    fn corners(clipped: ([ClipVertex; 4], usize)) -> Vec<(Vec3, f32)> {
+        let (corners, count) = clipped;
+        corners[..count]
+            .iter()
+            .map(|corner| {
+                assert_eq!(corner.uv, Vec2::new(0.0, corner.color.x));
+                (corner.position, corner.color.x)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn clip_near_keeps_the_part_in_front() {
+        let z = NEAR_PLANE;
+        // Wholly in front, including a corner on the plane.
+        let front = triangle([(0.0, 0.0, 2.0), (1.0, 0.0, 2.0), (0.0, 1.0, z)]);
+        assert_eq!(
+            corners(clip_near(front)),
+            front.map(|c| (c.position, c.color.x)).to_vec()
+        );
+        // Wholly behind.
+        let behind = triangle([(0.0, 0.0, -1.0), (1.0, 0.0, 0.0), (0.0, 1.0, z / 2.0)]);
+        assert_eq!(clip_near(behind).1, 0);
+
+        // One corner behind: a quad, with the cut corners halfway along their edges.
+        let one_behind = triangle([
+            (0.0, 0.0, z + 1.0),
+            (2.0, 0.0, z - 1.0),
+            (0.0, 2.0, z + 1.0),
+        ]);
+        assert_eq!(
+            corners(clip_near(one_behind)),
+            vec![
+                (Vec3::new(0.0, 0.0, z + 1.0), 0.0),
+                (Vec3::new(1.0, 0.0, z), 0.5),
+                (Vec3::new(1.0, 1.0, z), 1.5),
+                (Vec3::new(0.0, 2.0, z + 1.0), 2.0),
+            ]
+        );
+
+        // Two corners behind: a smaller triangle, keeping the winding.
+        let two_behind = triangle([
+            (0.0, 0.0, z - 1.0),
+            (4.0, 0.0, z - 1.0),
+            (0.0, 4.0, z + 3.0),
+        ]);
+        assert_eq!(
+            corners(clip_near(two_behind)),
+            vec![
+                (Vec3::new(3.0, 1.0, z), 1.25),
+                (Vec3::new(0.0, 4.0, z + 3.0), 2.0),
+                (Vec3::new(0.0, 1.0, z), 0.5),
+            ]
+        );
+    }
+}
@@ -0,0 +1,585 @@
+// This file contains the reader for Stanford PLY files, the format most 3D scanners
+// export.
+//
+// A PLY file starts with a text header declaring its elements and their properties,
+// followed by the element data in ASCII or in little- or big-endian binary. The `vertex`
+// and `face` elements are read into a `Mesh`; any other element and any unknown property
+// is skipped.
+
+use crate::mesh::Mesh;
+use crate::triangle::Face;
+use crate::vector::{Vec2, Vec3};
+
+/// How the element data after the header is encoded.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Format {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+/// The type of a scalar property value.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum ScalarType {
+    Int8,
+    UInt8,
+    Int16,
+    UInt16,
+    Int32,
+    UInt32,
+    Float32,
+    Float64,
+}
+
+impl ScalarType {
+    /// Parses a type name of the header, in either its old (`uchar`) or sized (`uint8`)
+    /// spelling.
+    fn from_name(name: &str) -> Option<ScalarType> {
+        match name {
+            "char" | "int8" => Some(ScalarType::Int8),
+            "uchar" | "uint8" => Some(ScalarType::UInt8),
+            "short" | "int16" => Some(ScalarType::Int16),
+            "ushort" | "uint16" => Some(ScalarType::UInt16),
+            "int" | "int32" => Some(ScalarType::Int32),
+            "uint" | "uint32" => Some(ScalarType::UInt32),
+            "float" | "float32" => Some(ScalarType::Float32),
+            "double" | "float64" => Some(ScalarType::Float64),
+            _ => None,
+        }
+    }
+
+    /// Size of a binary value of this type, in bytes.
+    fn size(&self) -> usize {
+        match self {
+            ScalarType::Int8 | ScalarType::UInt8 => 1,
+            ScalarType::Int16 | ScalarType::UInt16 => 2,
+            ScalarType::Int32 | ScalarType::UInt32 | ScalarType::Float32 => 4,
+            ScalarType::Float64 => 8,
+        }
+    }
+
+    /// The value representing full intensity for a color channel of this type.
+    fn color_scale(&self) -> f64 {
+        match self {
+            ScalarType::UInt8 => 255.0,
+            ScalarType::UInt16 => 65535.0,
+            _ => 1.0,
+        }
+    }
+}
+
+/// A property of an element, as declared in the header.
+#[derive(Debug, Clone)]
+struct Property {
+    name: String,
+    value_type: ScalarType,
+    /// The type of the item count for list properties, or `None` for scalars.
+    count_type: Option<ScalarType>,
+}
+
+/// An element of the file, such as `vertex` or `face`, as declared in the header.
+#[derive(Debug, Clone)]
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+impl Element {
+    /// Finds the index of the first property with one of the given names.
+    fn property(&self, names: &[&str]) -> Option<usize> {
+        self.properties
+            .iter()
+            .position(|property| names.contains(&property.name.as_str()))
+    }
+}
+
+/// Reads property values from the element data.
+struct ValueReader<'a> {
+    format: Format,
+    data: &'a [u8],
+    /// Offset of the next unread byte.
+    position: usize,
+}
+
+impl ValueReader<'_> {
+    /// Reads the next value, converted to a floating point number.
+    ///
+    /// # Errors
+    /// Returns a message if the data ends early or an ASCII value is not a number.
+    fn read(&mut self, value_type: ScalarType) -> Result<f64, String> {
+        if self.format == Format::Ascii {
+            return self.read_ascii();
+        }
+
+        let size = value_type.size();
+        let bytes = self
+            .data
+            .get(self.position..self.position + size)
+            .ok_or("unexpected end of data")?;
+        self.position += size;
+
+        let mut buffer = [0u8; 8];
+        buffer[..size].copy_from_slice(bytes);
+        if self.format == Format::BinaryBigEndian {
+            buffer[..size].reverse();
+        }
+        let [b0, b1, b2, b3, ..] = buffer;
+        Ok(match value_type {
+            ScalarType::Int8 => b0 as i8 as f64,
+            ScalarType::UInt8 => b0 as f64,
+            ScalarType::Int16 => i16::from_le_bytes([b0, b1]) as f64,
+            ScalarType::UInt16 => u16::from_le_bytes([b0, b1]) as f64,
+            ScalarType::Int32 => i32::from_le_bytes([b0, b1, b2, b3]) as f64,
+            ScalarType::UInt32 => u32::from_le_bytes([b0, b1, b2, b3]) as f64,
+            ScalarType::Float32 => f32::from_le_bytes([b0, b1, b2, b3]) as f64,
+            ScalarType::Float64 => f64::from_le_bytes(buffer),
+        })
+    }
+
+    /// Reads the next whitespace-separated word as a number.
+    fn read_ascii(&mut self) -> Result<f64, String> {
+        let rest = &self.data[self.position..];
+        let start = rest
+            .iter()
+            .position(|b| !b.is_ascii_whitespace())
+            .ok_or("unexpected end of data")?;
+        let length = rest[start..]
+            .iter()
+            .position(|b| b.is_ascii_whitespace())
+            .unwrap_or(rest.len() - start);
+        self.position += start + length;
+
+        let word = String::from_utf8_lossy(&rest[start..start + length]);
+        word.parse()
+            .map_err(|_| format!("expected a number, found `{}`", word))
+    }
+}
+
+impl Mesh {
+    /// Loads a mesh from a PLY file in ASCII or binary form.
+    ///
+    /// Vertices read `x`/`y`/`z`, and optionally `nx`/`ny`/`nz` normals, `u`/`v` (or
+    /// `s`/`t`) texture coordinates and `red`/`green`/`blue` colors. Faces read their
+    /// `vertex_indices` list, and polygons are split into fans of triangles.
+    ///
+    /// # Arguments
+    /// - `filename`: The path to the `.ply` file to load.
+    ///
+    /// # Returns
+    /// A `Mesh` instance loaded from the file.
+    ///
+    /// # Errors
+    /// Returns a message if the file cannot be read or contains invalid data.
+    pub fn load_ply(filename: &str) -> Result<Mesh, String> {
+        let bytes = std::fs::read(filename).map_err(|e| format!("{}: {}", filename, e))?;
+        Mesh::parse_ply(&bytes).map_err(|e| format!("{}: {}", filename, e))
+    }
+
+    /// Parses the contents of a PLY file.
+    ///
+    /// # Arguments
+    /// - `bytes`: The contents of the file.
+    ///
+    /// # Returns
+    /// The mesh described by the file.
+    ///
+    /// # Errors
+    /// Returns a message if the header or the element data is invalid.
+    pub fn parse_ply(bytes: &[u8]) -> Result<Mesh, String> {
+        let (format, elements, data_start) = parse_header(bytes)?;
+        let mut reader = ValueReader {
+            format,
+            data: &bytes[data_start..],
+            position: 0,
+        };
+
+        let mut mesh = Mesh::new(Vec::new(), Vec::new());
+        for element in &elements {
+            match element.name.as_str() {
+                "vertex" => read_vertices(&mut reader, element, &mut mesh)?,
+                "face" => read_faces(&mut reader, element, &mut mesh)?,
+                _ => {
+                    for _ in 0..element.count {
+                        read_values(&mut reader, element)
+                            .map_err(|e| format!("{}: {}", element.name, e))?;
+                    }
+                }
+            }
+        }
+
+        let in_range = |index: usize| index >= 1 && index <= mesh.vertices.len();
+        if let Some(face) = mesh
+            .faces
+            .iter()
+            .find(|face| !(in_range(face.a) && in_range(face.b) && in_range(face.c)))
+        {
+            return Err(format!(
+                "face {}/{}/{} refers to a missing vertex",
+                face.a - 1,
+                face.b - 1,
+                face.c - 1
+            ));
+        }
+        Ok(mesh)
+    }
+}
+
+/// Parses the header of a PLY file.
+///
+/// # Returns
+/// The data format, the declared elements in order, and the offset where the element
+/// data starts.
+fn parse_header(bytes: &[u8]) -> Result<(Format, Vec<Element>, usize), String> {
+    let mut format = None;
+    let mut elements: Vec<Element> = Vec::new();
+    let mut position = 0;
+
+    for number in 1.. {
+        let rest = &bytes[position..];
+        let length = rest
+            .iter()
+            .position(|&b| b == b'\n')
+            .ok_or("missing `end_header`")?;
+        let line = String::from_utf8_lossy(&rest[..length]);
+        position += length + 1;
+
+        let error = |message: &str| format!("header line {}: {}", number, message);
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            ["ply"] if number == 1 => {}
+            _ if number == 1 => return Err("not a PLY file".to_string()),
+            ["format", name, _version] => {
+                format = Some(match *name {
+                    "ascii" => Format::Ascii,
+                    "binary_little_endian" => Format::BinaryLittleEndian,
+                    "binary_big_endian" => Format::BinaryBigEndian,
+                    _ => return Err(error(&format!("unknown format `{}`", name))),
+                });
+            }
+            ["comment", ..] | ["obj_info", ..] | [] => {}
+            ["element", name, count] => elements.push(Element {
+                name: name.to_string(),
+                count: count
+                    .parse()
+                    .map_err(|_| error(&format!("invalid element count `{}`", count)))?,
+                properties: Vec::new(),
+            }),
+            ["property", rest @ ..] => {
+                let element = elements
+                    .last_mut()
+                    .ok_or_else(|| error("property before the first element"))?;
+                let scalar = |name: &str| {
+                    ScalarType::from_name(name)
+                        .ok_or_else(|| error(&format!("unknown property type `{}`", name)))
+                };
+                let property = match rest {
+                    ["list", count_type, value_type, name] => Property {
+                        name: name.to_string(),
+                        value_type: scalar(value_type)?,
+                        count_type: Some(scalar(count_type)?),
+                    },
+                    [value_type, name] => Property {
+                        name: name.to_string(),
+                        value_type: scalar(value_type)?,
+                        count_type: None,
+                    },
+                    _ => return Err(error("expected `property type name`")),
+                };
+                element.properties.push(property);
+            }
+            ["end_header"] => break,
+            _ => return Err(error(&format!("unexpected `{}`", line.trim()))),
+        }
+    }
+
+    let format = format.ok_or("missing `format` line")?;
+    Ok((format, elements, position))
+}
+
+/// Reads every property of one element instance.
+///
+/// # Returns
+/// The scalar value of each scalar property, and the items of each list property.
+/// Scalar properties have an empty item list and list properties a zero scalar.
+fn read_values(
+    reader: &mut ValueReader,
+    element: &Element,
+) -> Result<Vec<(f64, Vec<f64>)>, String> {
+    let mut values = Vec::with_capacity(element.properties.len());
+    for property in &element.properties {
+        match property.count_type {
+            Some(count_type) => {
+                let count = reader.read(count_type)? as usize;
+                let items = (0..count)
+                    .map(|_| reader.read(property.value_type))
+                    .collect::<Result<Vec<f64>, String>>()?;
+                values.push((0.0, items));
+            }
+            None => values.push((reader.read(property.value_type)?, Vec::new())),
+        }
+    }
+    Ok(values)
+}
+
+/// Reads the `vertex` element into the mesh's vertices and per-vertex attributes.
+fn read_vertices(
+    reader: &mut ValueReader,
+    element: &Element,
+    mesh: &mut Mesh,
+) -> Result<(), String> {
+    let position = [
+        element.property(&["x"]),
+        element.property(&["y"]),
+        element.property(&["z"]),
+    ];
+    let [Some(x), Some(y), Some(z)] = position else {
+        return Err("vertices need `x`, `y` and `z` properties".to_string());
+    };
+    let normal = match [
+        element.property(&["nx"]),
+        element.property(&["ny"]),
+        element.property(&["nz"]),
+    ] {
+        [Some(nx), Some(ny), Some(nz)] => Some([nx, ny, nz]),
+        _ => None,
+    };
+    let uv = match [
+        element.property(&["u", "s", "texture_u", "texture_s"]),
+        element.property(&["v", "t", "texture_v", "texture_t"]),
+    ] {
+        [Some(u), Some(v)] => Some([u, v]),
+        _ => None,
+    };
+    let color = match [
+        element.property(&["red", "r"]),
+        element.property(&["green", "g"]),
+        element.property(&["blue", "b"]),
+    ] {
+        [Some(r), Some(g), Some(b)] => Some([r, g, b]),
+        _ => None,
+    };
+
+    for i in 0..element.count {
+        let values = read_values(reader, element).map_err(|e| format!("vertex {}: {}", i, e))?;
+        let vec3 = |[a, b, c]: [usize; 3]| {
+            Vec3::new(values[a].0 as f32, values[b].0 as f32, values[c].0 as f32)
+        };
+
+        mesh.vertices.push(vec3([x, y, z]));
+        if let Some(normal) = normal {
+            mesh.vertex_normals.push(vec3(normal));
+        }
+        if let Some([u, v]) = uv {
+            mesh.vertex_uvs
+                .push(Vec2::new(values[u].0 as f32, values[v].0 as f32));
+        }
+        if let Some(color) = color {
+            let channel = |index: usize| {
+                let scale = element.properties[index].value_type.color_scale();
+                (values[index].0 / scale) as f32
+            };
+            mesh.vertex_colors.push(Vec3::new(
+                channel(color[0]),
+                channel(color[1]),
+                channel(color[2]),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Reads the `face` element into the mesh's faces.
+fn read_faces(reader: &mut ValueReader, element: &Element, mesh: &mut Mesh) -> Result<(), String> {
+    let indices = element
+        .property(&["vertex_indices", "vertex_index"])
+        .filter(|&index| element.properties[index].count_type.is_some())
+        .ok_or("faces need a `vertex_indices` list property")?;
+
+    for i in 0..element.count {
+        let values = read_values(reader, element).map_err(|e| format!("face {}: {}", i, e))?;
+        // PLY indices are 0-based, while faces use 1-based indices.
+        let corners = values[indices]
+            .1
+            .iter()
+            .map(|&index| {
+                if index >= 0.0 {
+                    (index as usize)
+                        .checked_add(1)
+                        .ok_or_else(|| format!("face {}: vertex index {} out of range", i, index))
+                } else {
+                    Err(format!("face {}: negative vertex index {}", i, index))
+                }
+            })
+            .collect::<Result<Vec<usize>, String>>()?;
+        if corners.len() < 3 {
+            return Err(format!("face {}: a face needs at least three vertices", i));
+        }
+        for j in 1..corners.len() - 1 {
+            mesh.faces
+                .push(Face::new(corners[0], corners[j], corners[j + 1]));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ASCII_SAMPLE: &str = "ply
+format ascii 1.0
+comment a unit square
+element vertex 4
+property float x
+property float y
+property float z
+property uchar red
+property uchar green
+property uchar blue
+element face 1
+property list uchar int vertex_indices
+element edge 1
+property int vertex1
+property int vertex2
+end_header
+0 0 0 255 0 0
+1 0 0 0 255 0
+1 1 0 0 0 255
+0 1 0 255 255 51
+4 0 1 2 3
+0 2
+";
+
+    /// Builds a binary PLY file holding a triangle with normals and a skipped element.
+    fn binary_sample(format: &str, big_endian: bool) -> Vec<u8> {
+        let mut bytes = format!(
+            "ply
+format {} 1.0
+element vertex 3
+property float x
+property float y
+property float z
+property float nx
+property float ny
+property float nz
+element material 1
+property list uchar ushort name
+element face 1
+property list uchar uint vertex_indices
+end_header
+",
+            format
+        )
+        .into_bytes();
+
+        let push_f32 = |bytes: &mut Vec<u8>, value: f32| {
+            bytes.extend(if big_endian {
+                value.to_be_bytes()
+            } else {
+                value.to_le_bytes()
+            })
+        };
+        for position in [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]] {
+            for value in position.into_iter().chain([0.0, 0.0, 1.0]) {
+                push_f32(&mut bytes, value);
+            }
+        }
+        bytes.push(2);
+        for value in [7u16, 9] {
+            bytes.extend(if big_endian {
+                value.to_be_bytes()
+            } else {
+                value.to_le_bytes()
+            });
+        }
+        bytes.push(3);
+        for index in [0u32, 1, 2] {
+            bytes.extend(if big_endian {
+                index.to_be_bytes()
+            } else {
+                index.to_le_bytes()
+            });
+        }
+        bytes
+    }
+
+    #[test]
+    fn ascii_sample_is_read() {
+        let mesh = Mesh::parse_ply(ASCII_SAMPLE.as_bytes()).unwrap();
+
+        assert_eq!(mesh.vertices.len(), 4);
+        assert_eq!(mesh.vertices[2], Vec3::new(1.0, 1.0, 0.0));
+        // The quad is split into a fan of two triangles.
+        assert_eq!(mesh.faces, vec![Face::new(1, 2, 3), Face::new(1, 3, 4)]);
+        assert!(mesh.vertex_normals.is_empty());
+        assert!(mesh.vertex_uvs.is_empty());
+    }
+
+    #[test]
+    fn uchar_colors_are_scaled() {
+        let mesh = Mesh::parse_ply(ASCII_SAMPLE.as_bytes()).unwrap();
+
+        assert_eq!(
+            mesh.vertex_colors,
+            vec![
+                Vec3::new(1.0, 0.0, 0.0),
+                Vec3::new(0.0, 1.0, 0.0),
+                Vec3::new(0.0, 0.0, 1.0),
+                Vec3::new(1.0, 1.0, 0.2),
+            ]
+        );
+    }
+
+    #[test]
+    fn binary_samples_are_read_in_either_byte_order() {
+        for (format, big_endian) in [("binary_little_endian", false), ("binary_big_endian", true)] {
+            let mesh = Mesh::parse_ply(&binary_sample(format, big_endian)).unwrap();
+
+            assert_eq!(
+                mesh.vertices,
+                vec![
+                    Vec3::new(0.0, 0.0, 0.0),
+                    Vec3::new(1.0, 0.0, 0.0),
+                    Vec3::new(0.0, 1.0, 0.0),
+                ],
+                "{}",
+                format
+            );
+            assert_eq!(mesh.vertex_normals, vec![Vec3::new(0.0, 0.0, 1.0); 3]);
+            // The `material` element and its list property are skipped.
+            assert_eq!(mesh.faces, vec![Face::new(1, 2, 3)], "{}", format);
+        }
+    }
+
+    #[test]
+    fn truncated_data_is_an_error() {
+        let mut bytes = binary_sample("binary_little_endian", false);
+        bytes.truncate(bytes.len() - 2);
+        let error = Mesh::parse_ply(&bytes).err().unwrap();
+        assert_eq!(error, "face 0: unexpected end of data");
+
+        let text = ASCII_SAMPLE.replace("0 2\n", "0");
+        let error = Mesh::parse_ply(text.as_bytes()).err().unwrap();
+        assert_eq!(error, "edge: unexpected end of data");
+    }
+
+    #[test]
+    fn invalid_faces_are_errors() {
+        let error = |indices: &str| {
+            let text = ASCII_SAMPLE.replace("4 0 1 2 3", indices);
+            Mesh::parse_ply(text.as_bytes()).err().unwrap()
+        };
+
+        assert_eq!(
+            error("3 0 1 1e30"),
+            format!("face 0: vertex index {} out of range", 1e30)
+        );
+        assert_eq!(error("3 0 -1 2"), "face 0: negative vertex index -1");
+        assert_eq!(
+            error("2 0 1"),
+            "face 0: a face needs at least three vertices"
+        );
+        assert_eq!(error("3 0 1 4"), "face 0/1/4 refers to a missing vertex");
+    }
+}
@@ -0,0 +1,575 @@
+// This file contains generators for procedural primitive meshes.
+//
+// Every generator produces triangles whose normal (`ab × ac`) points out of the shape,
+// the winding the backface culling in `Renderer::update` expects, along with per-vertex
+// normals and texture coordinates. Curved surfaces are built as a grid of vertices over
+// two parameters; seams duplicate their vertices so texture coordinates do not wrap.
+
+use std::collections::HashMap;
+use std::f32::consts::{PI, TAU};
+
+use crate::mesh::Mesh;
+use crate::triangle::Face;
+use crate::vector::{Vec2, Vec3};
+
+/// Builds a grid surface from a function giving the position and normal of each grid
+/// vertex.
+///
+/// The triangles face the side `∂P/∂u × ∂P/∂v` points to, where `u` follows the
+/// columns and `v` the rows. Triangles collapsed to a line, as at the poles of a
+/// sphere, are left out.
+///
+/// # Arguments
+/// - `columns`: The number of segments along `u`.
+/// - `rows`: The number of segments along `v`.
+/// - `vertex`: Gives the position and normal of the vertex in column `i` and row `j`,
+///   for `i` in `0..=columns` and `j` in `0..=rows`.
+fn grid(columns: usize, rows: usize, vertex: impl Fn(usize, usize) -> (Vec3, Vec3)) -> Mesh {
+    let mut mesh = Mesh::new(Vec::new(), Vec::new());
+    for j in 0..=rows {
+        for i in 0..=columns {
+            let (position, normal) = vertex(i, j);
+            mesh.vertices.push(position);
+            mesh.vertex_normals.push(normal);
+            mesh.vertex_uvs
+                .push(Vec2::new(i as f32 / columns as f32, j as f32 / rows as f32));
+        }
+    }
+
+    // 1-based index of the vertex in column `i` and row `j`.
+    let index = |i: usize, j: usize| j * (columns + 1) + i + 1;
+    for j in 0..rows {
+        for i in 0..columns {
+            let quad = [
+                index(i, j),
+                index(i + 1, j),
+                index(i + 1, j + 1),
+                index(i, j + 1),
+            ];
+            for [a, b, c] in [[quad[0], quad[1], quad[2]], [quad[0], quad[2], quad[3]]] {
+                let (pa, pb, pc) = (
+                    mesh.vertices[a - 1],
+                    mesh.vertices[b - 1],
+                    mesh.vertices[c - 1],
+                );
+                // Measured against the longest edge, so triangles that rounding leaves a
+                // sliver wide at the poles of a sphere still count as collapsed.
+                let longest = (pb - pa).len().max((pc - pa).len()).max((pc - pb).len());
+                if (pb - pa).cross(pc - pa).len() > 1e-5 * longest * longest {
+                    mesh.faces.push(Face::new(a, b, c));
+                }
+            }
+        }
+    }
+    mesh
+}
+
+/// Appends the vertices and faces of another mesh to a mesh.
+fn append(mesh: &mut Mesh, other: Mesh) {
+    let offset = mesh.vertices.len();
+    mesh.vertices.extend(other.vertices);
+    mesh.vertex_normals.extend(other.vertex_normals);
+    mesh.vertex_uvs.extend(other.vertex_uvs);
+    mesh.faces.extend(
+        other
+            .faces
+            .iter()
+            .map(|face| Face::new(face.a + offset, face.b + offset, face.c + offset)),
+    );
+}
+
+/// Builds a flat disk facing up or down, as the cap of a cylinder or cone.
+fn disk(radius: f32, y: f32, segments: usize, facing_up: bool) -> Mesh {
+    let normal = Vec3::new(0.0, if facing_up { 1.0 } else { -1.0 }, 0.0);
+    grid(segments, 1, |i, j| {
+        let angle = TAU * i as f32 / segments as f32;
+        // Rows run from the rim inwards when facing up, and outwards when facing down.
+        let distance = if facing_up { 1 - j } else { j } as f32 * radius;
+        (
+            Vec3::new(distance * angle.cos(), y, -distance * angle.sin()),
+            normal,
+        )
+    })
+}
+
+impl Mesh {
+    /// Creates a sphere from rings of latitude and longitude, centered at the origin.
+    ///
+    /// # Arguments
+    /// - `radius`: The radius of the sphere.
+    /// - `segments`: The number of segments around the equator, at least 3.
+    /// - `rings`: The number of segments from pole to pole, at least 2.
+    ///
+    /// # Returns
+    /// The sphere mesh, with `u` following the longitude and `v` the latitude.
+    pub fn new_uv_sphere(radius: f32, segments: usize, rings: usize) -> Mesh {
+        let (segments, rings) = (segments.max(3), rings.max(2));
+        grid(segments, rings, |i, j| {
+            let longitude = TAU * i as f32 / segments as f32;
+            let latitude = PI * (j as f32 / rings as f32 - 0.5);
+            let normal = Vec3::new(
+                latitude.cos() * longitude.cos(),
+                latitude.sin(),
+                -latitude.cos() * longitude.sin(),
+            );
+            (normal * radius, normal)
+        })
+    }
+
+    /// Creates a sphere by repeatedly subdividing an icosahedron, centered at the origin.
+    ///
+    /// Unlike a UV sphere, its triangles are all about the same size.
+    ///
+    /// # Arguments
+    /// - `radius`: The radius of the sphere.
+    /// - `subdivisions`: How many times every triangle is split into four. Each level
+    ///   multiplies the triangle count by four, starting from 20.
+    ///
+    /// # Returns
+    /// The sphere mesh, with spherical texture coordinates.
+    pub fn new_icosphere(radius: f32, subdivisions: u32) -> Mesh {
+        // The corners of an icosahedron lie on three orthogonal golden rectangles.
+        let t = (1.0 + 5.0_f32.sqrt()) / 2.0;
+        let mut directions: Vec<Vec3> = [
+            (-1.0, t, 0.0),
+            (1.0, t, 0.0),
+            (-1.0, -t, 0.0),
+            (1.0, -t, 0.0),
+            (0.0, -1.0, t),
+            (0.0, 1.0, t),
+            (0.0, -1.0, -t),
+            (0.0, 1.0, -t),
+            (t, 0.0, -1.0),
+            (t, 0.0, 1.0),
+            (-t, 0.0, -1.0),
+            (-t, 0.0, 1.0),
+        ]
+        .iter()
+        .map(|&(x, y, z)| Vec3::new(x, y, z).normalize())
+        .collect();
+        let mut triangles: Vec<[usize; 3]> = vec![
+            [0, 11, 5],
+            [0, 5, 1],
+            [0, 1, 7],
+            [0, 7, 10],
+            [0, 10, 11],
+            [1, 5, 9],
+            [5, 11, 4],
+            [11, 10, 2],
+            [10, 7, 6],
+            [7, 1, 8],
+            [3, 9, 4],
+            [3, 4, 2],
+            [3, 2, 6],
+            [3, 6, 8],
+            [3, 8, 9],
+            [4, 9, 5],
+            [2, 4, 11],
+            [6, 2, 10],
+            [8, 6, 7],
+            [9, 8, 1],
+        ];
+
+        for _ in 0..subdivisions {
+            // Edges are shared by two triangles, so their midpoints are cached.
+            let mut midpoints: HashMap<(usize, usize), usize> = HashMap::new();
+            let mut midpoint = |a: usize, b: usize| {
+                *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                    directions.push(((directions[a] + directions[b]) / 2.0).normalize());
+                    directions.len() - 1
+                })
+            };
+            triangles = triangles
+                .iter()
+                .flat_map(|&[a, b, c]| {
+                    let (ab, bc, ca) = (midpoint(a, b), midpoint(b, c), midpoint(c, a));
+                    [[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]
+                })
+                .collect();
+        }
+
+        let uv = |d: Vec3| Vec2::new(0.5 - d.z.atan2(d.x) / TAU, 0.5 + d.y.asin() / PI);
+        let mut mesh = Mesh::new(Vec::new(), Vec::new());
+        for direction in &directions {
+            mesh.vertices.push(*direction * radius);
+            mesh.vertex_normals.push(*direction);
+            mesh.vertex_uvs.push(uv(*direction));
+        }
+
+        // Triangles crossing the seam where `u` wraps from 1 back to 0 get copies of
+        // their low-`u` vertices shifted past 1, so they do not stretch across the map.
+        let mut shifted: HashMap<usize, usize> = HashMap::new();
+        for corners in &triangles {
+            let mut corners = corners.map(|corner| corner + 1);
+            let us = corners.map(|corner| mesh.vertex_uvs[corner - 1].x);
+            if us.iter().cloned().fold(f32::MIN, f32::max)
+                - us.iter().cloned().fold(f32::MAX, f32::min)
+                > 0.5
+            {
+                for (corner, u) in corners.iter_mut().zip(us) {
+                    if u < 0.5 {
+                        *corner = *shifted.entry(*corner).or_insert_with(|| {
+                            let uv = mesh.vertex_uvs[*corner - 1];
+                            mesh.vertices.push(mesh.vertices[*corner - 1]);
+                            mesh.vertex_normals.push(mesh.vertex_normals[*corner - 1]);
+                            mesh.vertex_uvs.push(Vec2::new(uv.x + 1.0, uv.y));
+                            mesh.vertices.len()
+                        });
+                    }
+                }
+            }
+            mesh.faces
+                .push(Face::new(corners[0], corners[1], corners[2]));
+        }
+        mesh
+    }
+
+    /// Creates a closed cylinder standing on the Y axis, centered at the origin.
+    ///
+    /// # Arguments
+    /// - `radius`: The radius of the cylinder.
+    /// - `height`: The height of the cylinder.
+    /// - `segments`: The number of segments around the cylinder, at least 3.
+    ///
+    /// # Returns
+    /// The cylinder mesh, with separate vertices for the side and caps so the edges
+    /// stay sharp.
+    pub fn new_cylinder(radius: f32, height: f32, segments: usize) -> Mesh {
+        let segments = segments.max(3);
+        let mut mesh = grid(segments, 1, |i, j| {
+            let angle = TAU * i as f32 / segments as f32;
+            let normal = Vec3::new(angle.cos(), 0.0, -angle.sin());
+            let y = height * (j as f32 - 0.5);
+            (Vec3::new(normal.x * radius, y, normal.z * radius), normal)
+        });
+        append(&mut mesh, disk(radius, height / 2.0, segments, true));
+        append(&mut mesh, disk(radius, -height / 2.0, segments, false));
+        mesh
+    }
+
+    /// Creates a closed cone standing on the Y axis, with its apex pointing up and its
+    /// center at the origin.
+    ///
+    /// # Arguments
+    /// - `radius`: The radius of the base.
+    /// - `height`: The distance from the base to the apex.
+    /// - `segments`: The number of segments around the cone, at least 3.
+    ///
+    /// # Returns
+    /// The cone mesh.
+    pub fn new_cone(radius: f32, height: f32, segments: usize) -> Mesh {
+        let segments = segments.max(3);
+        // The side's normal leans up by the slope of the cone.
+        let slope = Vec2::new(height, radius) / (height * height + radius * radius).sqrt();
+        let mut mesh = grid(segments, 1, |i, j| {
+            let angle = TAU * i as f32 / segments as f32;
+            let (cos, sin) = (angle.cos(), -angle.sin());
+            let distance = radius * (1 - j) as f32;
+            (
+                Vec3::new(distance * cos, height * (j as f32 - 0.5), distance * sin),
+                Vec3::new(slope.x * cos, slope.y, slope.x * sin),
+            )
+        });
+        append(&mut mesh, disk(radius, -height / 2.0, segments, false));
+        mesh
+    }
+
+    /// Creates a torus lying in the XZ plane, centered at the origin.
+    ///
+    /// # Arguments
+    /// - `major_radius`: The distance from the center to the middle of the tube.
+    /// - `minor_radius`: The radius of the tube.
+    /// - `segments`: The number of segments around the ring, at least 3.
+    /// - `sides`: The number of segments around the tube, at least 3.
+    ///
+    /// # Returns
+    /// The torus mesh, with `u` following the ring and `v` the tube.
+    pub fn new_torus(major_radius: f32, minor_radius: f32, segments: usize, sides: usize) -> Mesh {
+        let (segments, sides) = (segments.max(3), sides.max(3));
+        grid(segments, sides, |i, j| {
+            let ring = TAU * i as f32 / segments as f32;
+            let tube = TAU * j as f32 / sides as f32;
+            let normal = Vec3::new(
+                tube.cos() * ring.cos(),
+                tube.sin(),
+                -tube.cos() * ring.sin(),
+            );
+            let center = Vec3::new(ring.cos(), 0.0, -ring.sin()) * major_radius;
+            (center + normal * minor_radius, normal)
+        })
+    }
+
+    /// Creates a flat grid in the XZ plane facing up, centered at the origin.
+    ///
+    /// # Arguments
+    /// - `width`: The size along the X axis.
+    /// - `depth`: The size along the Z axis.
+    /// - `columns`: The number of segments along the X axis, at least 1.
+    /// - `rows`: The number of segments along the Z axis, at least 1.
+    ///
+    /// # Returns
+    /// The plane mesh.
+    pub fn new_plane(width: f32, depth: f32, columns: usize, rows: usize) -> Mesh {
+        let (columns, rows) = (columns.max(1), rows.max(1));
+        grid(columns, rows, |i, j| {
+            let x = width * (i as f32 / columns as f32 - 0.5);
+            let z = depth * (0.5 - j as f32 / rows as f32);
+            (Vec3::new(x, 0.0, z), Vec3::new(0.0, 1.0, 0.0))
+        })
+    }
+
+    /// Creates a capsule, a cylinder with hemispherical ends, standing on the Y axis and
+    /// centered at the origin.
+    ///
+    /// # Arguments
+    /// - `radius`: The radius of the cylinder and the hemispheres.
+    /// - `height`: The height of the cylindrical part; the capsule is `2 * radius`
+    ///   taller.
+    /// - `segments`: The number of segments around the capsule, at least 3.
+    /// - `rings`: The number of segments from the equator to each pole, at least 1.
+    ///
+    /// # Returns
+    /// The capsule mesh.
+    pub fn new_capsule(radius: f32, height: f32, segments: usize, rings: usize) -> Mesh {
+        let (segments, rings) = (segments.max(3), rings.max(1));
+        // Rows `0..=rings` form the lower hemisphere and the rest the upper one; the
+        // band between the two equator rows is the cylinder.
+        grid(segments, 2 * rings + 1, |i, j| {
+            let longitude = TAU * i as f32 / segments as f32;
+            let (latitude, offset) = if j <= rings {
+                (PI / 2.0 * (j as f32 / rings as f32 - 1.0), -height / 2.0)
+            } else {
+                (
+                    PI / 2.0 * ((j - rings - 1) as f32 / rings as f32),
+                    height / 2.0,
+                )
+            };
+            let normal = Vec3::new(
+                latitude.cos() * longitude.cos(),
+                latitude.sin(),
+                -latitude.cos() * longitude.sin(),
+            );
+            (normal * radius + Vec3::new(0.0, offset, 0.0), normal)
+        })
+    }
+
+    /// Creates a surface from a function of two parameters.
+    ///
+    /// The triangles face the side `∂P/∂u × ∂P/∂v` points to, and the normals are
+    /// estimated from the function by finite differences.
+    ///
+    /// # Arguments
+    /// - `columns`: The number of segments along `u`, at least 1.
+    /// - `rows`: The number of segments along `v`, at least 1.
+    /// - `surface`: Gives the position at `u` and `v`, both from 0.0 to 1.0.
+    ///
+    /// # Returns
+    /// The surface mesh, with `(u, v)` as texture coordinates.
+    #[allow(dead_code)]
+    pub fn new_parametric(columns: usize, rows: usize, surface: impl Fn(f32, f32) -> Vec3) -> Mesh {
+        let (columns, rows) = (columns.max(1), rows.max(1));
+        let step = 1e-3;
+        grid(columns, rows, |i, j| {
+            let (u, v) = (i as f32 / columns as f32, j as f32 / rows as f32);
+            let du = surface(u + step, v) - surface(u - step, v);
+            let dv = surface(u, v + step) - surface(u, v - step);
+            let normal = du.cross(dv);
+            let normal = if normal.len() > 0.0 {
+                normal.normalize()
+            } else {
+                normal
+            };
+            (surface(u, v), normal)
+        })
+    }
+
+    /// Creates a primitive by name, with default dimensions that fit in a box from -1
+    /// to 1 on every axis.
+    ///
+    /// # Arguments
+    /// - `name`: One of `cube`, `sphere`, `icosphere`, `cylinder`, `cone`, `torus`,
+    ///   `plane` or `capsule`.
+    ///
+    /// # Returns
+    /// The primitive, or `None` if the name is unknown.
+    pub fn new_primitive(name: &str) -> Option<Mesh> {
+        match name {
+            "cube" => Some(Mesh::new_cube()),
+            "sphere" => Some(Mesh::new_uv_sphere(1.0, 32, 16)),
+            "icosphere" => Some(Mesh::new_icosphere(1.0, 2)),
+            "cylinder" => Some(Mesh::new_cylinder(1.0, 2.0, 32)),
+            "cone" => Some(Mesh::new_cone(1.0, 2.0, 32)),
+            "torus" => Some(Mesh::new_torus(0.7, 0.3, 32, 16)),
+            "plane" => Some(Mesh::new_plane(2.0, 2.0, 8, 8)),
+            "capsule" => Some(Mesh::new_capsule(0.5, 1.0, 32, 8)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks that every face of a mesh winds away from the inside of the shape and
+    /// agrees with the vertex normals, if any, which must have unit length.
+    ///
+    /// # Arguments
+    /// - `mesh`: The mesh to check.
+    /// - `inside`: Gives the point inside the shape nearest to a point on its surface.
+    fn assert_outward(mesh: &Mesh, inside: impl Fn(Vec3) -> Vec3) {
+        assert!(!mesh.faces.is_empty());
+        for face in &mesh.faces {
+            let corners = [face.a, face.b, face.c];
+            let [a, b, c] = corners.map(|corner| mesh.vertices[corner - 1]);
+            let normal = (b - a).cross(c - a);
+            let centroid = (a + b + c) / 3.0;
+            assert!(
+                normal.dot(centroid - inside(centroid)) > 0.0,
+                "{:?} winds inwards",
+                face
+            );
+            if !mesh.vertex_normals.is_empty() {
+                for corner in corners {
+                    assert!(
+                        normal.dot(mesh.vertex_normals[corner - 1]) > 0.0,
+                        "{:?} disagrees with the normal of vertex {}",
+                        face,
+                        corner
+                    );
+                }
+            }
+        }
+
+        for normal in &mesh.vertex_normals {
+            assert!(
+                (normal.len() - 1.0).abs() < 1e-5,
+                "{:?} is not unit length",
+                normal
+            );
+        }
+    }
+
+    /// Checks that a mesh has a normal and a texture coordinate in `[0, 1]` for every
+    /// vertex.
+    fn assert_attributes(mesh: &Mesh) {
+        assert_eq!(mesh.vertex_normals.len(), mesh.vertices.len());
+        assert_eq!(mesh.vertex_uvs.len(), mesh.vertices.len());
+        for uv in &mesh.vertex_uvs {
+            assert!(
+                (0.0..=1.0).contains(&uv.x) && (0.0..=1.0).contains(&uv.y),
+                "{:?} is out of range",
+                uv
+            );
+        }
+    }
+
+    /// The center of every convex primitive.
+    fn origin(_: Vec3) -> Vec3 {
+        Vec3::new(0.0, 0.0, 0.0)
+    }
+
+    #[test]
+    fn cube_winds_outwards() {
+        let mesh = Mesh::new_primitive("cube").unwrap();
+        assert_outward(&mesh, origin);
+        // The cube has no vertex normals or texture coordinates.
+        assert!(mesh.vertex_normals.is_empty());
+        assert!(mesh.vertex_uvs.is_empty());
+    }
+
+    #[test]
+    fn uv_sphere_winds_outwards() {
+        for mesh in [
+            Mesh::new_primitive("sphere").unwrap(),
+            Mesh::new_uv_sphere(2.0, 0, 0),
+        ] {
+            assert_outward(&mesh, origin);
+            assert_attributes(&mesh);
+        }
+    }
+
+    #[test]
+    fn icosphere_winds_outwards() {
+        for mesh in [
+            Mesh::new_primitive("icosphere").unwrap(),
+            Mesh::new_icosphere(2.0, 0),
+        ] {
+            assert_outward(&mesh, origin);
+            assert_eq!(mesh.vertex_normals.len(), mesh.vertices.len());
+            assert_eq!(mesh.vertex_uvs.len(), mesh.vertices.len());
+            for (vertex, uv) in mesh.vertices.iter().zip(&mesh.vertex_uvs) {
+                assert!((0.0..=1.0).contains(&uv.y), "{:?} is out of range", uv);
+                // Vertices copied for the triangles crossing the seam have `u` shifted
+                // up by one, so the texture repeats rather than stretching back.
+                if uv.x > 1.0 {
+                    assert!(mesh.vertices.iter().zip(&mesh.vertex_uvs).any(
+                        |(other, other_uv)| other == vertex
+                            && (other_uv.x + 1.0 - uv.x).abs() < 1e-6
+                            && other_uv.y == uv.y
+                    ));
+                } else {
+                    assert!(uv.x >= 0.0, "{:?} is out of range", uv);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn cylinder_winds_outwards() {
+        for mesh in [
+            Mesh::new_primitive("cylinder").unwrap(),
+            Mesh::new_cylinder(0.5, 3.0, 0),
+        ] {
+            assert_outward(&mesh, origin);
+            assert_attributes(&mesh);
+        }
+    }
+
+    #[test]
+    fn cone_winds_outwards() {
+        for mesh in [
+            Mesh::new_primitive("cone").unwrap(),
+            Mesh::new_cone(0.5, 3.0, 0),
+        ] {
+            assert_outward(&mesh, origin);
+            assert_attributes(&mesh);
+        }
+    }
+
+    #[test]
+    fn torus_winds_outwards() {
+        for (mesh, major_radius) in [
+            (Mesh::new_primitive("torus").unwrap(), 0.7),
+            (Mesh::new_torus(2.0, 0.5, 24, 12), 2.0),
+        ] {
+            // The inside of the tube nearest a point is on the ring through its middle.
+            assert_outward(&mesh, |point| {
+                Vec3::new(point.x, 0.0, point.z).normalize() * major_radius
+            });
+            assert_attributes(&mesh);
+        }
+    }
+
+    #[test]
+    fn plane_winds_upwards() {
+        for mesh in [
+            Mesh::new_primitive("plane").unwrap(),
+            Mesh::new_plane(3.0, 1.0, 0, 0),
+        ] {
+            assert_outward(&mesh, |point| point - Vec3::new(0.0, 1.0, 0.0));
+            assert_attributes(&mesh);
+        }
+    }
+
+    #[test]
+    fn capsule_winds_outwards() {
+        for mesh in [
+            Mesh::new_primitive("capsule").unwrap(),
+            Mesh::new_capsule(1.0, 2.0, 0, 0),
+        ] {
+            assert_outward(&mesh, origin);
+            assert_attributes(&mesh);
+        }
+    }
+}
@@ -0,0 +1,1421 @@
+// This file contains the rasterizer that fills triangles into the color buffer, keeping
+// the closest surface at every pixel with a depth buffer.
+//
+// Triangles can be filled on several threads by splitting the frame into tiles. Each
+// tile is filled by a single thread, in triangle order, so the result does not depend on
+// the number of threads.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::blend::{BlendMode, CHANNELS};
+use crate::color::LinearColor;
+use crate::display::{WINDOW_HEIGHT, WINDOW_WIDTH};
+use crate::simd::{EdgeRow, Kernel, RowSetup, Span, SPAN_WIDTH};
+use crate::texture::Texture;
+use crate::triangle::Triangle;
+use crate::vector::Vec2;
+
+/// Resets the depth buffer so that every pixel is infinitely far away.
+///
+/// The buffer stores the inverse of the view depth (`1 / z`), which varies linearly
+/// across a projected triangle. Larger values are closer, and `0.0` is infinitely far.
+///
+/// # Arguments
+/// - `depth_buffer`: A mutable reference to the depth buffer.
+pub fn clear_depth_buffer(depth_buffer: &mut [f32]) {
+    depth_buffer.fill(0.0);
+}
+
+/// Clears an HDR color buffer by setting every sample to the same color.
+///
+/// # Arguments
+/// - `color_buffer`: The buffer, `CHANNELS` floats per sample.
+/// - `color`: The color to fill the buffer with, in linear light.
+pub fn clear_color_buffer(color_buffer: &mut [f32], color: LinearColor) {
+    for sample in color_buffer.chunks_exact_mut(CHANNELS) {
+        sample.copy_from_slice(&[color.r, color.g, color.b]);
+    }
+}
+
+/// Number of fractional bits vertex positions are snapped to, giving a grid of 1/256 of
+/// a pixel.
+const SUBPIXEL_BITS: u32 = 8;
+
+/// Number of grid steps per pixel.
+const SUBPIXEL_SCALE: i64 = 1 << SUBPIXEL_BITS;
+
+/// Furthest a vertex may lie from the window's corner, in pixels. Vertices beyond it are
+/// moved onto it, which keeps the fixed-point edge functions within an `i64`; it only
+/// bends triangles reaching millions of pixels off screen.
+const GUARD_BAND: f32 = (1 << 22) as f32;
+
+/// A screen position snapped to the sub-pixel grid, in steps of `1 / SUBPIXEL_SCALE`
+/// pixels.
+///
+/// Every pixel is tested against the same snapped vertices whichever triangle it belongs
+/// to, and exactly, so triangles sharing an edge agree on which side of it a pixel lies.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct FixedPoint {
+    x: i64,
+    y: i64,
+}
+
+impl FixedPoint {
+    /// Snaps a screen position to the nearest point of the sub-pixel grid.
+    fn snap(point: Vec2) -> FixedPoint {
+        let snap = |value: f32| {
+            let value = value.clamp(-GUARD_BAND, GUARD_BAND) * SUBPIXEL_SCALE as f32;
+            value.round() as i64
+        };
+        FixedPoint {
+            x: snap(point.x),
+            y: snap(point.y),
+        }
+    }
+
+    /// Converts the point back to a screen position in pixels.
+    fn to_vec2(self) -> Vec2 {
+        Vec2::new(
+            self.x as f32 / SUBPIXEL_SCALE as f32,
+            self.y as f32 / SUBPIXEL_SCALE as f32,
+        )
+    }
+}
+
+/// Calculates on which side of the edge `a -> b` the point `p` lies, exactly.
+///
+/// # Returns
+/// Twice the signed area of the triangle `a, b, p`, in square grid steps. The sign flips
+/// when `p` crosses the edge, and is zero on it.
+fn edge_function(a: FixedPoint, b: FixedPoint, p: FixedPoint) -> i64 {
+    (b.x - a.x) * (p.y - a.y) - (b.y - a.y) * (p.x - a.x)
+}
+
+/// Number of samples per pixel when multisampling.
+pub const MSAA_SAMPLES: u32 = 4;
+
+/// Where the single sample of a pixel lies, from the pixel's top-left corner in grid
+/// steps: its center.
+const PIXEL_CENTER: [FixedPoint; 1] = [sample_offset(0, 0)];
+
+/// Where the samples of a multisampled pixel lie, from the pixel's top-left corner in
+/// grid steps. The rotated grid pattern of Direct3D gives every sample its own row and
+/// column, which resolves near-horizontal and near-vertical edges into 4 shades.
+const SAMPLE_POSITIONS: [FixedPoint; MSAA_SAMPLES as usize] = [
+    sample_offset(-2, -6),
+    sample_offset(6, -2),
+    sample_offset(-6, 2),
+    sample_offset(2, 6),
+];
+
+/// Places a sample relative to the center of a pixel, in 1/16 of a pixel.
+const fn sample_offset(x: i64, y: i64) -> FixedPoint {
+    FixedPoint {
+        x: SUBPIXEL_SCALE / 2 + x * SUBPIXEL_SCALE / 16,
+        y: SUBPIXEL_SCALE / 2 + y * SUBPIXEL_SCALE / 16,
+    }
+}
+
+/// The position of a sample of a pixel on the sub-pixel grid.
+fn sample_position(x: i32, y: i32, offset: FixedPoint) -> FixedPoint {
+    FixedPoint {
+        x: x as i64 * SUBPIXEL_SCALE + offset.x,
+        y: y as i64 * SUBPIXEL_SCALE + offset.y,
+    }
+}
+
+/// An edge of a triangle, oriented so that the triangle lies on its positive side.
+#[derive(Debug, Copy, Clone)]
+struct Edge {
+    from: FixedPoint,
+    to: FixedPoint,
+    /// Added to the edge function before testing it for `>= 0`: 0 for top and left edges,
+    /// which own the pixel centers lying exactly on them, and -1 for the others.
+    bias: i64,
+    /// Change of the edge function from a point to the one a pixel to its right.
+    x_step: i64,
+    /// Change of the edge function from a point to the one a pixel below it.
+    y_step: i64,
+}
+
+impl Edge {
+    /// Orients an edge of a triangle.
+    ///
+    /// # Arguments
+    /// - `from`, `to`: The edge, as passed to `edge_function` for the weight of the
+    ///   opposite vertex.
+    /// - `area`: Twice the signed area of the triangle, whose sign gives the inside.
+    fn new(from: FixedPoint, to: FixedPoint, area: i64) -> Edge {
+        let (from, to) = if area > 0 { (from, to) } else { (to, from) };
+        // With y pointing down and the inside on the positive side, a top edge runs
+        // right along a horizontal line, and a left edge runs up.
+        let (dx, dy) = (to.x - from.x, to.y - from.y);
+        let top_left = (dy == 0 && dx > 0) || dy < 0;
+        Edge {
+            from,
+            to,
+            bias: if top_left { 0 } else { -1 },
+            x_step: -dy * SUBPIXEL_SCALE,
+            y_step: dx * SUBPIXEL_SCALE,
+        }
+    }
+
+    /// Calculates the biased edge function at a point, which is `>= 0` when the point is
+    /// inside the edge.
+    fn value(&self, point: FixedPoint) -> i64 {
+        edge_function(self.from, self.to, point) + self.bias
+    }
+
+    /// Calculates how the biased edge function changes from a pixel's top-left corner to
+    /// one of its samples.
+    fn sample_offset(&self, sample: FixedPoint) -> i64 {
+        (self.y_step * sample.y + self.x_step * sample.x) / SUBPIXEL_SCALE
+    }
+}
+
+/// Side length in pixels of the square blocks a triangle's bounds are walked in. Blocks
+/// start at multiples of it in the frame, whatever part of the frame is being filled,
+/// and those entirely outside one of the triangle's edges are skipped without visiting
+/// their rows.
+const BLOCK_SIZE: i32 = 8;
+
+// Tiles are split into whole blocks, so a block is the same in a tile as in the frame.
+const _: () = assert!(TILE_SIZE.is_multiple_of(BLOCK_SIZE as u32));
+// A row of a block is filled with a single call to a kernel.
+const _: () = assert!(BLOCK_SIZE as usize <= SPAN_WIDTH);
+
+/// A block of pixels within a triangle's bounds, and the triangle's edges at its corner.
+struct Block {
+    /// The block's top-left pixel, at multiples of `BLOCK_SIZE`. It may lie outside
+    /// the triangle's bounds.
+    x: i32,
+    y: i32,
+    /// The inclusive `[min_x, min_y, max_x, max_y]` pixels of the block within the
+    /// triangle's bounds.
+    bounds: [i32; 4],
+    /// The biased edge functions at the top-left corner of pixel `(x, y)`.
+    values: [i64; 3],
+}
+
+impl Block {
+    /// Calculates a triangle's edge functions along a row of the block.
+    ///
+    /// # Arguments
+    /// - `edges`: The triangle's edges.
+    /// - `y`: The row.
+    /// - `sample`: Where the sample lies in each pixel, as in `SAMPLE_POSITIONS`.
+    ///
+    /// # Returns
+    /// The edge functions at the sample of the block's first pixel in the row, and their
+    /// change from one pixel to the next.
+    fn edge_row(&self, edges: &[Edge; 3], y: i32, sample: FixedPoint) -> EdgeRow {
+        EdgeRow {
+            values: std::array::from_fn(|k| {
+                self.values[k]
+                    + edges[k].y_step * (y - self.y) as i64
+                    + edges[k].sample_offset(sample)
+            }),
+            steps: edges.each_ref().map(|edge| edge.x_step),
+        }
+    }
+}
+
+/// Walks a triangle's bounds in blocks of `BLOCK_SIZE` pixels, skipping the blocks
+/// where every sample lies outside one of its edges.
+///
+/// The edge functions are affine, so over the rectangle holding a block's samples each
+/// is largest at a corner of the rectangle. They are exact integers, so a block is only
+/// skipped when none of its samples could be covered.
+///
+/// # Arguments
+/// - `edges`: The triangle's edges.
+/// - `bounds`: The pixels to visit, from `pixel_bounds`.
+/// - `samples`: Where the samples lie in each pixel.
+/// - `visit`: Receives each block that may hold covered samples, from top to bottom
+///   and left to right.
+fn for_each_block(
+    edges: &[Edge; 3],
+    bounds: [i32; 4],
+    samples: &[FixedPoint],
+    mut visit: impl FnMut(&Block),
+) {
+    let [min_x, min_y, max_x, max_y] = bounds;
+    // The samples of a pixel lie within these offsets from its top-left corner.
+    let first_sample = FixedPoint {
+        x: samples.iter().map(|sample| sample.x).min().unwrap(),
+        y: samples.iter().map(|sample| sample.y).min().unwrap(),
+    };
+    let last_sample = FixedPoint {
+        x: samples.iter().map(|sample| sample.x).max().unwrap(),
+        y: samples.iter().map(|sample| sample.y).max().unwrap(),
+    };
+
+    let align = |value: i32| value.div_euclid(BLOCK_SIZE) * BLOCK_SIZE;
+    for y in (align(min_y)..=max_y).step_by(BLOCK_SIZE as usize) {
+        for x in (align(min_x)..=max_x).step_by(BLOCK_SIZE as usize) {
+            let corner = sample_position(x, y, FixedPoint { x: 0, y: 0 });
+            let block = Block {
+                x,
+                y,
+                bounds: [
+                    x.max(min_x),
+                    y.max(min_y),
+                    (x + BLOCK_SIZE - 1).min(max_x),
+                    (y + BLOCK_SIZE - 1).min(max_y),
+                ],
+                values: edges.each_ref().map(|edge| edge.value(corner)),
+            };
+
+            let [left, top, right, bottom] = block.bounds;
+            let outside = edges.iter().zip(block.values).any(|(edge, value)| {
+                let at = |column: i32, row: i32, sample: FixedPoint| {
+                    value
+                        + edge.x_step * (column - x) as i64
+                        + edge.y_step * (row - y) as i64
+                        + edge.sample_offset(sample)
+                };
+                let largest = [
+                    at(left, top, first_sample),
+                    at(
+                        right,
+                        top,
+                        FixedPoint {
+                            x: last_sample.x,
+                            y: first_sample.y,
+                        },
+                    ),
+                    at(
+                        left,
+                        bottom,
+                        FixedPoint {
+                            x: first_sample.x,
+                            y: last_sample.y,
+                        },
+                    ),
+                    at(right, bottom, last_sample),
+                ]
+                .into_iter()
+                .max()
+                .unwrap();
+                largest < 0
+            });
+            if !outside {
+                visit(&block);
+            }
+        }
+    }
+}
+
+/// A triangle's barycentric weights and inverse depth as functions of the screen
+/// position, in floating point, for multisampled targets.
+struct Interpolation {
+    /// `from.x` and `from.y` of each edge.
+    origins_x: [f32; 3],
+    origins_y: [f32; 3],
+    /// `to.x - from.x` and `to.y - from.y` of each edge.
+    dxs: [f32; 3],
+    dys: [f32; 3],
+    /// Twice the signed area of the triangle, in square pixels.
+    area: f32,
+    /// The inverse view depth of each vertex.
+    inv_depths: [f32; 3],
+}
+
+impl Interpolation {
+    /// Sets up the interpolation of a triangle from its snapped vertices.
+    ///
+    /// # Arguments
+    /// - `points`: The snapped vertices.
+    /// - `area`: Twice the signed area of the triangle, in square grid steps.
+    /// - `inv_depths`: The inverse view depth of each vertex.
+    fn new(points: [FixedPoint; 3], area: i64, inv_depths: [f32; 3]) -> Interpolation {
+        // Each edge runs from the vertex after the one it is opposite, as in
+        // `edge_function(b, c, p)` for the weight of `a`.
+        let [a, b, c] = points.map(FixedPoint::to_vec2);
+        let edges = [(b, c), (c, a), (a, b)];
+        Interpolation {
+            origins_x: edges.map(|(from, _)| from.x),
+            origins_y: edges.map(|(from, _)| from.y),
+            dxs: edges.map(|(from, to)| to.x - from.x),
+            dys: edges.map(|(from, to)| to.y - from.y),
+            area: area as f32 / (SUBPIXEL_SCALE * SUBPIXEL_SCALE) as f32,
+            inv_depths,
+        }
+    }
+
+    /// Calculates the barycentric weights and inverse depth at a point.
+    fn at(&self, point: Vec2) -> ([f32; 3], f32) {
+        let w: [f32; 3] = std::array::from_fn(|k| {
+            (self.dxs[k] * (point.y - self.origins_y[k])
+                - self.dys[k] * (point.x - self.origins_x[k]))
+                / self.area
+        });
+        let inv_depths = self.inv_depths;
+        let depth = w[0] * inv_depths[0] + w[1] * inv_depths[1] + w[2] * inv_depths[2];
+        (w, depth)
+    }
+
+    /// Sets up the inverse depth along a row as a line, which is cheaper to evaluate at
+    /// many scattered points than the weights.
+    ///
+    /// # Arguments
+    /// - `py`: The height of the row on screen.
+    ///
+    /// # Returns
+    /// The inverse depth at the row's left edge (`x = 0`), and its change per pixel to
+    /// the right.
+    fn depth_line(&self, py: f32) -> (f32, f32) {
+        let gradient = |terms: [f32; 3]| {
+            let [a, b, c] = self.inv_depths;
+            (terms[0] * a + terms[1] * b + terms[2] * c) / self.area
+        };
+        let (dx, dy) = (gradient(self.dys.map(|dy| -dy)), gradient(self.dxs));
+        // The plane of the inverse depth passes through the first vertex, the origin of
+        // the last edge.
+        let (ax, ay) = (self.origins_x[2], self.origins_y[2]);
+        (self.inv_depths[0] + dy * (py - ay) - dx * ax, dx)
+    }
+}
+
+/// Side length in pixels of the square tiles the frame is split into when rasterizing
+/// on several threads.
+pub const TILE_SIZE: u32 = 64;
+
+/// A rectangle of the frame that triangles are filled into.
+///
+/// The buffers cover only the rectangle, one row of `width` pixels after another, so a
+/// tile can be filled on its own thread while others fill the rest of the frame. Each
+/// pixel holds `samples` samples, one after another.
+pub struct RasterTarget<'a> {
+    /// The color of each sample in the rectangle, `CHANNELS` floats in linear light per
+    /// sample.
+    pub color_buffer: &'a mut [f32],
+    /// The inverse view depth of each sample in the rectangle.
+    pub depth_buffer: &'a mut [f32],
+    /// The left edge of the rectangle in the frame.
+    pub x: u32,
+    /// The top edge of the rectangle in the frame.
+    pub y: u32,
+    /// The width of the rectangle in pixels.
+    pub width: u32,
+    /// The height of the rectangle in pixels.
+    pub height: u32,
+    /// Number of samples per pixel: 1, or `MSAA_SAMPLES` to multisample.
+    pub samples: u32,
+    /// Whether samples passing the depth test store their depth. Transparent surfaces
+    /// are drawn without, so they do not hide each other.
+    pub depth_write: bool,
+}
+
+impl<'a> RasterTarget<'a> {
+    /// Creates a target covering the whole window.
+    ///
+    /// # Arguments
+    /// - `color_buffer`: The frame's HDR color buffer.
+    /// - `depth_buffer`: The window's depth buffer.
+    pub fn frame(color_buffer: &'a mut [f32], depth_buffer: &'a mut [f32]) -> RasterTarget<'a> {
+        RasterTarget::new(color_buffer, depth_buffer, WINDOW_WIDTH, WINDOW_HEIGHT, 1)
+    }
+
+    /// Creates a target covering a whole frame of any size.
+    ///
+    /// # Arguments
+    /// - `color_buffer`: The frame's HDR color buffer, `CHANNELS` floats per sample.
+    /// - `depth_buffer`: The frame's depth buffer, one value per sample.
+    /// - `width`: The width of the frame in pixels.
+    /// - `height`: The height of the frame in pixels.
+    /// - `samples`: Number of samples per pixel: 1, or `MSAA_SAMPLES` to multisample.
+    pub fn new(
+        color_buffer: &'a mut [f32],
+        depth_buffer: &'a mut [f32],
+        width: u32,
+        height: u32,
+        samples: u32,
+    ) -> RasterTarget<'a> {
+        debug_assert!(samples == 1 || samples == MSAA_SAMPLES);
+        debug_assert_eq!(depth_buffer.len(), (width * height * samples) as usize);
+        RasterTarget {
+            color_buffer,
+            depth_buffer,
+            x: 0,
+            y: 0,
+            width,
+            height,
+            samples,
+            depth_write: true,
+        }
+    }
+
+    /// Borrows the target again, to fill it more than once.
+    pub fn reborrow(&mut self) -> RasterTarget<'_> {
+        RasterTarget {
+            color_buffer: self.color_buffer,
+            depth_buffer: self.depth_buffer,
+            ..*self
+        }
+    }
+
+    /// Lists where the samples lie in each pixel of the target.
+    fn sample_positions(&self) -> &'static [FixedPoint] {
+        if self.samples > 1 {
+            &SAMPLE_POSITIONS
+        } else {
+            &PIXEL_CENTER
+        }
+    }
+}
+
+/// Calculates the pixels a triangle may cover, clipped to a rectangle.
+///
+/// # Arguments
+/// - `points`: The snapped vertices of the triangle.
+/// - `samples`: Where the samples lie in each pixel.
+/// - `x`, `y`, `width`, `height`: The rectangle, in pixels.
+///
+/// # Returns
+/// The inclusive `(min_x, min_y, max_x, max_y)` pixels with a sample within the
+/// triangle's bounds, or `None` if the triangle lies outside the rectangle.
+fn pixel_bounds(
+    points: [FixedPoint; 3],
+    samples: &[FixedPoint],
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+) -> Option<[i32; 4]> {
+    // The first pixel whose last sample is at or after the triangle's lowest
+    // coordinate, and the last pixel whose first sample is at or before its highest.
+    let range = |coordinate: fn(&FixedPoint) -> i64| {
+        let low = points.iter().map(coordinate).min().unwrap();
+        let high = points.iter().map(coordinate).max().unwrap();
+        let first_sample = samples.iter().map(coordinate).min().unwrap();
+        let last_sample = samples.iter().map(coordinate).max().unwrap();
+        (
+            (low - last_sample + SUBPIXEL_SCALE - 1).div_euclid(SUBPIXEL_SCALE),
+            (high - first_sample).div_euclid(SUBPIXEL_SCALE),
+        )
+    };
+    let (min_x, max_x) = range(|point| point.x);
+    let (min_y, max_y) = range(|point| point.y);
+    let min_x = min_x.max(x as i64) as i32;
+    let min_y = min_y.max(y as i64) as i32;
+    let max_x = max_x.min((x + width) as i64 - 1) as i32;
+    let max_y = max_y.min((y + height) as i64 - 1) as i32;
+    (min_x <= max_x && min_y <= max_y).then_some([min_x, min_y, max_x, max_y])
+}
+
+/// Fills a triangle, skipping pixels hidden behind closer surfaces.
+///
+/// The vertices are first snapped to a grid of `1 / 2^SUBPIXEL_BITS` pixels. A pixel is
+/// covered when its center lies inside all three edges of the triangle; a center exactly
+/// on an edge is covered only if it is a top or left edge. Triangles sharing an edge
+/// therefore fill every pixel along it exactly once, with no gaps or overlaps. Triangles
+/// of either winding are filled.
+///
+/// The triangle's bounds are walked in blocks of `BLOCK_SIZE` by `BLOCK_SIZE` pixels, and
+/// a block lying wholly outside one of the edges is skipped without testing its pixels.
+/// The kernel tests the pixels of each row of the other blocks against the edges
+/// together.
+/// The vertex colors and texture coordinates are blended across the triangle with
+/// perspective correction, so they do not swim as the view changes. A textured
+/// triangle's colors are multiplied by its texture, sampled at the blended coordinates.
+///
+/// When the target holds several samples per pixel, each sample is covered and depth
+/// tested on its own, but the color is blended once per pixel and stored in every
+/// sample that passed, as with multisampling on a GPU.
+///
+/// Each color is combined with the sample it is stored in by the blend mode, after the
+/// depth test. The depth is written too unless the target's `depth_write` is unset.
+///
+/// Every pixel is computed from its own position and the block it lies in, which is the
+/// same in a tile as in the whole frame, so filling the frame in tiles gives exactly the
+/// same result as filling it whole, and every kernel gives the same result as the scalar
+/// one.
+///
+/// # Arguments
+/// - `target`: The part of the frame to fill; pixels outside it are left alone.
+/// - `triangle`: The triangle to fill.
+/// - `textures`: The textures, indexed by the triangle's `TextureId`.
+/// - `kernel`: The implementation of the inner loop to use.
+pub fn fill_triangle(
+    target: &mut RasterTarget,
+    triangle: &Triangle,
+    textures: &[Texture],
+    kernel: Kernel,
+) {
+    let blend = triangle.blend;
+    let mut shade = |color_buffer: &mut [f32], index: usize, color, _depth| {
+        let offset = index * CHANNELS;
+        blend.apply_linear(&mut color_buffer[offset..offset + CHANNELS], color, 1.0);
+    };
+    rasterize(target, triangle, textures, kernel, &mut shade);
+}
+
+/// Finds the samples of a triangle in front of the depth buffer, as `fill_triangle`
+/// describes, and hands each to `shade` with its color and inverse depth.
+///
+/// # Arguments
+/// - `shade`: Receives the target's color buffer and, for each sample, its index in
+///   the target, its color and its inverse depth.
+fn rasterize<F>(
+    target: &mut RasterTarget,
+    triangle: &Triangle,
+    textures: &[Texture],
+    kernel: Kernel,
+    shade: &mut F,
+) where
+    F: FnMut(&mut [f32], usize, LinearColor, f32),
+{
+    let fixed = triangle.points.map(FixedPoint::snap);
+    let [a, b, c] = fixed;
+    let area = edge_function(a, b, c);
+    if area == 0 {
+        return; // Degenerate triangles cover no pixels.
+    }
+
+    // Only visit the pixels inside both the triangle's bounds and the target.
+    let samples = target.sample_positions();
+    let bounds = match pixel_bounds(
+        fixed,
+        samples,
+        target.x,
+        target.y,
+        target.width,
+        target.height,
+    ) {
+        Some(bounds) => bounds,
+        None => return,
+    };
+
+    // Each edge runs from the vertex after the one it is opposite, as in
+    // `edge_function(b, c, p)` for the weight of `a`.
+    let edges = [(b, c), (c, a), (a, b)].map(|(from, to)| Edge::new(from, to, area));
+    let surface = Surface::new(triangle, textures);
+    if target.samples > 1 {
+        // The weights are interpolated in floating point, from the snapped vertices.
+        let interpolation = Interpolation::new(fixed, area, triangle.inv_depths);
+        fill_multisampled(
+            target,
+            bounds,
+            &edges,
+            &interpolation,
+            &surface,
+            kernel,
+            shade,
+        );
+        return;
+    }
+
+    // The edges are oriented so that dividing them by the unsigned area gives the
+    // weights, which change by the edges' steps divided by it too.
+    let area = area.abs() as f64;
+    let weight = |value: i64| (value as f64 / area) as f32;
+    let x_steps = edges.map(|edge| weight(edge.x_step));
+    let y_steps = edges.map(|edge| weight(edge.y_step));
+
+    let inv_depths = triangle.inv_depths;
+    let flat = surface.is_flat();
+    let mut span = Span {
+        covered: 0,
+        mask: 0,
+        weights: [[0.0; SPAN_WIDTH]; 3],
+        depths: [0.0; SPAN_WIDTH],
+    };
+    for_each_block(&edges, bounds, samples, |block| {
+        // The weights at the block's first pixel come from the exact edge functions, and
+        // are stepped from there, so a pixel's weights only depend on its block.
+        let mut weights: [f32; 3] = std::array::from_fn(|k| {
+            weight(block.values[k] - edges[k].bias + edges[k].sample_offset(samples[0]))
+        });
+        let [min_x, min_y, max_x, max_y] = block.bounds;
+        for y in block.y..=max_y {
+            let row = RowSetup {
+                edges: block.edge_row(&edges, y, samples[0]),
+                weights,
+                steps: x_steps,
+                inv_depths,
+            };
+            weights = std::array::from_fn(|k| weights[k] + y_steps[k]);
+            if y < min_y {
+                continue;
+            }
+
+            // A block is never wider than a span, so each of its rows takes one call.
+            let start = ((y as u32 - target.y) * target.width + (min_x as u32 - target.x)) as usize;
+            let count = (max_x - min_x + 1) as usize;
+            kernel.span(
+                &row,
+                (min_x - block.x) as usize,
+                &target.depth_buffer[start..start + count],
+                &mut span,
+            );
+
+            for i in (0..count).filter(|i| span.mask & (1 << i) != 0) {
+                let index = start + i;
+                let depth = span.depths[i];
+                if target.depth_write {
+                    target.depth_buffer[index] = depth;
+                }
+                let color = if flat {
+                    surface.colors[0]
+                } else {
+                    // Weights of the vertices in view space rather than on screen.
+                    surface.at(std::array::from_fn(|k| {
+                        span.weights[k][i] * inv_depths[k] / depth
+                    }))
+                };
+                shade(target.color_buffer, index, color, depth);
+            }
+        }
+    });
+}
+
+/// Fills a triangle into a target with `MSAA_SAMPLES` samples per pixel.
+///
+/// The color of a pixel is blended at its center when the triangle covers every
+/// sample, and otherwise at the average position of the covered samples, which lies
+/// inside the triangle. This keeps the colors of thin slivers from being extrapolated.
+///
+/// # Arguments
+/// - `target`: The part of the frame to fill.
+/// - `bounds`: The pixels to visit, from `pixel_bounds`.
+/// - `edges`: The triangle's edges.
+/// - `interpolation`: The triangle's weights and depth across the screen.
+/// - `surface`: The triangle's colors and texture.
+/// - `kernel`: The implementation of the coverage test to use.
+/// - `shade`: Receives each sample, as in `rasterize`.
+fn fill_multisampled<F>(
+    target: &mut RasterTarget,
+    bounds: [i32; 4],
+    edges: &[Edge; 3],
+    interpolation: &Interpolation,
+    surface: &Surface,
+    kernel: Kernel,
+    shade: &mut F,
+) where
+    F: FnMut(&mut [f32], usize, LinearColor, f32),
+{
+    const ALL_SAMPLES: u32 = (1 << MSAA_SAMPLES) - 1;
+    let flat = surface.is_flat();
+    let inv_depths = interpolation.inv_depths;
+    let offsets = SAMPLE_POSITIONS.map(|offset| offset.to_vec2());
+
+    for_each_block(edges, bounds, &SAMPLE_POSITIONS, |block| {
+        let [min_x, min_y, max_x, max_y] = block.bounds;
+        let (first, count) = ((min_x - block.x) as usize, (max_x - min_x + 1) as usize);
+        for y in min_y..=max_y {
+            // Bit `i` of a sample's mask is set when the sample of pixel `min_x + i` is
+            // covered.
+            let masks = SAMPLE_POSITIONS
+                .map(|sample| kernel.coverage(&block.edge_row(edges, y, sample), first, count));
+            let depth_lines = offsets.map(|offset| interpolation.depth_line(y as f32 + offset.y));
+
+            for i in 0..count {
+                let covered = masks.iter().enumerate().fold(0, |covered, (sample, mask)| {
+                    covered | (mask >> i & 1) << sample
+                });
+                if covered == 0 {
+                    continue;
+                }
+                let x = min_x + i as i32;
+
+                // Depth test each covered sample at its own position.
+                let pixel = Vec2::new(x as f32, y as f32);
+                let first = (((y as u32 - target.y) * target.width + (x as u32 - target.x))
+                    * MSAA_SAMPLES) as usize;
+                let mut passed = 0;
+                let mut depths = [0.0; MSAA_SAMPLES as usize];
+                for sample in (0..MSAA_SAMPLES as usize).filter(|s| covered & (1 << s) != 0) {
+                    let (left_depth, step) = depth_lines[sample];
+                    let depth = left_depth + step * (pixel.x + offsets[sample].x);
+                    if depth > target.depth_buffer[first + sample] {
+                        passed |= 1 << sample;
+                        depths[sample] = depth;
+                    }
+                }
+                if passed == 0 {
+                    continue;
+                }
+
+                let color = if flat {
+                    surface.colors[0]
+                } else {
+                    let position = if covered == ALL_SAMPLES {
+                        pixel + Vec2::new(0.5, 0.5)
+                    } else {
+                        let (sum, count) = (0..MSAA_SAMPLES as usize)
+                            .filter(|s| covered & (1 << s) != 0)
+                            .fold((Vec2::new(0.0, 0.0), 0.0), |(sum, count), s| {
+                                (sum + offsets[s], count + 1.0)
+                            });
+                        pixel + sum / count
+                    };
+                    let (weights, depth) = interpolation.at(position);
+                    // Weights of the vertices in view space rather than on screen.
+                    surface.at(std::array::from_fn(|k| weights[k] * inv_depths[k] / depth))
+                };
+                for sample in (0..MSAA_SAMPLES as usize).filter(|s| passed & (1 << s) != 0) {
+                    let index = first + sample;
+                    if target.depth_write {
+                        target.depth_buffer[index] = depths[sample];
+                    }
+                    shade(target.color_buffer, index, color, depths[sample]);
+                }
+            }
+        }
+    });
+}
+
+/// Fills triangles into the frame in order, on one or more threads.
+///
+/// With more than one thread, the frame is split into tiles of `TILE_SIZE` pixels and
+/// each triangle is listed in the tiles its bounds overlap. Threads then take tiles one
+/// at a time and fill their triangles, in the original order, into copies of the
+/// tile's pixels, which are written back once every tile is done. The result is
+/// identical to filling the triangles on one thread.
+///
+/// # Arguments
+/// - `target`: The whole frame, from `RasterTarget::frame` or `RasterTarget::new`.
+/// - `triangles`: The triangles to fill, drawn over one another in this order.
+/// - `textures`: The textures, indexed by the triangles' `TextureId`.
+/// - `threads`: The number of threads to fill with; 1 fills the whole frame on the
+///   calling thread.
+/// - `kernel`: The implementation of the inner loop to use.
+pub fn fill_triangles(
+    mut target: RasterTarget,
+    triangles: &[Triangle],
+    textures: &[Texture],
+    threads: usize,
+    kernel: Kernel,
+) {
+    if threads <= 1 {
+        for triangle in triangles {
+            fill_triangle(&mut target, triangle, textures, kernel);
+        }
+        return;
+    }
+
+    // Bin the triangles into the tiles their bounds overlap.
+    let (width, height) = (target.width, target.height);
+    let tiles_x = width.div_ceil(TILE_SIZE);
+    let tiles_y = height.div_ceil(TILE_SIZE);
+    let mut bins: Vec<Vec<&Triangle>> = vec![Vec::new(); (tiles_x * tiles_y) as usize];
+    for triangle in triangles {
+        let points = triangle.points.map(FixedPoint::snap);
+        let bounds = pixel_bounds(points, target.sample_positions(), 0, 0, width, height);
+        if let Some([min_x, min_y, max_x, max_y]) = bounds {
+            let (min_x, min_y) = (min_x as u32 / TILE_SIZE, min_y as u32 / TILE_SIZE);
+            let (max_x, max_y) = (max_x as u32 / TILE_SIZE, max_y as u32 / TILE_SIZE);
+            for tile_y in min_y..=max_y {
+                for tile_x in min_x..=max_x {
+                    bins[(tile_y * tiles_x + tile_x) as usize].push(triangle);
+                }
+            }
+        }
+    }
+
+    // Threads take the next unfilled tile until none are left, reading the frame while
+    // it is shared between them.
+    let frame = &target;
+    let next_tile = AtomicUsize::new(0);
+    let filled: Vec<FilledTile> = std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut filled = Vec::new();
+                    loop {
+                        let tile = next_tile.fetch_add(1, Ordering::Relaxed);
+                        if tile >= bins.len() {
+                            break;
+                        }
+                        if bins[tile].is_empty() {
+                            continue;
+                        }
+                        let x = tile as u32 % tiles_x * TILE_SIZE;
+                        let y = tile as u32 / tiles_x * TILE_SIZE;
+                        filled.push(fill_tile(frame, x, y, &bins[tile], textures, kernel));
+                    }
+                    filled
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().expect("rasterizer thread panicked"))
+            .collect()
+    });
+
+    let samples = target.samples as usize;
+    for tile in filled {
+        let row_length = tile.width as usize * samples;
+        for row in 0..tile.height as usize {
+            let start = ((tile.y as usize + row) * width as usize + tile.x as usize) * samples;
+            let tile_start = row * row_length;
+            target.depth_buffer[start..start + row_length]
+                .copy_from_slice(&tile.depth_buffer[tile_start..tile_start + row_length]);
+            target.color_buffer[start * CHANNELS..(start + row_length) * CHANNELS].copy_from_slice(
+                &tile.color_buffer[tile_start * CHANNELS..(tile_start + row_length) * CHANNELS],
+            );
+        }
+    }
+}
+
+/// The pixels of a tile after its triangles were filled, waiting to be written back.
+struct FilledTile {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    color_buffer: Vec<f32>,
+    depth_buffer: Vec<f32>,
+}
+
+/// Copies a tile out of the frame and fills triangles into it.
+fn fill_tile(
+    frame: &RasterTarget,
+    x: u32,
+    y: u32,
+    triangles: &[&Triangle],
+    textures: &[Texture],
+    kernel: Kernel,
+) -> FilledTile {
+    let width = TILE_SIZE.min(frame.width - x);
+    let height = TILE_SIZE.min(frame.height - y);
+    let samples = frame.samples;
+    let mut tile = FilledTile {
+        x,
+        y,
+        width,
+        height,
+        color_buffer: Vec::with_capacity((width * height * samples) as usize * CHANNELS),
+        depth_buffer: Vec::with_capacity((width * height * samples) as usize),
+    };
+    for row in y..y + height {
+        let start = ((row * frame.width + x) * samples) as usize;
+        let end = start + (width * samples) as usize;
+        tile.depth_buffer
+            .extend_from_slice(&frame.depth_buffer[start..end]);
+        tile.color_buffer
+            .extend_from_slice(&frame.color_buffer[start * CHANNELS..end * CHANNELS]);
+    }
+
+    let mut target = RasterTarget {
+        color_buffer: &mut tile.color_buffer,
+        depth_buffer: &mut tile.depth_buffer,
+        x,
+        y,
+        width,
+        height,
+        samples,
+        depth_write: frame.depth_write,
+    };
+    for triangle in triangles {
+        fill_triangle(&mut target, triangle, textures, kernel);
+    }
+    tile
+}
+
+/// Finds the samples every triangle covers in front of the depth buffer, without
+/// writing to the target, on the calling thread.
+///
+/// # Arguments
+/// - `target`: The frame whose depth buffer the triangles are tested against. Its color
+///   buffer may be empty.
+/// - `triangles`: The triangles, visited in order.
+/// - `textures`: The textures, indexed by the triangles' `TextureId`.
+/// - `kernel`: The implementation of the inner loop to use.
+/// - `emit`: Receives, for each sample covered, its index in the target, color, inverse
+///   depth and the blend mode of its triangle.
+pub fn fill_fragments(
+    target: &mut RasterTarget,
+    triangles: &[Triangle],
+    textures: &[Texture],
+    kernel: Kernel,
+    mut emit: impl FnMut(usize, LinearColor, f32, BlendMode),
+) {
+    let mut target = RasterTarget {
+        depth_write: false,
+        ..target.reborrow()
+    };
+    for triangle in triangles {
+        let mut shade = |_: &mut [f32], index, color, depth| {
+            emit(index, color, depth, triangle.blend);
+        };
+        rasterize(&mut target, triangle, textures, kernel, &mut shade);
+    }
+}
+
+/// Finds the pixels triangles cover with a kernel, for checking kernels against each
+/// other.
+///
+/// # Arguments
+/// - `triangles`: The triangles to test.
+/// - `width`, `height`: The size of the frame, in pixels.
+/// - `kernel`: The implementation of the coverage test to use.
+///
+/// # Returns
+/// The coverage mask of every row of every block the triangles are walked in, at the
+/// center of the pixels and at each of the `MSAA_SAMPLES` samples, in order.
+pub fn coverage_masks(triangles: &[Triangle], width: u32, height: u32, kernel: Kernel) -> Vec<u32> {
+    let mut masks = Vec::new();
+    for triangle in triangles {
+        let fixed = triangle.points.map(FixedPoint::snap);
+        let [a, b, c] = fixed;
+        let area = edge_function(a, b, c);
+        if area == 0 {
+            continue;
+        }
+        // The bounds of the samples hold the pixel centers too.
+        let bounds = match pixel_bounds(fixed, &SAMPLE_POSITIONS, 0, 0, width, height) {
+            Some(bounds) => bounds,
+            None => continue,
+        };
+        let edges = [(b, c), (c, a), (a, b)].map(|(from, to)| Edge::new(from, to, area));
+        for_each_block(&edges, bounds, &SAMPLE_POSITIONS, |block| {
+            let [min_x, min_y, max_x, max_y] = block.bounds;
+            let (first, count) = ((min_x - block.x) as usize, (max_x - min_x + 1) as usize);
+            for y in min_y..=max_y {
+                for &sample in PIXEL_CENTER.iter().chain(&SAMPLE_POSITIONS) {
+                    let edges = block.edge_row(&edges, y, sample);
+                    masks.push(kernel.coverage(&edges, first, count));
+                }
+            }
+        });
+    }
+    masks
+}
+
+/// The colors of a triangle and the texture multiplying them.
+struct Surface<'a> {
+    colors: [LinearColor; 3],
+    texture: Option<(&'a Texture, [Vec2; 3])>,
+}
+
+impl<'a> Surface<'a> {
+    /// Looks up the texture of a triangle.
+    fn new(triangle: &Triangle, textures: &'a [Texture]) -> Surface<'a> {
+        Surface {
+            colors: triangle.colors,
+            texture: triangle
+                .texture
+                .map(|texture| (&textures[texture], triangle.uvs)),
+        }
+    }
+
+    /// Checks whether the surface has the same color everywhere, so it need not be
+    /// blended at every sample.
+    fn is_flat(&self) -> bool {
+        let colors = self.colors;
+        self.texture.is_none() && colors[0] == colors[1] && colors[1] == colors[2]
+    }
+
+    /// Finds the color at a point of the triangle.
+    ///
+    /// # Arguments
+    /// - `weights`: The barycentric weights of the vertices in view space.
+    fn at(&self, weights: [f32; 3]) -> LinearColor {
+        let color = blend_colors(self.colors, weights);
+        match self.texture {
+            Some((texture, uvs)) => {
+                let uv = uvs[0] * weights[0] + uvs[1] * weights[1] + uvs[2] * weights[2];
+                color * texture.sample(uv)
+            }
+            None => color,
+        }
+    }
+}
+
+/// Blends three colors, alpha included, by barycentric weights that sum to one.
+fn blend_colors(colors: [LinearColor; 3], weights: [f32; 3]) -> LinearColor {
+    colors[0] * weights[0] + colors[1] * weights[1] + colors[2] * weights[2]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::Mesh;
+    use crate::triangle::Face;
+    use crate::vector::Vec3;
+
+    const WIDTH: u32 = 200;
+    const HEIGHT: u32 = 160;
+
+    /// Fills triangles with depth writes off and additive blending, so the red channel
+    /// of each sample counts how many times it was written.
+    fn write_counts(triangles: &[Triangle], samples: u32, threads: usize) -> Vec<f32> {
+        let count = (WIDTH * HEIGHT * samples) as usize;
+        let mut color_buffer = vec![0.0; count * CHANNELS];
+        let mut depth_buffer = vec![0.0; count];
+        let target = RasterTarget {
+            depth_write: false,
+            ..RasterTarget::new(&mut color_buffer, &mut depth_buffer, WIDTH, HEIGHT, samples)
+        };
+        fill_triangles(target, triangles, &[], threads, Kernel::Scalar);
+        color_buffer
+            .chunks_exact(CHANNELS)
+            .map(|sample| sample[0])
+            .collect()
+    }
+
+    /// Turns faces of a mesh into triangles that add 1 to every sample they cover.
+    fn counting_triangles(
+        mesh: &Mesh,
+        faces: impl Iterator<Item = Face>,
+        project: impl Fn(Vec3) -> Vec2,
+    ) -> Vec<Triangle> {
+        faces
+            .map(|face| {
+                let points = [face.a, face.b, face.c].map(|i| project(mesh.vertices[i - 1]));
+                Triangle::new(
+                    points,
+                    [1.0; 3],
+                    [LinearColor::new(1.0, 0.0, 0.0, 1.0); 3],
+                    BlendMode::Additive,
+                )
+            })
+            .collect()
+    }
+
+    /// Checks that every sample was written at most once, and that the samples of every
+    /// pixel `inside` accepts were written exactly once.
+    fn assert_written_once(counts: &[f32], samples: u32, inside: impl Fn(Vec2) -> bool) {
+        for (index, &count) in counts.iter().enumerate() {
+            let pixel = index as u32 / samples;
+            let (x, y) = (pixel % WIDTH, pixel / WIDTH);
+            let center = Vec2::new(x as f32 + 0.5, y as f32 + 0.5);
+            assert!(count <= 1.0, "pixel ({}, {}) written {} times", x, y, count);
+            if inside(center) {
+                assert_eq!(count, 1.0, "gap at pixel ({}, {})", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn plane_fills_every_pixel_once() {
+        let mesh = Mesh::new_plane(2.0, 2.0, 7, 5);
+        let placements = [
+            // A slanted parallelogram, so the shared edges cross pixels at all angles.
+            (
+                Vec2::new(100.37, 80.21),
+                Vec2::new(70.3, 15.1),
+                Vec2::new(20.7, 55.9),
+            ),
+            // Squares of 20 pixels from pixel center to pixel center, so the edges and
+            // diagonals run exactly through the samples the fill rule decides between.
+            (
+                Vec2::new(100.5, 80.5),
+                Vec2::new(70.0, 0.0),
+                Vec2::new(0.0, 50.0),
+            ),
+        ];
+        for (center, u, v) in placements {
+            let project = |p: Vec3| center + u * p.x + v * p.z;
+            let triangles = counting_triangles(&mesh, mesh.faces.iter().copied(), project);
+
+            // Pixels more than a couple of pixels inside the parallelogram.
+            let determinant = u.x * v.y - u.y * v.x;
+            let inside = |p: Vec2| {
+                let d = p - center;
+                let x = (d.x * v.y - d.y * v.x) / determinant;
+                let z = (u.x * d.y - u.y * d.x) / determinant;
+                x.abs() < 0.95 && z.abs() < 0.95
+            };
+            for samples in [1, MSAA_SAMPLES] {
+                let counts = write_counts(&triangles, samples, 1);
+                assert_written_once(&counts, samples, inside);
+            }
+        }
+    }
+
+    #[test]
+    fn icosphere_fills_every_pixel_once() {
+        let mesh = Mesh::new_icosphere(1.0, 3);
+        let sphere_center = Vec3::new(0.1, -0.2, 4.0);
+        let fov_factor = 200.0;
+        let project = |p: Vec3| {
+            let p = p + sphere_center;
+            Vec2::new(
+                fov_factor * p.x / p.z + WIDTH as f32 / 2.0,
+                fov_factor * p.y / p.z + HEIGHT as f32 / 2.0,
+            )
+        };
+        // The faces turned towards the eye, at the origin, cover the sphere's outline
+        // without overlapping.
+        let front = mesh.faces.iter().copied().filter(|face| {
+            let [a, b, c] = [face.a, face.b, face.c].map(|i| mesh.vertices[i - 1]);
+            let mut normal = (b - a).cross(c - a);
+            let centroid = (a + b + c) / 3.0;
+            if normal.dot(centroid) < 0.0 {
+                normal = normal * -1.0;
+            }
+            normal.dot(centroid + sphere_center) < 0.0
+        });
+        let triangles = counting_triangles(&mesh, front, project);
+
+        let outline_center = project(Vec3::new(0.0, 0.0, 0.0));
+        let outline_radius = fov_factor / (sphere_center.len().powi(2) - 1.0).sqrt();
+        let inside = |p: Vec2| (p - outline_center).len() < 0.9 * outline_radius;
+        for samples in [1, MSAA_SAMPLES] {
+            let counts = write_counts(&triangles, samples, 1);
+            assert_written_once(&counts, samples, inside);
+        }
+    }
+
+    /// Generates the same pseudo-random numbers from 0.0 to 1.0 on every run.
+    fn random_numbers() -> impl FnMut() -> f32 {
+        let mut state: u32 = 0x2545_f491;
+        move || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as f32 / u32::MAX as f32
+        }
+    }
+
+    /// A 4 by 2 texture of distinct colors.
+    fn texture() -> Texture {
+        Texture::parse_ppm(b"P3 4 2 255  255 0 0  0 255 0  0 0 255  255 255 0  0 255 255  255 0 255  40 80 120  255 255 255").unwrap()
+    }
+
+    /// Generates triangles of every size, some reaching past the frame's edges, and
+    /// every fourth one sampling `texture` at texture coordinates beyond its edges.
+    fn random_triangles(count: usize) -> Vec<Triangle> {
+        let mut random = random_numbers();
+        (0..count)
+            .map(|i| {
+                let size = [4.0, 40.0, 400.0][i % 3];
+                let corner = Vec2::new(
+                    random() * (WIDTH as f32 + 40.0) - 20.0,
+                    random() * (HEIGHT as f32 + 40.0) - 20.0,
+                );
+                let points =
+                    [(); 3].map(|_| corner + Vec2::new(random() - 0.5, random() - 0.5) * size);
+                let inv_depths = [(); 3].map(|_| 0.1 + random());
+                let colors = [(); 3]
+                    .map(|_| LinearColor::new(random() * 2.0, random(), random(), 0.3 + random()));
+                let blend = [BlendMode::Replace, BlendMode::AlphaOver][i % 2];
+                let triangle = Triangle::new(points, inv_depths, colors, blend);
+                match i % 4 {
+                    0 => {
+                        let uvs = [(); 3].map(|_| Vec2::new(random() * 3.0 - 1.0, random() * 3.0));
+                        triangle.with_texture(0, uvs)
+                    }
+                    _ => triangle,
+                }
+            })
+            .collect()
+    }
+
+    /// Fills triangles into a cleared frame, testing and writing depth.
+    ///
+    /// # Returns
+    /// The color and depth buffers.
+    fn fill(
+        triangles: &[Triangle],
+        samples: u32,
+        threads: usize,
+        kernel: Kernel,
+    ) -> (Vec<f32>, Vec<f32>) {
+        let count = (WIDTH * HEIGHT * samples) as usize;
+        let mut color_buffer = vec![0.0; count * CHANNELS];
+        let mut depth_buffer = vec![0.0; count];
+        let target =
+            RasterTarget::new(&mut color_buffer, &mut depth_buffer, WIDTH, HEIGHT, samples);
+        fill_triangles(target, triangles, &[texture()], threads, kernel);
+        (color_buffer, depth_buffer)
+    }
+
+    /// Lists the vector kernels the CPU running the tests supports.
+    fn vector_kernels() -> Vec<Kernel> {
+        let mut kernels = Vec::new();
+        #[cfg(target_arch = "x86_64")]
+        {
+            kernels.push(Kernel::Sse2);
+            if is_x86_feature_detected!("avx2") {
+                kernels.push(Kernel::Avx2);
+            }
+        }
+        kernels
+    }
+
+    #[test]
+    fn kernels_match_the_scalar_kernel() {
+        let triangles = random_triangles(600);
+        let reference = fill(&triangles, 1, 1, Kernel::Scalar);
+        assert!(reference.1.iter().any(|&depth| depth > 0.0));
+        for kernel in vector_kernels() {
+            let (color_buffer, depth_buffer) = fill(&triangles, 1, 1, kernel);
+            assert!(
+                color_buffer == reference.0,
+                "{} colors differ",
+                kernel.name()
+            );
+            assert!(
+                depth_buffer == reference.1,
+                "{} depths differ",
+                kernel.name()
+            );
+        }
+    }
+
+    #[test]
+    fn kernels_find_the_same_coverage() {
+        // Triangles reaching the guard band too, for edge functions far from zero.
+        let mut triangles = random_triangles(600);
+        let far = 1e9;
+        let white = [LinearColor::new(1.0, 1.0, 1.0, 1.0); 3];
+        for points in [
+            [(-far, -far), (far, 10.3), (30.7, far)],
+            [(-far, 50.2), (far, 51.7), (far, 60.0)],
+        ] {
+            let points = points.map(|(x, y)| Vec2::new(x, y));
+            triangles.push(Triangle::new(points, [1.0; 3], white, BlendMode::Replace));
+        }
+
+        let reference = coverage_masks(&triangles, WIDTH, HEIGHT, Kernel::Scalar);
+        assert!(reference.iter().any(|&mask| mask != 0));
+        for kernel in vector_kernels() {
+            let masks = coverage_masks(&triangles, WIDTH, HEIGHT, kernel);
+            assert!(masks == reference, "{} coverage differs", kernel.name());
+        }
+    }
+
+    #[test]
+    fn threads_match_a_single_thread() {
+        // Overlapping triangles centered on the corners between tiles, on top of the
+        // random ones, so several tiles blend the same triangles in the same order.
+        let mut triangles = random_triangles(300);
+        let mut random = random_numbers();
+        for y in (TILE_SIZE..HEIGHT).step_by(TILE_SIZE as usize) {
+            for x in (TILE_SIZE..WIDTH).step_by(TILE_SIZE as usize) {
+                let corner = Vec2::new(x as f32, y as f32);
+                for _ in 0..4 {
+                    let points =
+                        [(); 3].map(|_| corner + Vec2::new(random() - 0.5, random() - 0.5) * 60.0);
+                    let colors =
+                        [(); 3].map(|_| LinearColor::new(random(), random() * 2.0, random(), 0.5));
+                    triangles.push(Triangle::new(
+                        points,
+                        [0.5; 3],
+                        colors,
+                        BlendMode::AlphaOver,
+                    ));
+                }
+            }
+        }
+
+        for samples in [1, MSAA_SAMPLES] {
+            let reference = fill(&triangles, samples, 1, Kernel::Scalar);
+            assert!(reference.1.iter().any(|&depth| depth > 0.0));
+            for threads in [2, 3, 8] {
+                let (color_buffer, depth_buffer) =
+                    fill(&triangles, samples, threads, Kernel::Scalar);
+                assert!(
+                    color_buffer == reference.0,
+                    "{} threads with {} samples: colors differ",
+                    threads,
+                    samples
+                );
+                assert!(
+                    depth_buffer == reference.1,
+                    "{} threads with {} samples: depths differ",
+                    threads,
+                    samples
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn blocks_keep_every_covered_sample() {
+        let kernels = [vec![Kernel::Scalar], vector_kernels()].concat();
+        let runs = [1, MSAA_SAMPLES]
+            .into_iter()
+            .flat_map(|samples| kernels.iter().map(move |&kernel| (samples, kernel)));
+        for (samples, kernel) in runs {
+            let count = (WIDTH * HEIGHT * samples) as usize;
+            let mut depth_buffer = vec![0.0; count];
+            let mut target = RasterTarget {
+                depth_write: false,
+                ..RasterTarget::new(&mut [], &mut depth_buffer, WIDTH, HEIGHT, samples)
+            };
+            let offsets = target.sample_positions();
+
+            let textures = [texture()];
+            for triangle in random_triangles(300) {
+                let mut filled = vec![false; count];
+                let mut shade = |_: &mut [f32], index: usize, _, _| filled[index] = true;
+                rasterize(&mut target, &triangle, &textures, kernel, &mut shade);
+
+                // Test every sample of the frame against the edges on its own.
+                let fixed = triangle.points.map(FixedPoint::snap);
+                let [a, b, c] = fixed;
+                let area = edge_function(a, b, c);
+                let edges = [(b, c), (c, a), (a, b)].map(|(from, to)| Edge::new(from, to, area));
+                for (index, &filled) in filled.iter().enumerate() {
+                    let pixel = index as u32 / samples;
+                    let offset = offsets[index % samples as usize];
+                    let p = sample_position((pixel % WIDTH) as i32, (pixel / WIDTH) as i32, offset);
+                    let covered = area != 0 && edges.iter().all(|edge| edge.value(p) >= 0);
+                    assert_eq!(
+                        filled,
+                        covered,
+                        "{}: sample {} of {:?}",
+                        kernel.name(),
+                        index,
+                        triangle.points
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn textures_are_sampled_with_perspective_correction() {
+        // A quad filling the frame, receding from a depth of 1 on the left to 4 on the
+        // right, with the texture's `u` running across it along the centers of its top
+        // row of texels.
+        let (w, h) = (WIDTH as f32, HEIGHT as f32);
+        let white = [LinearColor::new(1.0, 1.0, 1.0, 1.0); 3];
+        let quad = [
+            [Vec2::new(0.0, 0.0), Vec2::new(w, 0.0), Vec2::new(w, h)],
+            [Vec2::new(0.0, 0.0), Vec2::new(w, h), Vec2::new(0.0, h)],
+        ];
+        let triangles: Vec<Triangle> = quad
+            .iter()
+            .map(|points| {
+                let inv_depths = points.map(|point| if point.x == 0.0 { 1.0 } else { 0.25 });
+                let uvs = points.map(|point| Vec2::new(point.x / w, 0.75));
+                Triangle::new(*points, inv_depths, white, BlendMode::Replace).with_texture(0, uvs)
+            })
+            .collect();
+        let texture = texture();
+
+        for samples in [1, MSAA_SAMPLES] {
+            let (color_buffer, _) = fill(&triangles, samples, 1, Kernel::Scalar);
+            for (index, sample) in color_buffer.chunks_exact(CHANNELS).enumerate() {
+                let pixel = index as u32 / samples;
+                let (x, y) = ((pixel % WIDTH) as f32 + 0.5, (pixel / WIDTH) as f32 + 0.5);
+                // Pixels along the diagonal are blended at their covered samples.
+                if (x * h - y * w).abs() / (w * w + h * h).sqrt() < 1.5 {
+                    continue;
+                }
+                // Screen positions vary linearly in `1 / z`, not in `z` or `u`.
+                let s = x / w;
+                let u = s * 0.25 / ((1.0 - s) + s * 0.25);
+                let expected = texture.sample(Vec2::new(u, 0.75));
+                for (channel, expected) in sample.iter().zip([expected.r, expected.g, expected.b]) {
+                    assert!(
+                        (channel - expected).abs() < 1e-4,
+                        "{} samples, pixel ({}, {}): {:?}, expected {:?}",
+                        samples,
+                        x,
+                        y,
+                        sample,
+                        expected
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,332 @@
+// This file contains the scene graph: meshes shared between nodes, and a hierarchy of
+// nodes whose transforms are relative to their parents.
+//
+// Meshes are stored once and referred to by `MeshId`, so many instances of the same mesh
+// only cost their transforms.
+
+use crate::bounds::{Aabb, BoundingSphere};
+use crate::light::DirectionalLight;
+use crate::material::{Material, MaterialId};
+use crate::matrix::Mat4;
+use crate::mesh::Mesh;
+use crate::texture::Texture;
+use crate::vector::Vec3;
+
+/// Index of a mesh stored in a `Scene`.
+pub type MeshId = usize;
+/// Index of a node stored in a `Scene`.
+pub type NodeId = usize;
+
+/// The position, orientation and size of a node relative to its parent.
+#[derive(Debug, Copy, Clone)]
+pub struct Transform {
+    /// Translation (position) relative to the parent.
+    pub translation: Vec3,
+    /// Rotation around the x, y and z axes, in radians, applied in that order.
+    pub rotation: Vec3,
+    /// Scale along the x, y and z axes.
+    pub scale: Vec3,
+}
+
+impl Transform {
+    /// Creates a transform that leaves its node where its parent is.
+    ///
+    /// # Returns
+    /// A `Transform` with no translation or rotation and a scale of 1.
+    pub fn identity() -> Transform {
+        Transform {
+            translation: Vec3::new(0.0, 0.0, 0.0), // Default position is the origin.
+            rotation: Vec3::new(0.0, 0.0, 0.0),    // No rotation by default.
+            scale: Vec3::new(1.0, 1.0, 1.0),       // Default scale is 1.
+        }
+    }
+
+    /// Builds the matrix of the transform, which scales, then rotates around X, Y and Z,
+    /// then translates.
+    ///
+    /// # Returns
+    /// The local matrix as a `Mat4`.
+    pub fn matrix(&self) -> Mat4 {
+        Mat4::translation(self.translation)
+            * Mat4::rotation_z(self.rotation.z)
+            * Mat4::rotation_y(self.rotation.y)
+            * Mat4::rotation_x(self.rotation.x)
+            * Mat4::scale(self.scale)
+    }
+
+    /// Linearly interpolates every component between two transforms.
+    ///
+    /// # Arguments
+    /// - `other`: The transform to interpolate towards.
+    /// - `t`: The interpolation factor, where `0.0` gives `self` and `1.0` gives `other`.
+    ///
+    /// # Returns
+    /// A new interpolated `Transform`.
+    pub fn lerp(&self, other: &Transform, t: f32) -> Transform {
+        Transform {
+            translation: self.translation.lerp(other.translation, t),
+            rotation: self.rotation.lerp(other.rotation, t),
+            scale: self.scale.lerp(other.scale, t),
+        }
+    }
+}
+
+/// An element of the scene hierarchy.
+#[derive(Debug, Clone)]
+pub struct Node {
+    /// Name used to identify the node.
+    #[allow(dead_code)]
+    pub name: String,
+    /// Transform relative to the parent node.
+    pub transform: Transform,
+    /// Mesh drawn at this node, if any.
+    pub mesh: Option<MeshId>,
+    /// Material of the mesh. Nodes without one use their parent's.
+    pub material: Option<MaterialId>,
+    /// Nodes whose transforms are relative to this one.
+    pub children: Vec<NodeId>,
+    /// Whether this node and its children are drawn.
+    pub visible: bool,
+    /// Rotation speed around the x, y and z axes, in radians per second.
+    pub angular_velocity: Vec3,
+}
+
+impl Node {
+    /// Creates a new visible, stationary `Node`.
+    ///
+    /// # Arguments
+    /// - `name`: Name used to identify the node.
+    /// - `mesh`: Mesh drawn at this node, if any.
+    ///
+    /// # Returns
+    /// A new `Node` with an identity transform and no children.
+    pub fn new(name: &str, mesh: Option<MeshId>) -> Node {
+        Node {
+            name: name.to_string(),
+            transform: Transform::identity(),
+            mesh,
+            material: None,
+            children: Vec::new(),
+            visible: true,
+            angular_velocity: Vec3::new(0.0, 0.0, 0.0),
+        }
+    }
+}
+
+/// A mesh placed in the world by a node.
+#[derive(Debug, Copy, Clone)]
+pub struct MeshInstance {
+    /// The mesh to draw.
+    pub mesh: MeshId,
+    /// The material to draw the mesh with, if any.
+    pub material: Option<MaterialId>,
+    /// Transformation from the mesh's model space into world space.
+    pub world: Mat4,
+}
+
+/// Calculates the bounding volumes cached for a mesh.
+fn mesh_bounds(mesh: &Mesh) -> Option<(Aabb, BoundingSphere)> {
+    Some((mesh.aabb()?, mesh.bounding_sphere()?))
+}
+
+/// A hierarchy of nodes referencing shared meshes.
+///
+/// Meshes are stored once and referenced by index, so several nodes can draw the same
+/// mesh data with different transforms.
+pub struct Scene {
+    /// Mesh data referenced by nodes.
+    pub meshes: Vec<Mesh>,
+    /// Bounding box and sphere of each mesh in its own coordinates, indexed by
+    /// `MeshId`, or `None` for meshes without vertices. Computed when meshes are added.
+    pub mesh_bounds: Vec<Option<(Aabb, BoundingSphere)>>,
+    /// Materials referenced by nodes.
+    pub materials: Vec<Material>,
+    /// Textures referenced by materials, indexed by `TextureId`.
+    pub textures: Vec<Texture>,
+    /// Lights illuminating the scene.
+    pub lights: Vec<DirectionalLight>,
+    /// All nodes of the scene.
+    pub nodes: Vec<Node>,
+    /// Nodes without a parent.
+    pub roots: Vec<NodeId>,
+}
+
+impl Scene {
+    /// Creates an empty `Scene`.
+    ///
+    /// # Returns
+    /// A `Scene` with no meshes or nodes.
+    pub fn new() -> Scene {
+        Scene {
+            meshes: Vec::new(),
+            mesh_bounds: Vec::new(),
+            materials: Vec::new(),
+            textures: Vec::new(),
+            lights: Vec::new(),
+            nodes: Vec::new(),
+            roots: Vec::new(),
+        }
+    }
+
+    /// Adds mesh data that nodes can reference.
+    ///
+    /// # Arguments
+    /// - `mesh`: The mesh to add.
+    ///
+    /// # Returns
+    /// The `MeshId` of the added mesh.
+    pub fn add_mesh(&mut self, mesh: Mesh) -> MeshId {
+        self.mesh_bounds.push(mesh_bounds(&mesh));
+        self.meshes.push(mesh);
+        self.meshes.len() - 1
+    }
+
+    /// Adds a node to the hierarchy.
+    ///
+    /// # Arguments
+    /// - `node`: The node to add.
+    /// - `parent`: The node to attach it to, or `None` to make it a root.
+    ///
+    /// # Returns
+    /// The `NodeId` of the added node.
+    pub fn add_node(&mut self, node: Node, parent: Option<NodeId>) -> NodeId {
+        let id = self.nodes.len();
+        self.nodes.push(node);
+        match parent {
+            Some(parent) => self.nodes[parent].children.push(id),
+            None => self.roots.push(id),
+        }
+        id
+    }
+
+    /// Moves the contents of another scene into this one.
+    ///
+    /// # Arguments
+    /// - `other`: The scene to take the meshes, materials, textures, lights and nodes of.
+    /// - `parent`: The node to attach the other scene's roots to, or `None` to make
+    ///   them roots of this scene.
+    pub fn import(&mut self, other: Scene, parent: Option<NodeId>) {
+        let mesh_offset = self.meshes.len();
+        let material_offset = self.materials.len();
+        let texture_offset = self.textures.len();
+        let node_offset = self.nodes.len();
+
+        self.meshes.extend(other.meshes);
+        self.mesh_bounds.extend(other.mesh_bounds);
+        self.materials
+            .extend(other.materials.into_iter().map(|mut material| {
+                material.texture = material.texture.map(|texture| texture + texture_offset);
+                material
+            }));
+        self.textures.extend(other.textures);
+        self.lights.extend(other.lights);
+        self.nodes.extend(other.nodes.into_iter().map(|mut node| {
+            node.mesh = node.mesh.map(|mesh| mesh + mesh_offset);
+            node.material = node.material.map(|material| material + material_offset);
+            for child in &mut node.children {
+                *child += node_offset;
+            }
+            node
+        }));
+
+        let roots = other.roots.iter().map(|root| root + node_offset);
+        match parent {
+            Some(parent) => self.nodes[parent].children.extend(roots),
+            None => self.roots.extend(roots),
+        }
+    }
+
+    /// Loads the diffuse texture of every material that names one and has not loaded it
+    /// yet. Materials naming the same file share one texture.
+    ///
+    /// # Errors
+    /// Returns a message if a texture cannot be read or is not a PPM image.
+    pub fn load_textures(&mut self) -> Result<(), String> {
+        for i in 0..self.materials.len() {
+            let path = match (
+                &self.materials[i].diffuse_texture,
+                self.materials[i].texture,
+            ) {
+                (Some(path), None) => path.clone(),
+                _ => continue,
+            };
+            let loaded = self.materials[..i]
+                .iter()
+                .find(|material| material.diffuse_texture.as_ref() == Some(&path))
+                .and_then(|material| material.texture);
+            let texture = match loaded {
+                Some(texture) => texture,
+                None => {
+                    self.textures.push(Texture::load_ppm(&path)?);
+                    self.textures.len() - 1
+                }
+            };
+            self.materials[i].texture = Some(texture);
+        }
+        Ok(())
+    }
+
+    /// Collects the local transform of every node, indexed by `NodeId`.
+    ///
+    /// # Returns
+    /// A copy of the node transforms.
+    pub fn transforms(&self) -> Vec<Transform> {
+        self.nodes.iter().map(|node| node.transform).collect()
+    }
+
+    /// Traverses the hierarchy and collects every visible mesh instance.
+    ///
+    /// # Arguments
+    /// - `transforms`: The local transform to use for each node, indexed by `NodeId`.
+    ///   This lets callers draw interpolated transforms without modifying the scene.
+    ///
+    /// # Returns
+    /// The instances with their world matrices, composed down the hierarchy.
+    pub fn instances(&self, transforms: &[Transform]) -> Vec<MeshInstance> {
+        let mut instances = Vec::new();
+        let mut stack: Vec<(NodeId, Mat4, Option<MaterialId>)> = self
+            .roots
+            .iter()
+            .rev()
+            .map(|&root| (root, Mat4::identity(), None))
+            .collect();
+
+        while let Some((id, parent_world, parent_material)) = stack.pop() {
+            let node = &self.nodes[id];
+            if !node.visible {
+                continue; // Hidden nodes hide their whole subtree.
+            }
+
+            let world = parent_world * transforms[id].matrix();
+            let material = node.material.or(parent_material);
+            if let Some(mesh) = node.mesh {
+                instances.push(MeshInstance {
+                    mesh,
+                    material,
+                    world,
+                });
+            }
+            for &child in node.children.iter().rev() {
+                stack.push((child, world, material));
+            }
+        }
+        instances
+    }
+
+    /// Calculates the world-space bounding box of all visible mesh instances.
+    ///
+    /// # Arguments
+    /// - `transforms`: The local transform to use for each node, indexed by `NodeId`.
+    ///
+    /// # Returns
+    /// The box, or `None` if nothing is visible.
+    pub fn bounds(&self, transforms: &[Transform]) -> Option<Aabb> {
+        self.instances(transforms)
+            .iter()
+            .filter_map(|instance| {
+                let (aabb, _) = self.mesh_bounds[instance.mesh]?;
+                Some(aabb.transformed(&instance.world))
+            })
+            .reduce(|a, b| a.union(b))
+    }
+}
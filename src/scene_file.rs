@@ -0,0 +1,1046 @@
+// This file contains the reader for scene description files.
+//
+// A scene file is a small TOML-like text format. Lines hold either a section header or a
+// `key = value` pair, and `#` starts a comment. Values are numbers, booleans, quoted
+// strings or arrays of numbers such as `[0, 1.5, -2]`. Colors are arrays of three
+// channels from 0.0 to 1.0, hex strings such as `"#ff8800"` or names such as `"white"`,
+// and are sRGB-encoded, as color pickers give them. Angles are in degrees and paths are
+// relative to the scene file.
+//
+//     background = "#101018"
+//     mtllib = "materials.mtl"          # may be repeated
+//
+//     [render]
+//     mode = "solid_wireframe"          # wireframe, solid or solid_wireframe
+//     backface_culling = true
+//     frustum_culling = true            # skip meshes whose bounds are off-screen
+//     show_vertices = false
+//     wireframe_color = [0, 0.6, 0]
+//     line_style = "aliased"            # aliased or smooth
+//     line_width = 1                    # in pixels, from 1 to 32
+//     line_cap = "butt"                 # butt, square or round, for lines wider than 1
+//     ambient = 0.1
+//     vertex_colors = true              # shade meshes that have them with vertex colors
+//     threads = 0                       # threads drawing the frame; 0 for one per core
+//     simd = true                       # fill pixels with SSE2/AVX2 when available
+//     antialiasing = "off"              # off, ssaa2, ssaa4 or msaa4
+//     downsample_filter = "box"         # box or tent, for ssaa2 and ssaa4
+//     transparency = "sorted"           # sorted, weighted or abuffer
+//     exposure = 0                      # in stops; each one doubles the brightness
+//     tonemap = "none"                  # none, reinhard or aces
+//
+//     [camera]
+//     target = [0, 0, 0]                # defaults to the center of the scene
+//     distance = 5                      # defaults to fitting the scene in view
+//     yaw = 0
+//     pitch = 0
+//     fov = 46                          # vertical field of view
+//
+//     [material.steel]
+//     color = [0.6, 0.6, 0.65]
+//     opacity = 1
+//     blend = "alpha"                   # replace, alpha, premultiplied, additive or multiply
+//     texture = "steel.ppm"             # a PPM image multiplying the color
+//
+//     [light.sun]
+//     direction = [0.5, 1, 1]
+//     color = [1, 1, 0.9]
+//     intensity = 1
+//
+//     [node.body]
+//     mesh = "f22.obj"                  # or `primitive = "sphere"`, see `Mesh::new_primitive`
+//     center = true                     # move the mesh's bounding-box center to the origin
+//     normalize = 2                     # scale the mesh so its largest side is this long
+//     material = "steel"
+//     parent = "root"                   # must be declared earlier in the file
+//     translation = [0, 0, 0]
+//     rotation = [0, 90, 0]
+//     scale = [1, 1, 1]                 # or a single number
+//     spin = [0, 30, 0]                 # degrees per second
+//     visible = true
+//
+//     [node.car]
+//     import = "car.glb"                # attaches a glTF file's nodes as children
+//
+// A `.gltf` or `.glb` file can also be loaded in place of a scene file, and is then shown
+// shaded by a single light.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::antialias::{AntiAliasing, DownsampleFilter};
+use crate::blend::BlendMode;
+use crate::color::Color;
+use crate::display::{LineCap, LineStyle, MAX_LINE_WIDTH};
+use crate::gltf;
+use crate::light::DirectionalLight;
+use crate::material::Material;
+use crate::mesh::Mesh;
+use crate::scene::{MeshId, Node, NodeId, Scene};
+use crate::settings::{RenderMode, RenderSettings};
+use crate::tonemap::Tonemapper;
+use crate::transparency::Transparency;
+use crate::vector::Vec3;
+
+/// An error found while reading a scene file, with the position it was found at.
+#[derive(Debug, Clone)]
+pub struct ParseError {
+    /// The 1-based line of the error.
+    pub line: usize,
+    /// The 1-based column of the error, counted in characters.
+    pub column: usize,
+    /// A description of the problem.
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+/// The camera settings of a scene file. Missing values are filled in by the viewer.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct CameraDescription {
+    /// The point the camera orbits around.
+    pub target: Option<Vec3>,
+    /// Distance between the camera and its target.
+    pub distance: Option<f32>,
+    /// Rotation around the world Y axis, in radians.
+    pub yaw: f32,
+    /// Elevation above the target, in radians.
+    pub pitch: f32,
+    /// Vertical field of view, in radians.
+    pub fov: Option<f32>,
+}
+
+/// Everything declared by a scene file.
+pub struct SceneDescription {
+    /// The meshes, materials, lights and nodes of the scene.
+    pub scene: Scene,
+    /// How the camera is set up.
+    pub camera: CameraDescription,
+    /// How the scene is drawn.
+    pub settings: RenderSettings,
+    /// Every file the scene was built from, including the scene file itself when it
+    /// was loaded with `load`.
+    pub files: Vec<PathBuf>,
+}
+
+/// A value on the right-hand side of a `key = value` line.
+#[derive(Debug, Clone)]
+enum Value {
+    Number(f32),
+    Bool(bool),
+    Str(String),
+    Array(Vec<f32>),
+}
+
+/// A `key = value` line.
+#[derive(Debug, Clone)]
+struct Entry {
+    key: String,
+    value: Value,
+    /// Position of the key.
+    line: usize,
+    column: usize,
+    /// Column where the value starts.
+    value_column: usize,
+}
+
+impl Entry {
+    /// Creates an error pointing at the value of the entry.
+    fn error(&self, message: &str) -> ParseError {
+        ParseError {
+            line: self.line,
+            column: self.value_column,
+            message: message.to_string(),
+        }
+    }
+
+    fn number(&self) -> Result<f32, ParseError> {
+        match self.value {
+            Value::Number(n) => Ok(n),
+            _ => Err(self.error(&format!("`{}` expects a number", self.key))),
+        }
+    }
+
+    fn count(&self) -> Result<usize, ParseError> {
+        match self.value {
+            Value::Number(n) if n >= 0.0 && n.fract() == 0.0 => Ok(n as usize),
+            _ => Err(self.error(&format!("`{}` expects a whole number", self.key))),
+        }
+    }
+
+    fn boolean(&self) -> Result<bool, ParseError> {
+        match self.value {
+            Value::Bool(b) => Ok(b),
+            _ => Err(self.error(&format!("`{}` expects `true` or `false`", self.key))),
+        }
+    }
+
+    fn string(&self) -> Result<&str, ParseError> {
+        match &self.value {
+            Value::Str(s) => Ok(s),
+            _ => Err(self.error(&format!("`{}` expects a quoted string", self.key))),
+        }
+    }
+
+    fn vec3(&self) -> Result<Vec3, ParseError> {
+        match &self.value {
+            Value::Array(values) if values.len() == 3 => {
+                Ok(Vec3::new(values[0], values[1], values[2]))
+            }
+            _ => Err(self.error(&format!("`{}` expects an array of 3 numbers", self.key))),
+        }
+    }
+
+    /// Reads a vector of angles in degrees, converted to radians.
+    fn angles(&self) -> Result<Vec3, ParseError> {
+        Ok(self.vec3()? * std::f32::consts::PI / 180.0)
+    }
+
+    /// Reads a color given as `[r, g, b]`, as a hex string such as `"#rrggbb"`, or by
+    /// name; see `Color::parse`. The alpha of hex strings is ignored.
+    fn color(&self) -> Result<Vec3, ParseError> {
+        if let Value::Str(text) = &self.value {
+            return match Color::parse(text) {
+                Ok(color) => {
+                    let [r, g, b, _] = color.to_encoded();
+                    Ok(Vec3::new(r, g, b))
+                }
+                Err(e) => Err(self.error(&e)),
+            };
+        }
+        self.vec3().map_err(|_| {
+            self.error(&format!(
+                "`{}` expects a color like [r, g, b] or \"#rrggbb\"",
+                self.key
+            ))
+        })
+    }
+
+    /// Reads an opaque color as `color` does, for the settings drawn straight into the
+    /// window's color buffer.
+    fn color_bytes(&self) -> Result<Color, ParseError> {
+        let rgb = self.color()?;
+        Ok(Color::from_encoded([rgb.x, rgb.y, rgb.z, 1.0]))
+    }
+}
+
+/// A `[kind.name]` header and the entries below it.
+#[derive(Debug, Clone)]
+struct Section {
+    kind: String,
+    name: Option<String>,
+    entries: Vec<Entry>,
+    line: usize,
+}
+
+impl Section {
+    /// Creates an error pointing at the section header.
+    fn error(&self, message: &str) -> ParseError {
+        ParseError {
+            line: self.line,
+            column: 1,
+            message: message.to_string(),
+        }
+    }
+}
+
+/// Identifies a mesh loaded by a node: the key naming its source (`mesh` or
+/// `primitive`), the file or primitive name, and the `center` and `normalize` options as
+/// bits.
+type MeshKey = (String, String, (bool, Option<u32>));
+
+/// Loads a scene file and every mesh, material and texture file it references.
+///
+/// # Arguments
+/// - `filename`: The path to the scene file.
+///
+/// # Returns
+/// The scene and settings declared in the file.
+///
+/// # Errors
+/// Returns a message starting with `file:line:column` if the file cannot be read or
+/// contains invalid data, or starting with the texture's path if a texture cannot be
+/// loaded.
+pub fn load(filename: &str) -> Result<SceneDescription, String> {
+    let extension = Path::new(filename)
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase());
+    if matches!(extension.as_deref(), Some("gltf" | "glb")) {
+        return load_gltf(filename);
+    }
+
+    let contents = std::fs::read_to_string(filename).map_err(|e| format!("{}: {}", filename, e))?;
+    let directory = Path::new(filename).parent().unwrap_or(Path::new(""));
+    let mut description = parse(&contents, directory).map_err(|e| format!("{}:{}", filename, e))?;
+    description.files.insert(0, PathBuf::from(filename));
+    description.scene.load_textures()?;
+    Ok(description)
+}
+
+/// Lists the files a scene file refers to, without loading them.
+///
+/// This is what is left to watch when `load` fails: a mesh or texture that is missing
+/// is still listed, so creating it can be noticed.
+///
+/// # Arguments
+/// - `filename`: The path to the scene file.
+///
+/// # Returns
+/// The scene file itself, followed by the paths of its `mtllib`, `texture`, `import` and
+/// `mesh` entries. Only the scene file is listed if it cannot be read or split into
+/// sections.
+pub fn referenced_files(filename: &str) -> Vec<PathBuf> {
+    let mut files = vec![PathBuf::from(filename)];
+    let directory = Path::new(filename).parent().unwrap_or(Path::new(""));
+    let sections = match std::fs::read_to_string(filename) {
+        Ok(contents) => parse_sections(&contents).unwrap_or_default(),
+        Err(_) => Vec::new(),
+    };
+    for entry in sections.iter().flat_map(|section| &section.entries) {
+        if let ("mtllib" | "texture" | "import" | "mesh", Value::Str(path)) =
+            (entry.key.as_str(), &entry.value)
+        {
+            let path = directory.join(path);
+            if !files.contains(&path) {
+                files.push(path);
+            }
+        }
+    }
+    files
+}
+
+/// Shows a glTF file on its own, as if from a scene file that only imports it.
+fn load_gltf(filename: &str) -> Result<SceneDescription, String> {
+    let import = gltf::load(filename)?;
+    let mut scene = import.scene;
+    scene.load_textures()?;
+    if scene.lights.is_empty() {
+        scene
+            .lights
+            .push(DirectionalLight::new(Vec3::new(-0.5, -1.0, 1.0)));
+    }
+
+    let mut files = vec![PathBuf::from(filename)];
+    files.extend(import.files);
+    Ok(SceneDescription {
+        scene,
+        camera: CameraDescription::default(),
+        settings: RenderSettings {
+            mode: RenderMode::Solid,
+            show_vertices: false,
+            ..RenderSettings::default()
+        },
+        files,
+    })
+}
+
+/// Parses the contents of a scene file.
+///
+/// # Arguments
+/// - `contents`: The text of the scene file.
+/// - `directory`: The directory relative paths are resolved against.
+///
+/// # Returns
+/// The scene and settings declared in the text.
+pub fn parse(contents: &str, directory: &Path) -> Result<SceneDescription, ParseError> {
+    let sections = parse_sections(contents)?;
+
+    let mut scene = Scene::new();
+    let mut camera = CameraDescription::default();
+    let mut settings = RenderSettings::default();
+    let mut meshes: HashMap<MeshKey, MeshId> = HashMap::new();
+    let mut files: Vec<PathBuf> = Vec::new();
+    let mut nodes: HashMap<String, NodeId> = HashMap::new();
+    // Node materials are resolved once every material has been declared.
+    let mut node_materials: Vec<(NodeId, Entry)> = Vec::new();
+
+    for section in &sections {
+        match (section.kind.as_str(), &section.name) {
+            ("", _) => {
+                for entry in &section.entries {
+                    match entry.key.as_str() {
+                        "background" => settings.background = entry.color_bytes()?,
+                        "mtllib" => {
+                            let path = directory.join(entry.string()?);
+                            let materials = Material::load_mtl(&path.to_string_lossy())
+                                .map_err(|e| entry.error(&e))?;
+                            files.push(path);
+                            files.extend(
+                                materials
+                                    .iter()
+                                    .filter_map(|material| material.diffuse_texture.as_ref())
+                                    .map(PathBuf::from),
+                            );
+                            scene.materials.extend(materials);
+                        }
+                        _ => return Err(unknown_key(entry, "the top level")),
+                    }
+                }
+            }
+            ("render", None) => {
+                for entry in &section.entries {
+                    match entry.key.as_str() {
+                        "mode" => {
+                            settings.mode =
+                                RenderMode::from_name(entry.string()?).ok_or_else(|| {
+                                    entry.error(
+                                        "expected \"wireframe\", \"solid\" or \"solid_wireframe\"",
+                                    )
+                                })?
+                        }
+                        "backface_culling" => settings.backface_culling = entry.boolean()?,
+                        "frustum_culling" => settings.frustum_culling = entry.boolean()?,
+                        "show_vertices" => settings.show_vertices = entry.boolean()?,
+                        "wireframe_color" => settings.wireframe_color = entry.color_bytes()?,
+                        "line_style" => {
+                            settings.line_style = LineStyle::from_name(entry.string()?)
+                                .ok_or_else(|| entry.error("expected \"aliased\" or \"smooth\""))?
+                        }
+                        "line_width" => {
+                            let width = entry.number()?;
+                            if !(1.0..=MAX_LINE_WIDTH).contains(&width) {
+                                return Err(entry.error(&format!(
+                                    "line_width must be between 1 and {} pixels",
+                                    MAX_LINE_WIDTH
+                                )));
+                            }
+                            settings.line_width = width;
+                        }
+                        "line_cap" => {
+                            settings.line_cap =
+                                LineCap::from_name(entry.string()?).ok_or_else(|| {
+                                    entry.error("expected \"butt\", \"square\" or \"round\"")
+                                })?
+                        }
+                        "ambient" => settings.ambient = entry.number()?,
+                        "vertex_colors" => settings.vertex_colors = entry.boolean()?,
+                        "threads" => settings.threads = entry.count()?,
+                        "simd" => settings.simd = entry.boolean()?,
+                        "antialiasing" => {
+                            settings.antialiasing = AntiAliasing::from_name(entry.string()?)
+                                .ok_or_else(|| {
+                                    entry.error(
+                                        "expected \"off\", \"ssaa2\", \"ssaa4\" or \"msaa4\"",
+                                    )
+                                })?
+                        }
+                        "downsample_filter" => {
+                            settings.downsample_filter =
+                                DownsampleFilter::from_name(entry.string()?)
+                                    .ok_or_else(|| entry.error("expected \"box\" or \"tent\""))?
+                        }
+                        "transparency" => {
+                            settings.transparency = Transparency::from_name(entry.string()?)
+                                .ok_or_else(|| {
+                                    entry.error("expected \"sorted\", \"weighted\" or \"abuffer\"")
+                                })?
+                        }
+                        "exposure" => settings.exposure = entry.number()?,
+                        "tonemap" => {
+                            settings.tonemapper = Tonemapper::from_name(entry.string()?)
+                                .ok_or_else(|| {
+                                    entry.error("expected \"none\", \"reinhard\" or \"aces\"")
+                                })?
+                        }
+                        _ => return Err(unknown_key(entry, "[render]")),
+                    }
+                }
+            }
+            ("camera", None) => {
+                for entry in &section.entries {
+                    match entry.key.as_str() {
+                        "target" => camera.target = Some(entry.vec3()?),
+                        "distance" => camera.distance = Some(entry.number()?),
+                        "yaw" => camera.yaw = entry.number()?.to_radians(),
+                        "pitch" => camera.pitch = entry.number()?.to_radians(),
+                        "fov" => {
+                            let fov = entry.number()?;
+                            if !(1.0..=179.0).contains(&fov) {
+                                return Err(entry.error("fov must be between 1 and 179 degrees"));
+                            }
+                            camera.fov = Some(fov.to_radians());
+                        }
+                        _ => return Err(unknown_key(entry, "[camera]")),
+                    }
+                }
+            }
+            ("material", Some(name)) => {
+                let mut material = Material::new(name);
+                for entry in &section.entries {
+                    match entry.key.as_str() {
+                        "color" => material.diffuse = entry.color()?,
+                        "opacity" => material.opacity = entry.number()?,
+                        "blend" => {
+                            material.blend =
+                                BlendMode::from_name(entry.string()?).ok_or_else(|| {
+                                    entry.error(
+                                        "expected \"replace\", \"alpha\", \"premultiplied\", \"additive\" or \"multiply\"",
+                                    )
+                                })?
+                        }
+                        "texture" => {
+                            let path = directory.join(entry.string()?);
+                            material.diffuse_texture = Some(path.to_string_lossy().into_owned());
+                            files.push(path);
+                        }
+                        _ => return Err(unknown_key(entry, "a material")),
+                    }
+                }
+                scene.materials.push(material);
+            }
+            ("light", Some(_)) => {
+                let mut light = DirectionalLight::new(Vec3::new(0.0, 0.0, 1.0));
+                for entry in &section.entries {
+                    match entry.key.as_str() {
+                        "type" if entry.string()? == "directional" => {}
+                        "type" => {
+                            return Err(entry.error("only \"directional\" lights are supported"))
+                        }
+                        "direction" => {
+                            let direction = entry.vec3()?;
+                            if direction.len() == 0.0 {
+                                return Err(entry.error("direction must not be zero"));
+                            }
+                            light.direction = direction.normalize();
+                        }
+                        "color" => light.color = entry.color()?,
+                        "intensity" => light.intensity = entry.number()?,
+                        _ => return Err(unknown_key(entry, "a light")),
+                    }
+                }
+                scene.lights.push(light);
+            }
+            ("node", Some(name)) => {
+                if nodes.contains_key(name) {
+                    return Err(section.error(&format!("node `{}` is declared twice", name)));
+                }
+
+                let mut node = Node::new(name, None);
+                let mut parent = None;
+                let mut material = None;
+                let mut import = None;
+                let mut source: Option<Entry> = None;
+                let mut center = false;
+                let mut normalize = None;
+                for entry in &section.entries {
+                    match entry.key.as_str() {
+                        "mesh" | "primitive" => source = Some(entry.clone()),
+                        "center" => center = entry.boolean()?,
+                        "normalize" => normalize = Some(entry.number()?),
+                        "material" => material = Some(entry.clone()),
+                        "import" => {
+                            let path = directory.join(entry.string()?);
+                            let imported =
+                                gltf::load(&path.to_string_lossy()).map_err(|e| entry.error(&e))?;
+                            files.push(path);
+                            files.extend(imported.files);
+                            import = Some(imported.scene);
+                        }
+                        "parent" => {
+                            let parent_name = entry.string()?;
+                            parent = Some(*nodes.get(parent_name).ok_or_else(|| {
+                                entry.error(&format!(
+                                    "unknown parent `{}`; parents must be declared first",
+                                    parent_name
+                                ))
+                            })?);
+                        }
+                        "translation" => node.transform.translation = entry.vec3()?,
+                        "rotation" => node.transform.rotation = entry.angles()?,
+                        "scale" => {
+                            node.transform.scale = match entry.value {
+                                Value::Number(n) => Vec3::new(n, n, n),
+                                _ => entry.vec3()?,
+                            }
+                        }
+                        "spin" => node.angular_velocity = entry.angles()?,
+                        "visible" => node.visible = entry.boolean()?,
+                        _ => return Err(unknown_key(entry, "a node")),
+                    }
+                }
+
+                if let Some(entry) = source {
+                    // Meshes are shared between nodes that load them the same way.
+                    let options = (center, normalize.map(f32::to_bits));
+                    let key = (entry.key.clone(), entry.string()?.to_string(), options);
+                    node.mesh = Some(match meshes.get(&key) {
+                        Some(&mesh) => mesh,
+                        None => {
+                            let mut mesh = if entry.key == "mesh" {
+                                let path = directory.join(entry.string()?);
+                                let mesh = Mesh::load(&path.to_string_lossy())
+                                    .map_err(|e| entry.error(&e))?;
+                                if !files.contains(&path) {
+                                    files.push(path);
+                                }
+                                mesh
+                            } else {
+                                let name = entry.string()?;
+                                Mesh::new_primitive(name).ok_or_else(|| {
+                                    entry.error(&format!("unknown primitive `{}`", name))
+                                })?
+                            };
+                            if center {
+                                mesh.center();
+                            }
+                            if let Some(size) = normalize {
+                                mesh.normalize(size);
+                            }
+                            let id = scene.add_mesh(mesh);
+                            meshes.insert(key, id);
+                            id
+                        }
+                    });
+                }
+
+                let id = scene.add_node(node, parent);
+                nodes.insert(name.clone(), id);
+                if let Some(imported) = import {
+                    scene.import(imported, Some(id));
+                }
+                if let Some(material) = material {
+                    node_materials.push((id, material));
+                }
+            }
+            ("render", Some(_)) | ("camera", Some(_)) => {
+                return Err(section.error(&format!("[{}] does not take a name", section.kind)))
+            }
+            ("material", None) | ("light", None) | ("node", None) => {
+                return Err(
+                    section.error(&format!("expected a name, as in [{}.name]", section.kind))
+                )
+            }
+            (kind, _) => return Err(section.error(&format!("unknown section `{}`", kind))),
+        }
+    }
+
+    for (id, entry) in node_materials {
+        let name = entry.string()?;
+        let material = scene
+            .materials
+            .iter()
+            .position(|material| material.name == name)
+            .ok_or_else(|| entry.error(&format!("unknown material `{}`", name)))?;
+        scene.nodes[id].material = Some(material);
+    }
+
+    Ok(SceneDescription {
+        scene,
+        camera,
+        settings,
+        files,
+    })
+}
+
+/// Creates the error for a key that does not belong in its section.
+fn unknown_key(entry: &Entry, place: &str) -> ParseError {
+    ParseError {
+        line: entry.line,
+        column: entry.column,
+        message: format!("unknown key `{}` in {}", entry.key, place),
+    }
+}
+
+/// Splits a scene file into sections of parsed entries. Entries before the first header
+/// belong to a section with an empty kind.
+fn parse_sections(contents: &str) -> Result<Vec<Section>, ParseError> {
+    let mut sections = vec![Section {
+        kind: String::new(),
+        name: None,
+        entries: Vec::new(),
+        line: 1,
+    }];
+
+    for (index, text) in contents.lines().enumerate() {
+        let line = index + 1;
+        let chars: Vec<char> = text.chars().collect();
+        let mut cursor = Cursor {
+            chars: &chars,
+            position: 0,
+            line,
+        };
+
+        cursor.skip_whitespace();
+        match cursor.peek() {
+            None | Some('#') => continue,
+            Some('[') => {
+                cursor.position += 1;
+                let start = cursor.position;
+                while cursor.peek().is_some_and(|c| c != ']') {
+                    cursor.position += 1;
+                }
+                if cursor.peek() != Some(']') {
+                    return Err(cursor.error("expected `]` to close the section header"));
+                }
+                let header: String = chars[start..cursor.position].iter().collect();
+                cursor.position += 1;
+                cursor.expect_end()?;
+
+                let header = header.trim();
+                let (kind, name) = match header.split_once('.') {
+                    Some((kind, name)) => (kind.trim(), Some(name.trim().to_string())),
+                    None => (header, None),
+                };
+                if kind.is_empty() || name.as_deref() == Some("") {
+                    return Err(ParseError {
+                        line,
+                        column: start + 1,
+                        message: "empty section name".to_string(),
+                    });
+                }
+                sections.push(Section {
+                    kind: kind.to_string(),
+                    name,
+                    entries: Vec::new(),
+                    line,
+                });
+            }
+            Some(_) => {
+                let column = cursor.position + 1;
+                let start = cursor.position;
+                while cursor
+                    .peek()
+                    .is_some_and(|c| c.is_alphanumeric() || c == '_')
+                {
+                    cursor.position += 1;
+                }
+                if cursor.position == start {
+                    return Err(cursor.error("expected a key or a section header"));
+                }
+                let key: String = chars[start..cursor.position].iter().collect();
+
+                cursor.skip_whitespace();
+                if cursor.peek() != Some('=') {
+                    return Err(cursor.error(&format!("expected `=` after `{}`", key)));
+                }
+                cursor.position += 1;
+                cursor.skip_whitespace();
+                let value_column = cursor.position + 1;
+                let value = cursor.value()?;
+                cursor.expect_end()?;
+
+                sections.last_mut().unwrap().entries.push(Entry {
+                    key,
+                    value,
+                    line,
+                    column,
+                    value_column,
+                });
+            }
+        }
+    }
+    Ok(sections)
+}
+
+/// Reads values from a single line, tracking the column for error messages.
+struct Cursor<'a> {
+    chars: &'a [char],
+    position: usize,
+    line: usize,
+}
+
+impl Cursor<'_> {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.position += 1;
+        }
+    }
+
+    /// Creates an error pointing at the current position.
+    fn error(&self, message: &str) -> ParseError {
+        ParseError {
+            line: self.line,
+            column: self.position + 1,
+            message: message.to_string(),
+        }
+    }
+
+    /// Checks that only whitespace or a comment is left on the line.
+    fn expect_end(&mut self) -> Result<(), ParseError> {
+        self.skip_whitespace();
+        match self.peek() {
+            None | Some('#') => Ok(()),
+            Some(c) => Err(self.error(&format!("unexpected `{}`", c))),
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, ParseError> {
+        match self.peek() {
+            Some('"') => self.string().map(Value::Str),
+            Some('[') => self.array().map(Value::Array),
+            Some(_) => {
+                let start = self.position;
+                let word = self.word();
+                match word.as_str() {
+                    "true" => Ok(Value::Bool(true)),
+                    "false" => Ok(Value::Bool(false)),
+                    _ => word.parse().map(Value::Number).map_err(|_| ParseError {
+                        line: self.line,
+                        column: start + 1,
+                        message: format!("invalid value `{}`", word),
+                    }),
+                }
+            }
+            None => Err(self.error("expected a value")),
+        }
+    }
+
+    /// Reads characters up to the next whitespace, comma, bracket or comment.
+    fn word(&mut self) -> String {
+        let start = self.position;
+        while self
+            .peek()
+            .is_some_and(|c| !c.is_whitespace() && !matches!(c, ',' | ']' | '#'))
+        {
+            self.position += 1;
+        }
+        self.chars[start..self.position].iter().collect()
+    }
+
+    fn string(&mut self) -> Result<String, ParseError> {
+        let start = self.position;
+        self.position += 1; // Opening quote.
+        let mut result = String::new();
+        loop {
+            match self.peek() {
+                None => {
+                    self.position = start;
+                    return Err(self.error("unterminated string"));
+                }
+                Some('"') => {
+                    self.position += 1;
+                    return Ok(result);
+                }
+                Some('\\') => {
+                    self.position += 1;
+                    match self.peek() {
+                        Some(c @ ('"' | '\\')) => result.push(c),
+                        _ => return Err(self.error("unknown escape sequence")),
+                    }
+                    self.position += 1;
+                }
+                Some(c) => {
+                    result.push(c);
+                    self.position += 1;
+                }
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Vec<f32>, ParseError> {
+        self.position += 1; // Opening bracket.
+        let mut values = Vec::new();
+        loop {
+            self.skip_whitespace();
+            if self.peek() == Some(']') && values.is_empty() {
+                self.position += 1;
+                return Ok(values);
+            }
+
+            let start = self.position;
+            let word = self.word();
+            let number = word.parse().map_err(|_| ParseError {
+                line: self.line,
+                column: start + 1,
+                message: format!("expected a number, found `{}`", word),
+            })?;
+            values.push(number);
+
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.position += 1,
+                Some(']') => {
+                    self.position += 1;
+                    return Ok(values);
+                }
+                _ => return Err(self.error("expected `,` or `]`")),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::LinearColor;
+    use crate::settings::RenderMode;
+
+    /// A scene that only uses primitives, so it needs no other files.
+    const SAMPLE: &str = r##"# A sphere orbiting a cube.
+background = "#101018"
+
+[render]
+mode = "solid"     # filled
+line_width = 2
+
+[camera]
+fov = 60
+
+[material.steel]
+color = [0.6, 0.6, 0.65]
+opacity = 0.5
+
+[light.sun]
+direction = [0, -2, 0]
+
+[node.root]
+primitive = "cube"
+material = "steel"
+
+[node.moon]
+primitive = "sphere"
+parent = "root"
+scale = 0.5
+"##;
+
+    /// Parses a scene that must not load.
+    ///
+    /// # Returns
+    /// The line, column and message of the error.
+    fn error(text: &str) -> (usize, usize, String) {
+        match parse(text, Path::new("")) {
+            Ok(_) => panic!("parsed without an error:\n{}", text),
+            Err(e) => (e.line, e.column, e.message),
+        }
+    }
+
+    #[test]
+    fn parse_reads_a_scene() {
+        let description = parse(SAMPLE, Path::new("")).unwrap();
+        let (scene, settings) = (&description.scene, &description.settings);
+        assert_eq!(settings.background, Color::rgb(0x10, 0x10, 0x18));
+        assert_eq!(settings.mode, RenderMode::Solid);
+        assert_eq!(settings.line_width, 2.0);
+        assert_eq!(description.camera.fov, Some(60f32.to_radians()));
+
+        assert_eq!(scene.materials.len(), 1);
+        assert_eq!(scene.materials[0].diffuse, Vec3::new(0.6, 0.6, 0.65));
+        assert_eq!(scene.materials[0].opacity, 0.5);
+        assert_eq!(scene.lights.len(), 1);
+        assert_eq!(scene.lights[0].direction, Vec3::new(0.0, -1.0, 0.0));
+
+        assert_eq!(scene.roots, [0]);
+        assert_eq!(scene.nodes[0].children, [1]);
+        assert_eq!(scene.nodes[0].material, Some(0));
+        assert_eq!(scene.nodes[1].transform.scale, Vec3::new(0.5, 0.5, 0.5));
+        assert_eq!(scene.meshes.len(), 2);
+        assert!(description.files.is_empty());
+    }
+
+    #[test]
+    fn errors_point_at_their_line_and_column() {
+        let cases = [
+            (
+                "[render]\n  speed = 3",
+                (2, 3, "unknown key `speed` in [render]"),
+            ),
+            (
+                "[render]\nline_width = \"wide\"",
+                (2, 14, "`line_width` expects a number"),
+            ),
+            ("[render]\nmode = solid", (2, 8, "invalid value `solid`")),
+            (
+                "[render]\nmode = \"shaded\"",
+                (
+                    2,
+                    8,
+                    "expected \"wireframe\", \"solid\" or \"solid_wireframe\"",
+                ),
+            ),
+            ("background = \"#101018", (1, 14, "unterminated string")),
+            (
+                "[camera\nfov = 40",
+                (1, 8, "expected `]` to close the section header"),
+            ),
+            (
+                "[camera]\ntarget = [0, 1, 2",
+                (2, 18, "expected `,` or `]`"),
+            ),
+            (
+                "[camera]\ntarget = [0, x, 2]",
+                (2, 14, "expected a number, found `x`"),
+            ),
+            (
+                "[camera]\nfov = 0",
+                (2, 7, "fov must be between 1 and 179 degrees"),
+            ),
+            (
+                "[camera]\nfov = 180",
+                (2, 7, "fov must be between 1 and 179 degrees"),
+            ),
+            ("[camera]\nfov = 46 46", (2, 10, "unexpected `4`")),
+            ("[gizmo]", (1, 1, "unknown section `gizmo`")),
+        ];
+        for (text, (line, column, message)) in cases {
+            assert_eq!(error(text), (line, column, message.to_string()), "{}", text);
+        }
+        assert!(parse("[camera]\nfov = 179", Path::new("")).is_ok());
+        assert!(parse("[camera]\nfov = 1", Path::new("")).is_ok());
+    }
+
+    #[test]
+    fn references_must_be_declared() {
+        let parent = SAMPLE.replace("parent = \"root\"", "parent = \"sun\"");
+        assert_eq!(
+            error(&parent),
+            (
+                24,
+                10,
+                "unknown parent `sun`; parents must be declared first".to_string()
+            )
+        );
+        // Parents must come first, but materials may be declared after their nodes.
+        let order = "[node.a]\nparent = \"b\"\n\n[node.b]\n";
+        assert_eq!(error(order).0, 2);
+        let late = SAMPLE.replace("[material.steel]", "[material.iron]") + "[material.steel]\n";
+        assert!(parse(&late, Path::new("")).is_ok());
+
+        let material = SAMPLE.replace("material = \"steel\"", "material = \"gold\"");
+        assert_eq!(
+            error(&material),
+            (20, 12, "unknown material `gold`".to_string())
+        );
+        let primitive = SAMPLE.replace("\"sphere\"", "\"teapot\"");
+        assert_eq!(
+            error(&primitive),
+            (23, 13, "unknown primitive `teapot`".to_string())
+        );
+    }
+
+    #[test]
+    fn load_reads_textures() {
+        let directory =
+            std::env::temp_dir().join(format!("rusty-renderer-scene-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(directory.join("steel.ppm"), "P3 1 1 255 255 0 0\n").unwrap();
+        let scene_path = directory.join("textured.scene");
+        let filename = scene_path.to_string_lossy().into_owned();
+
+        // Materials naming the same image share one texture.
+        let text = "[material.a]\ntexture = \"steel.ppm\"\n\n[material.b]\ntexture = \"steel.ppm\"\n\n[material.c]\n";
+        std::fs::write(&scene_path, text).unwrap();
+        let description = load(&filename).unwrap();
+        let scene = &description.scene;
+        assert_eq!(scene.textures.len(), 1);
+        assert_eq!(
+            scene.textures[0].texels,
+            [LinearColor::new(1.0, 0.0, 0.0, 1.0)]
+        );
+        let textures: Vec<_> = scene.materials.iter().map(|m| m.texture).collect();
+        assert_eq!(textures, [Some(0), Some(0), None]);
+        assert!(description.files.contains(&directory.join("steel.ppm")));
+
+        std::fs::write(&scene_path, "[material.a]\ntexture = \"missing.ppm\"\n").unwrap();
+        let error = load(&filename).err().unwrap();
+        assert!(error.contains("missing.ppm"), "{}", error);
+    }
+}
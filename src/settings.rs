@@ -0,0 +1,190 @@
+// This file contains the options that control how the scene is drawn, which scene files
+// set and keys change at runtime.
+
+use crate::antialias::{AntiAliasing, DownsampleFilter};
+use crate::color::Color;
+use crate::display::{LineCap, LineStyle, Stroke};
+use crate::simd::Kernel;
+use crate::tonemap::Tonemapper;
+use crate::transparency::Transparency;
+
+/// How triangles are drawn.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RenderMode {
+    /// Only the edges of each triangle.
+    Wireframe,
+    /// Flat-shaded filled triangles.
+    Solid,
+    /// Flat-shaded filled triangles with their edges drawn on top.
+    SolidWireframe,
+}
+
+impl RenderMode {
+    /// Parses a render mode from its name in a scene file.
+    ///
+    /// # Arguments
+    /// - `name`: One of `wireframe`, `solid` or `solid_wireframe`.
+    ///
+    /// # Returns
+    /// The matching `RenderMode`, or `None` if the name is unknown.
+    pub fn from_name(name: &str) -> Option<RenderMode> {
+        match name {
+            "wireframe" => Some(RenderMode::Wireframe),
+            "solid" => Some(RenderMode::Solid),
+            "solid_wireframe" => Some(RenderMode::SolidWireframe),
+            _ => None,
+        }
+    }
+
+    /// Checks whether triangles are filled in this mode.
+    pub fn draws_faces(&self) -> bool {
+        *self != RenderMode::Wireframe
+    }
+
+    /// Checks whether triangle edges are drawn in this mode.
+    pub fn draws_edges(&self) -> bool {
+        *self != RenderMode::Solid
+    }
+}
+
+/// Options controlling how the scene is drawn.
+#[derive(Debug, Copy, Clone)]
+pub struct RenderSettings {
+    /// How triangles are drawn.
+    pub mode: RenderMode,
+    /// Whether triangles facing away from the camera are skipped.
+    pub backface_culling: bool,
+    /// Whether mesh instances whose bounds lie outside the view are skipped.
+    pub frustum_culling: bool,
+    /// Whether a marker is drawn at each triangle vertex.
+    pub show_vertices: bool,
+    /// Color the color buffer is cleared to every frame.
+    pub background: Color,
+    /// Color of triangle edges.
+    pub wireframe_color: Color,
+    /// Whether triangle edges are anti-aliased.
+    pub line_style: LineStyle,
+    /// Width of triangle edges in pixels.
+    pub line_width: f32,
+    /// How the ends of triangle edges wider than a pixel are shaped.
+    pub line_cap: LineCap,
+    /// Light reaching every surface regardless of the scene's lights, from 0.0 to 1.0.
+    pub ambient: f32,
+    /// Whether meshes with vertex colors are shaded with them instead of their
+    /// material's diffuse color.
+    pub vertex_colors: bool,
+    /// Number of threads that transform vertices, assemble and fill triangles, or 0 for
+    /// one per CPU core.
+    pub threads: usize,
+    /// Whether the rasterizer tests several pixels at once with the CPU's vector
+    /// instructions, when it has any. The result is the same either way.
+    pub simd: bool,
+    /// How the edges of filled triangles are smoothed.
+    pub antialiasing: AntiAliasing,
+    /// How a supersampled frame is filtered down to the window's resolution.
+    pub downsample_filter: DownsampleFilter,
+    /// How transparent triangles are combined with the frame.
+    pub transparency: Transparency,
+    /// How much the frame is brightened before it is tone mapped, in stops.
+    pub exposure: f32,
+    /// How values brighter than white are brought into the range the screen can show.
+    pub tonemapper: Tonemapper,
+}
+
+impl Default for RenderSettings {
+    fn default() -> RenderSettings {
+        RenderSettings {
+            mode: RenderMode::Wireframe,
+            backface_culling: true,
+            frustum_culling: true,
+            show_vertices: true,
+            background: Color::BLACK,
+            wireframe_color: Color::rgba(0, 150, 0, 255),
+            line_style: LineStyle::Aliased,
+            line_width: 1.0,
+            line_cap: LineCap::Butt,
+            ambient: 0.1,
+            vertex_colors: true,
+            threads: 0,
+            simd: true,
+            antialiasing: AntiAliasing::Off,
+            downsample_filter: DownsampleFilter::Box,
+            transparency: Transparency::Sorted,
+            exposure: 0.0,
+            tonemapper: Tonemapper::None,
+        }
+    }
+}
+
+impl RenderSettings {
+    /// Resolves the number of threads to draw the frame with.
+    ///
+    /// # Returns
+    /// `threads`, or the number of CPU cores if it is 0.
+    pub fn thread_count(&self) -> usize {
+        match self.threads {
+            0 => std::thread::available_parallelism().map_or(1, |count| count.get()),
+            threads => threads,
+        }
+    }
+
+    /// Picks the rasterizer's inner loop.
+    ///
+    /// # Returns
+    /// The fastest kernel the CPU supports if `simd` is set, or the scalar one.
+    pub fn kernel(&self) -> Kernel {
+        if self.simd {
+            Kernel::best()
+        } else {
+            Kernel::Scalar
+        }
+    }
+
+    /// Collects how triangle edges are drawn.
+    pub fn stroke(&self) -> Stroke {
+        Stroke {
+            style: self.line_style,
+            width: self.line_width,
+            cap: self.line_cap,
+        }
+    }
+
+    /// Carries the options changed with the keyboard over to settings read again from
+    /// the scene file. Options left as the file set them take the file's new value.
+    ///
+    /// # Arguments
+    /// - `current`: The settings in use, including the changes made at runtime.
+    /// - `loaded`: The settings the scene file held when it was last loaded.
+    ///
+    /// # Returns
+    /// These settings, with every option that `current` changed from `loaded` replaced
+    /// by its value in `current`.
+    pub fn keep_runtime_changes(
+        self,
+        current: &RenderSettings,
+        loaded: &RenderSettings,
+    ) -> RenderSettings {
+        fn pick<T: PartialEq + Copy>(reloaded: T, current: T, loaded: T) -> T {
+            if current != loaded {
+                current
+            } else {
+                reloaded
+            }
+        }
+
+        RenderSettings {
+            line_style: pick(self.line_style, current.line_style, loaded.line_style),
+            line_width: pick(self.line_width, current.line_width, loaded.line_width),
+            antialiasing: pick(self.antialiasing, current.antialiasing, loaded.antialiasing),
+            downsample_filter: pick(
+                self.downsample_filter,
+                current.downsample_filter,
+                loaded.downsample_filter,
+            ),
+            transparency: pick(self.transparency, current.transparency, loaded.transparency),
+            exposure: pick(self.exposure, current.exposure, loaded.exposure),
+            tonemapper: pick(self.tonemapper, current.tonemapper, loaded.tonemapper),
+            ..self
+        }
+    }
+}
@@ -0,0 +1,307 @@
+// This file contains the rasterizer's inner loop, which finds the pixels of a short run
+// in a row that a triangle covers, interpolates the triangle across them and tests them
+// against the depth buffer.
+//
+// The rasterizer hands the kernels the edge functions and barycentric weights at the
+// start of a row and their change from one pixel to the next, and the kernels step them
+// across the row. The edge functions are exact 64-bit integers, so every kernel agrees
+// on which pixels are covered.
+//
+// The kernels for SSE2 and AVX2 evaluate 4 or 8 pixels at once with `std::arch`, and a
+// scalar kernel serves other CPUs and is the reference the others are tested against.
+// Every kernel performs the same `f32` operations in the same order for each pixel,
+// without fused multiply-adds, so they all produce exactly the same bits.
+
+/// Most pixels a kernel tests at once.
+pub const SPAN_WIDTH: usize = 8;
+
+/// The implementations of the inner loop.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Kernel {
+    /// One pixel at a time, on any CPU.
+    Scalar,
+    /// Four pixels at a time with SSE2.
+    #[allow(dead_code)] // Only built for x86_64.
+    Sse2,
+    /// Eight pixels at a time with AVX2.
+    #[allow(dead_code)] // Only built for x86_64.
+    Avx2,
+}
+
+impl Kernel {
+    /// Picks the fastest kernel the CPU supports.
+    pub fn best() -> Kernel {
+        #[cfg(target_arch = "x86_64")]
+        {
+            if is_x86_feature_detected!("avx2") {
+                return Kernel::Avx2;
+            }
+            return Kernel::Sse2; // Every x86_64 CPU has SSE2.
+        }
+        #[allow(unreachable_code)]
+        Kernel::Scalar
+    }
+
+    /// Returns the name of the kernel, for display.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Kernel::Scalar => "scalar",
+            Kernel::Sse2 => "sse2",
+            Kernel::Avx2 => "avx2",
+        }
+    }
+
+    /// Finds the pixels of a run in a row that lie inside all three edges of a triangle.
+    ///
+    /// # Arguments
+    /// - `edges`: The triangle's edge functions along the row.
+    /// - `first`: How many pixels the run starts after the row's first pixel. The run
+    ///   must end within `SPAN_WIDTH` pixels of the row's first pixel.
+    /// - `count`: The number of pixels in the run.
+    ///
+    /// # Returns
+    /// A mask with bit `i` set when pixel `i` of the run is covered.
+    #[inline]
+    pub fn coverage(&self, edges: &EdgeRow, first: usize, count: usize) -> u32 {
+        debug_assert!(first + count <= SPAN_WIDTH);
+        match self {
+            #[cfg(target_arch = "x86_64")]
+            // SAFETY: `best` only picks this kernel when the CPU supports AVX2.
+            Kernel::Avx2 => unsafe { x86::coverage_avx2(edges, first, count) },
+            #[cfg(target_arch = "x86_64")]
+            // SAFETY: SSE2 is part of the x86_64 baseline.
+            Kernel::Sse2 => unsafe { x86::coverage_sse2(edges, first, count) },
+            _ => coverage_scalar(edges, first, count),
+        }
+    }
+
+    /// Interpolates a triangle across a run of pixels in a row, and tests the ones it
+    /// covers against the depth buffer.
+    ///
+    /// # Arguments
+    /// - `row`: The triangle's edge functions and weights along the row.
+    /// - `first`: How many pixels the run starts after the row's first pixel. The run
+    ///   must end within `SPAN_WIDTH` pixels of the row's first pixel.
+    /// - `depth_row`: The depth buffer values of the pixels in the run.
+    /// - `span`: Receives the pixels covered, and those closer than the depth buffer
+    ///   with their barycentric weights and depths. It is reused between calls to save
+    ///   clearing it every time.
+    #[inline]
+    pub fn span(&self, row: &RowSetup, first: usize, depth_row: &[f32], span: &mut Span) {
+        span.covered = self.coverage(&row.edges, first, depth_row.len());
+        if span.covered == 0 {
+            span.mask = 0;
+            return;
+        }
+        match self {
+            #[cfg(target_arch = "x86_64")]
+            // SAFETY: `best` only picks this kernel when the CPU supports AVX2.
+            Kernel::Avx2 => unsafe { x86::span_avx2(row, first, depth_row, span) },
+            #[cfg(target_arch = "x86_64")]
+            // SAFETY: SSE2 is part of the x86_64 baseline.
+            Kernel::Sse2 => unsafe { x86::span_sse2(row, first, depth_row, span) },
+            _ => span_scalar(row, first, depth_row, span),
+        }
+    }
+}
+
+/// A triangle's edge functions along a row of pixels.
+///
+/// Pixel `i` of the row is covered when `values[k] + steps[k] * i >= 0` for all three
+/// edges. The values are biased so that pixels exactly on an edge are only covered by
+/// one of the triangles sharing it.
+#[derive(Debug, Copy, Clone)]
+pub struct EdgeRow {
+    /// The biased edge function of each edge at the sample of the row's first pixel.
+    pub values: [i64; 3],
+    /// The change of each edge function from one pixel to the next.
+    pub steps: [i64; 3],
+}
+
+/// A triangle's edge functions and barycentric weights along a row of pixels.
+///
+/// The weights are affine across the screen, so they change by the same `steps` from
+/// each pixel to the next. Pixel `i` of the row has the weights `weights + steps * i`,
+/// which every kernel calculates with the same operations.
+#[derive(Debug, Copy, Clone)]
+pub struct RowSetup {
+    /// Which pixels of the row the triangle covers.
+    pub edges: EdgeRow,
+    /// The weight of each vertex at the center of the row's first pixel.
+    pub weights: [f32; 3],
+    /// The change of each weight from one pixel to the next.
+    pub steps: [f32; 3],
+    /// The inverse view depth of each vertex.
+    pub inv_depths: [f32; 3],
+}
+
+/// The result of testing a run of pixels.
+#[derive(Debug, Copy, Clone)]
+pub struct Span {
+    /// Bit `i` is set when pixel `i` is covered by the triangle.
+    pub covered: u32,
+    /// Bit `i` is set when pixel `i` is covered and passes the depth test.
+    pub mask: u32,
+    /// The barycentric weight of each vertex at each pixel. Only meaningful for pixels
+    /// set in `mask`.
+    pub weights: [[f32; SPAN_WIDTH]; 3],
+    /// The interpolated inverse depth at each pixel. Only meaningful for pixels set in
+    /// `mask`.
+    pub depths: [f32; SPAN_WIDTH],
+}
+
+/// Tests pixels against the edges one at a time.
+#[inline]
+fn coverage_scalar(edges: &EdgeRow, first: usize, count: usize) -> u32 {
+    let mut covered = 0;
+    for i in 0..count {
+        let column = (first + i) as i64;
+        if (0..3).all(|k| edges.values[k] + edges.steps[k] * column >= 0) {
+            covered |= 1 << i;
+        }
+    }
+    covered
+}
+
+/// Tests covered pixels against the depth buffer one at a time.
+#[inline]
+fn span_scalar(row: &RowSetup, first: usize, depth_row: &[f32], span: &mut Span) {
+    span.mask = 0;
+    for (i, &buffered) in depth_row.iter().enumerate() {
+        if span.covered & (1 << i) == 0 {
+            continue;
+        }
+        let column = (first + i) as f32;
+        let w: [f32; 3] = std::array::from_fn(|k| row.weights[k] + row.steps[k] * column);
+        let depth = w[0] * row.inv_depths[0] + w[1] * row.inv_depths[1] + w[2] * row.inv_depths[2];
+        if depth > buffered {
+            span.mask |= 1 << i;
+            for (weights, weight) in span.weights.iter_mut().zip(w) {
+                weights[i] = weight;
+            }
+            span.depths[i] = depth;
+        }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    use std::arch::x86_64::*;
+
+    use super::{EdgeRow, RowSetup, Span, SPAN_WIDTH};
+
+    /// Copies up to `SPAN_WIDTH` depth values into a full-width array.
+    fn pad(depth_row: &[f32]) -> [f32; SPAN_WIDTH] {
+        let mut padded = [0.0; SPAN_WIDTH];
+        padded[..depth_row.len()].copy_from_slice(depth_row);
+        padded
+    }
+
+    /// Keeps the mask bits of the pixels that exist.
+    fn valid(mask: u32, count: usize) -> u32 {
+        mask & ((1 << count) - 1)
+    }
+
+    /// Tests 8 pixels against the edges, 4 at a time.
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn coverage_avx2(edges: &EdgeRow, first: usize, count: usize) -> u32 {
+        // A lane's sign bit ends up set when its pixel is outside any of the edges.
+        let mut low = _mm256_setzero_si256();
+        let mut high = _mm256_setzero_si256();
+        for k in 0..3 {
+            let step = edges.steps[k];
+            let start = _mm256_set1_epi64x(edges.values[k] + step * first as i64);
+            let values = _mm256_add_epi64(start, _mm256_setr_epi64x(0, step, 2 * step, 3 * step));
+            low = _mm256_or_si256(low, values);
+            high = _mm256_or_si256(high, _mm256_add_epi64(values, _mm256_set1_epi64x(4 * step)));
+        }
+        let outside = _mm256_movemask_pd(_mm256_castsi256_pd(low)) as u32
+            | (_mm256_movemask_pd(_mm256_castsi256_pd(high)) as u32) << 4;
+        valid(!outside, count)
+    }
+
+    /// Tests 8 pixels against the edges, 2 at a time.
+    #[target_feature(enable = "sse2")]
+    pub unsafe fn coverage_sse2(edges: &EdgeRow, first: usize, count: usize) -> u32 {
+        // A lane's sign bit ends up set when its pixel is outside any of the edges.
+        let mut signs = [_mm_setzero_si128(); SPAN_WIDTH / 2];
+        for k in 0..3 {
+            let step = edges.steps[k];
+            let start = _mm_set1_epi64x(edges.values[k] + step * first as i64);
+            let mut values = _mm_add_epi64(start, _mm_set_epi64x(step, 0));
+            for pair in &mut signs {
+                *pair = _mm_or_si128(*pair, values);
+                values = _mm_add_epi64(values, _mm_set1_epi64x(2 * step));
+            }
+        }
+        let outside = signs.iter().enumerate().fold(0, |outside, (pair, &signs)| {
+            outside | (_mm_movemask_pd(_mm_castsi128_pd(signs)) as u32) << (pair * 2)
+        });
+        valid(!outside, count)
+    }
+
+    /// Tests 8 pixels against the depth buffer at once.
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn span_avx2(row: &RowSetup, first: usize, depth_row: &[f32], span: &mut Span) {
+        let columns = _mm256_cvtepi32_ps(_mm256_add_epi32(
+            _mm256_set1_epi32(first as i32),
+            _mm256_setr_epi32(0, 1, 2, 3, 4, 5, 6, 7),
+        ));
+
+        let mut depth = _mm256_setzero_ps();
+        for k in 0..3 {
+            let w = _mm256_add_ps(
+                _mm256_set1_ps(row.weights[k]),
+                _mm256_mul_ps(_mm256_set1_ps(row.steps[k]), columns),
+            );
+            _mm256_storeu_ps(span.weights[k].as_mut_ptr(), w);
+            let weighted = _mm256_mul_ps(w, _mm256_set1_ps(row.inv_depths[k]));
+            depth = if k == 0 {
+                weighted
+            } else {
+                _mm256_add_ps(depth, weighted)
+            };
+        }
+        _mm256_storeu_ps(span.depths.as_mut_ptr(), depth);
+
+        let buffered = _mm256_loadu_ps(pad(depth_row).as_ptr());
+        let closer = _mm256_cmp_ps::<_CMP_GT_OQ>(depth, buffered);
+        let mask = _mm256_movemask_ps(closer) as u32;
+        span.mask = valid(mask, depth_row.len()) & span.covered;
+    }
+
+    /// Tests 8 pixels against the depth buffer, 4 at a time.
+    #[target_feature(enable = "sse2")]
+    pub unsafe fn span_sse2(row: &RowSetup, first: usize, depth_row: &[f32], span: &mut Span) {
+        let buffered = pad(depth_row);
+
+        span.mask = 0;
+        for half in 0..2 {
+            let lane = half * 4;
+            let columns = _mm_cvtepi32_ps(_mm_add_epi32(
+                _mm_set1_epi32((first + lane) as i32),
+                _mm_setr_epi32(0, 1, 2, 3),
+            ));
+
+            let mut depth = _mm_setzero_ps();
+            for k in 0..3 {
+                let w = _mm_add_ps(
+                    _mm_set1_ps(row.weights[k]),
+                    _mm_mul_ps(_mm_set1_ps(row.steps[k]), columns),
+                );
+                _mm_storeu_ps(span.weights[k][lane..].as_mut_ptr(), w);
+                let weighted = _mm_mul_ps(w, _mm_set1_ps(row.inv_depths[k]));
+                depth = if k == 0 {
+                    weighted
+                } else {
+                    _mm_add_ps(depth, weighted)
+                };
+            }
+            _mm_storeu_ps(span.depths[lane..].as_mut_ptr(), depth);
+
+            let closer = _mm_cmpgt_ps(depth, _mm_loadu_ps(buffered[lane..].as_ptr()));
+            span.mask |= (_mm_movemask_ps(closer) as u32) << lane;
+        }
+        span.mask = valid(span.mask, depth_row.len()) & span.covered;
+    }
+}
@@ -0,0 +1,80 @@
+// This file contains the timings and counters gathered for each frame, which the
+// performance overlay shows.
+
+use std::time::Duration;
+
+/// Weight of the newest frame in the smoothed frames-per-second value.
+const FPS_SMOOTHING: f32 = 0.1;
+
+/// Timings and counters gathered while producing a frame.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct RenderStats {
+    /// Frames per second, smoothed over recent frames.
+    pub fps: f32,
+    /// Total duration of the last frame, including the frame limiter.
+    pub frame_time: Duration,
+    /// Time spent moving vertices into camera space.
+    pub transform_time: Duration,
+    /// Time spent clipping, back-face culling and projecting triangles.
+    pub clip_time: Duration,
+    /// Time spent drawing triangles into the color buffer.
+    pub raster_time: Duration,
+    /// Time spent averaging the samples of an anti-aliased frame into its pixels.
+    pub resolve_time: Duration,
+    /// Time spent tone mapping the HDR frame into the window's color buffer.
+    pub tonemap_time: Duration,
+    /// Time spent copying the color buffer to the window.
+    pub present_time: Duration,
+    /// Number of mesh instances in the scene.
+    pub objects_submitted: usize,
+    /// Number of mesh instances skipped because their bounds lie outside the view.
+    pub objects_culled: usize,
+    /// Number of triangles sent through the pipeline.
+    pub triangles_submitted: usize,
+    /// Number of triangles discarded because they face away from the camera.
+    pub triangles_culled: usize,
+    /// Number of triangles reaching behind the near plane, which are cut at it, or
+    /// discarded if they lie wholly behind it.
+    pub triangles_clipped: usize,
+    /// Number of triangles drawn into the color buffer, counting each piece of a
+    /// triangle cut at the near plane.
+    pub triangles_drawn: usize,
+    /// Number of the triangles drawn that are transparent.
+    pub triangles_transparent: usize,
+    /// Name of the anti-aliasing mode the frame was drawn with.
+    pub antialiasing: &'static str,
+    /// Name of the filter a supersampled frame was resolved with, or empty.
+    pub downsample_filter: &'static str,
+    /// Name of the mode transparent triangles were drawn with.
+    pub transparency: &'static str,
+    /// Name of the curve the frame was tone mapped with.
+    pub tonemapper: &'static str,
+    /// Exposure the frame was tone mapped with, in stops.
+    pub exposure: f32,
+}
+
+impl RenderStats {
+    /// Starts collecting a new frame, resetting the object and triangle counters.
+    ///
+    /// # Arguments
+    /// - `delta`: The duration of the previous frame in seconds.
+    pub fn begin_frame(&mut self, delta: f32) {
+        self.frame_time = Duration::from_secs_f32(delta);
+        if delta > 0.0 {
+            let fps = 1.0 / delta;
+            self.fps = if self.fps == 0.0 {
+                fps
+            } else {
+                self.fps + (fps - self.fps) * FPS_SMOOTHING
+            };
+        }
+
+        self.objects_submitted = 0;
+        self.objects_culled = 0;
+        self.triangles_submitted = 0;
+        self.triangles_culled = 0;
+        self.triangles_clipped = 0;
+        self.triangles_drawn = 0;
+        self.triangles_transparent = 0;
+    }
+}
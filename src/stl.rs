@@ -0,0 +1,377 @@
+// This file contains the reader and writer for STL files, the triangle soup format most
+// CAD tools export.
+//
+// STL stores every triangle with its own copy of its three corners. When loading, corners
+// at exactly the same position are welded back into shared vertices so the mesh uses the
+// same indexed representation as OBJ files.
+
+use std::collections::HashMap;
+
+use crate::mesh::Mesh;
+use crate::triangle::Face;
+use crate::vector::Vec3;
+
+/// Size of the header at the start of a binary STL file, in bytes.
+const BINARY_HEADER_SIZE: usize = 80;
+/// Size of one triangle record in a binary STL file: a normal, three vertices and a
+/// 16-bit attribute.
+const BINARY_TRIANGLE_SIZE: usize = 12 * 4 + 2;
+
+/// Collects triangle corners into shared vertices, merging corners at identical
+/// positions.
+struct Welder {
+    vertices: Vec<Vec3>,
+    /// Maps the bit pattern of a position to its 1-based vertex index.
+    indices: HashMap<[u32; 3], usize>,
+}
+
+impl Welder {
+    fn new() -> Welder {
+        Welder {
+            vertices: Vec::new(),
+            indices: HashMap::new(),
+        }
+    }
+
+    /// Finds the vertex at a position, adding it if there is none yet.
+    ///
+    /// # Returns
+    /// The 1-based index of the vertex.
+    fn index(&mut self, position: Vec3) -> usize {
+        // `+ 0.0` turns -0.0 into 0.0 so both weld together.
+        let key = [
+            (position.x + 0.0).to_bits(),
+            (position.y + 0.0).to_bits(),
+            (position.z + 0.0).to_bits(),
+        ];
+        let vertices = &mut self.vertices;
+        *self.indices.entry(key).or_insert_with(|| {
+            vertices.push(position);
+            vertices.len()
+        })
+    }
+}
+
+impl Mesh {
+    /// Loads a mesh from an STL file, in either its ASCII or binary form.
+    ///
+    /// The form is detected from the contents: a file is binary when its size matches
+    /// the triangle count in its header, and ASCII when it starts with `solid`.
+    /// Duplicate corners are welded into shared vertices and the facet normals are kept
+    /// in `face_normals`.
+    ///
+    /// # Arguments
+    /// - `filename`: The path to the `.stl` file to load.
+    ///
+    /// # Returns
+    /// A `Mesh` instance loaded from the file.
+    ///
+    /// # Errors
+    /// Returns a message if the file cannot be read or contains invalid data.
+    pub fn load_stl(filename: &str) -> Result<Mesh, String> {
+        let bytes = std::fs::read(filename).map_err(|e| format!("{}: {}", filename, e))?;
+        Mesh::parse_stl(&bytes).map_err(|e| format!("{}: {}", filename, e))
+    }
+
+    /// Parses the contents of an STL file.
+    ///
+    /// # Arguments
+    /// - `bytes`: The contents of the file.
+    ///
+    /// # Returns
+    /// The welded mesh.
+    ///
+    /// # Errors
+    /// Returns a message if the data is neither a complete binary STL file nor valid
+    /// ASCII STL. Problems in ASCII files name their line.
+    pub fn parse_stl(bytes: &[u8]) -> Result<Mesh, String> {
+        // Some exporters start binary headers with "solid" too, so the size check wins.
+        if binary_triangle_count(bytes)
+            .is_some_and(|count| bytes.len() == binary_size(count as usize))
+        {
+            return Ok(parse_binary_stl(bytes));
+        }
+        if bytes.trim_ascii_start().starts_with(b"solid") {
+            let text = std::str::from_utf8(bytes).map_err(|e| format!("invalid text: {}", e))?;
+            return parse_ascii_stl(text);
+        }
+        match binary_triangle_count(bytes) {
+            Some(count) => Err(format!(
+                "binary header declares {} triangles ({} bytes) but the file has {} bytes",
+                count,
+                binary_size(count as usize),
+                bytes.len()
+            )),
+            None => Err("file is too short to be an STL file".to_string()),
+        }
+    }
+
+    /// Saves the mesh as a binary STL file.
+    ///
+    /// Faces use their normal from `face_normals` when there is one, and a normal
+    /// computed from their vertices otherwise.
+    ///
+    /// # Arguments
+    /// - `filename`: The path of the file to write.
+    ///
+    /// # Errors
+    /// Returns a message if the file cannot be written.
+    pub fn save_stl(&self, filename: &str) -> Result<(), String> {
+        std::fs::write(filename, self.to_binary_stl()).map_err(|e| format!("{}: {}", filename, e))
+    }
+
+    /// Encodes the mesh as the contents of a binary STL file.
+    ///
+    /// # Returns
+    /// The bytes of the file.
+    pub fn to_binary_stl(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(binary_size(self.faces.len()));
+
+        let mut header = [0u8; BINARY_HEADER_SIZE];
+        let title = b"binary STL written by rusty-renderer";
+        header[..title.len()].copy_from_slice(title);
+        bytes.extend_from_slice(&header);
+        bytes.extend_from_slice(&(self.faces.len() as u32).to_le_bytes());
+
+        for (i, face) in self.faces.iter().enumerate() {
+            let normal = match self.face_normals.get(i) {
+                Some(normal) => *normal,
+                None => self.compute_face_normal(face),
+            };
+            let corners = [
+                self.vertices[face.a - 1],
+                self.vertices[face.b - 1],
+                self.vertices[face.c - 1],
+            ];
+            for v in std::iter::once(normal).chain(corners) {
+                bytes.extend_from_slice(&v.x.to_le_bytes());
+                bytes.extend_from_slice(&v.y.to_le_bytes());
+                bytes.extend_from_slice(&v.z.to_le_bytes());
+            }
+            bytes.extend_from_slice(&0u16.to_le_bytes()); // Attribute byte count.
+        }
+        bytes
+    }
+}
+
+/// Calculates the size of a binary STL file holding a number of triangles.
+fn binary_size(triangles: usize) -> usize {
+    BINARY_HEADER_SIZE + 4 + triangles * BINARY_TRIANGLE_SIZE
+}
+
+/// Reads the triangle count of a binary STL header.
+///
+/// # Returns
+/// The count, or `None` if the data is too short to hold a header.
+fn binary_triangle_count(bytes: &[u8]) -> Option<u32> {
+    let count = bytes.get(BINARY_HEADER_SIZE..BINARY_HEADER_SIZE + 4)?;
+    Some(u32::from_le_bytes([count[0], count[1], count[2], count[3]]))
+}
+
+/// Parses a binary STL file whose size has already been checked.
+fn parse_binary_stl(bytes: &[u8]) -> Mesh {
+    let mut welder = Welder::new();
+    let mut faces = Vec::new();
+    let mut face_normals = Vec::new();
+
+    let triangles = &bytes[BINARY_HEADER_SIZE + 4..];
+    for record in triangles.chunks_exact(BINARY_TRIANGLE_SIZE) {
+        let float = |i: usize| {
+            f32::from_le_bytes([
+                record[i * 4],
+                record[i * 4 + 1],
+                record[i * 4 + 2],
+                record[i * 4 + 3],
+            ])
+        };
+        let vec3 = |i: usize| Vec3::new(float(i * 3), float(i * 3 + 1), float(i * 3 + 2));
+
+        face_normals.push(vec3(0));
+        faces.push(Face::new(
+            welder.index(vec3(1)),
+            welder.index(vec3(2)),
+            welder.index(vec3(3)),
+        ));
+    }
+
+    let mut mesh = Mesh::new(welder.vertices, faces);
+    mesh.face_normals = face_normals;
+    mesh
+}
+
+/// Parses an ASCII STL file.
+///
+/// Facets with more than three vertices are split into a fan of triangles sharing the
+/// facet's normal.
+fn parse_ascii_stl(text: &str) -> Result<Mesh, String> {
+    let mut welder = Welder::new();
+    let mut faces = Vec::new();
+    let mut face_normals = Vec::new();
+
+    let mut normal = Vec3::new(0.0, 0.0, 0.0);
+    let mut corners: Vec<usize> = Vec::new();
+    let mut in_facet = false;
+
+    for (number, line) in text.lines().enumerate() {
+        let error = |message: &str| format!("line {}: {}", number + 1, message);
+        let mut words = line.split_whitespace();
+
+        match words.next() {
+            Some("facet") => {
+                if in_facet {
+                    return Err(error("`facet` inside another facet"));
+                }
+                if words.next() != Some("normal") {
+                    return Err(error("expected `facet normal nx ny nz`"));
+                }
+                normal = read_vec3(&mut words)
+                    .ok_or_else(|| error("expected three normal coordinates"))?;
+                corners.clear();
+                in_facet = true;
+            }
+            Some("vertex") => {
+                if !in_facet {
+                    return Err(error("`vertex` outside of a facet"));
+                }
+                let position = read_vec3(&mut words)
+                    .ok_or_else(|| error("expected three vertex coordinates"))?;
+                corners.push(welder.index(position));
+            }
+            Some("endfacet") => {
+                if !in_facet {
+                    return Err(error("`endfacet` without a facet"));
+                }
+                if corners.len() < 3 {
+                    return Err(error("a facet needs at least three vertices"));
+                }
+                for i in 1..corners.len() - 1 {
+                    faces.push(Face::new(corners[0], corners[i], corners[i + 1]));
+                    face_normals.push(normal);
+                }
+                in_facet = false;
+            }
+            // `solid`, `outer loop`, `endloop` and `endsolid` carry no geometry.
+            _ => {}
+        }
+    }
+    if in_facet {
+        return Err(format!("line {}: missing `endfacet`", text.lines().count()));
+    }
+
+    let mut mesh = Mesh::new(welder.vertices, faces);
+    mesh.face_normals = face_normals;
+    Ok(mesh)
+}
+
+/// Reads three numbers from a line's remaining words.
+///
+/// # Returns
+/// The vector, or `None` if fewer than three numbers follow.
+fn read_vec3<'a>(words: &mut impl Iterator<Item = &'a str>) -> Option<Vec3> {
+    let mut coordinate = || words.next()?.parse::<f32>().ok();
+    Some(Vec3::new(coordinate()?, coordinate()?, coordinate()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ASCII_SAMPLE: &str = "solid square
+  facet normal 0 0 1
+    outer loop
+      vertex 0 0 0
+      vertex 1 0 0
+      vertex 1 1 0
+    endloop
+  endfacet
+  facet normal 0 0 1
+    outer loop
+      vertex 0 0 0
+      vertex 1 1 0
+      vertex 0 1 0
+    endloop
+  endfacet
+endsolid square
+";
+
+    #[test]
+    fn binary_round_trip_keeps_the_cube() {
+        let cube = Mesh::new_cube();
+        let mesh = Mesh::parse_stl(&cube.to_binary_stl()).unwrap();
+
+        // The cube's faces visit its vertices in order, so welding restores them as-is.
+        assert_eq!(mesh.vertices, cube.vertices);
+        assert_eq!(mesh.faces, cube.faces);
+        assert_eq!(mesh.faces.len(), 12);
+        let normals: Vec<Vec3> = cube
+            .faces
+            .iter()
+            .map(|face| cube.compute_face_normal(face))
+            .collect();
+        assert_eq!(mesh.face_normals, normals);
+    }
+
+    #[test]
+    fn save_stl_writes_a_loadable_file() {
+        let path = std::env::temp_dir().join(format!("rusty-renderer-{}.stl", std::process::id()));
+        let filename = path.to_string_lossy();
+        Mesh::new_cube().save_stl(&filename).unwrap();
+        let mesh = Mesh::load_stl(&filename);
+        std::fs::remove_file(&path).unwrap();
+
+        let mesh = mesh.unwrap();
+        assert_eq!(mesh.vertices.len(), 8);
+        assert_eq!(mesh.faces.len(), 12);
+    }
+
+    #[test]
+    fn ascii_sample_is_welded() {
+        let mesh = Mesh::parse_stl(ASCII_SAMPLE.as_bytes()).unwrap();
+
+        assert_eq!(
+            mesh.vertices,
+            [
+                Vec3::new(0.0, 0.0, 0.0),
+                Vec3::new(1.0, 0.0, 0.0),
+                Vec3::new(1.0, 1.0, 0.0),
+                Vec3::new(0.0, 1.0, 0.0),
+            ]
+        );
+        assert_eq!(mesh.faces, [Face::new(1, 2, 3), Face::new(1, 3, 4)]);
+        assert_eq!(mesh.face_normals, [Vec3::new(0.0, 0.0, 1.0); 2]);
+    }
+
+    #[test]
+    fn ascii_errors_name_their_line() {
+        let text = ASCII_SAMPLE.replace("vertex 1 0 0", "vertex 1 0");
+        assert_eq!(
+            Mesh::parse_stl(text.as_bytes()).err().unwrap(),
+            "line 5: expected three vertex coordinates"
+        );
+    }
+
+    #[test]
+    fn truncated_binary_is_an_error() {
+        let bytes = Mesh::new_cube().to_binary_stl();
+        let error = Mesh::parse_stl(&bytes[..bytes.len() - 1]).err().unwrap();
+        assert_eq!(
+            error,
+            "binary header declares 12 triangles (684 bytes) but the file has 683 bytes"
+        );
+    }
+
+    #[test]
+    fn oversized_binary_is_an_error() {
+        let mut bytes = Mesh::new_cube().to_binary_stl();
+        bytes.extend_from_slice(&[0; BINARY_TRIANGLE_SIZE]);
+        assert!(Mesh::parse_stl(&bytes).is_err());
+    }
+
+    #[test]
+    fn short_input_is_an_error() {
+        assert_eq!(
+            Mesh::parse_stl(b"not an stl").err().unwrap(),
+            "file is too short to be an STL file"
+        );
+    }
+}
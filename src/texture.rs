@@ -0,0 +1,262 @@
+// This file contains the textures that color the surface of meshes, and the reader for the
+// Netpbm PPM images they are loaded from.
+//
+// A PPM file starts with a text header: `P3` (ASCII) or `P6` (binary), the width, the
+// height and the largest channel value, separated by whitespace and `#` comments. The
+// pixels follow row by row from the top, as decimal numbers in a `P3` file, or after a
+// single whitespace byte as one byte per channel in a `P6` file, or two big-endian bytes
+// when the largest value is above 255.
+
+use crate::color::{self, LinearColor};
+use crate::vector::Vec2;
+
+/// Index of a texture stored in a `Scene`.
+pub type TextureId = usize;
+
+/// An image sampled across the surface of a mesh.
+#[derive(Debug, Clone)]
+pub struct Texture {
+    /// Width of the image in texels.
+    pub width: usize,
+    /// Height of the image in texels.
+    pub height: usize,
+    /// The texels row by row from the top of the image, in linear light.
+    pub texels: Vec<LinearColor>,
+}
+
+impl Texture {
+    /// Loads a texture from a PPM image.
+    ///
+    /// # Arguments
+    /// - `filename`: The path to the `.ppm` file to load.
+    ///
+    /// # Returns
+    /// The texture, with its sRGB-encoded channels decoded into linear light.
+    ///
+    /// # Errors
+    /// Returns a message if the file cannot be read or is not a valid PPM image.
+    pub fn load_ppm(filename: &str) -> Result<Texture, String> {
+        let bytes = std::fs::read(filename).map_err(|e| format!("{}: {}", filename, e))?;
+        Texture::parse_ppm(&bytes).map_err(|e| format!("{}: {}", filename, e))
+    }
+
+    /// Parses the contents of a PPM image.
+    ///
+    /// # Arguments
+    /// - `bytes`: The contents of the file.
+    ///
+    /// # Returns
+    /// The texture, with its sRGB-encoded channels decoded into linear light.
+    ///
+    /// # Errors
+    /// Returns a message if the image is not a valid `P3` or `P6` file.
+    pub fn parse_ppm(bytes: &[u8]) -> Result<Texture, String> {
+        let mut position = 0;
+        let binary = match bytes.get(..2) {
+            Some(b"P3") => false,
+            Some(b"P6") => true,
+            _ => return Err("not a PPM image: expected `P3` or `P6`".to_string()),
+        };
+        position += 2;
+
+        let width = header_number(bytes, &mut position, "width")?;
+        let height = header_number(bytes, &mut position, "height")?;
+        let max_value = header_number(bytes, &mut position, "maximum value")?;
+        if width == 0 || height == 0 {
+            return Err(format!("empty image of {} by {} pixels", width, height));
+        }
+        if !(1..=65535).contains(&max_value) {
+            return Err(format!(
+                "maximum value {} is not between 1 and 65535",
+                max_value
+            ));
+        }
+        let count = width
+            .checked_mul(height)
+            .and_then(|pixels| pixels.checked_mul(3))
+            .ok_or_else(|| format!("image of {} by {} pixels is too large", width, height))?;
+
+        let values: Vec<usize> = if binary {
+            // Exactly one whitespace byte separates the header from the pixels.
+            if !bytes.get(position).is_some_and(u8::is_ascii_whitespace) {
+                return Err("expected whitespace after the header".to_string());
+            }
+            position += 1;
+            let size = if max_value > 255 { 2 } else { 1 };
+            let data = &bytes[position..];
+            if data.len() / size < count {
+                return Err(format!(
+                    "expected {} bytes of pixel data, found {}",
+                    count * size,
+                    data.len()
+                ));
+            }
+            data.chunks_exact(size)
+                .take(count)
+                .map(|value| value.iter().fold(0, |sum, &byte| sum << 8 | byte as usize))
+                .collect()
+        } else {
+            (0..count)
+                .map(|_| header_number(bytes, &mut position, "pixel value"))
+                .collect::<Result<_, _>>()?
+        };
+        if let Some(value) = values.iter().find(|&&value| value > max_value) {
+            return Err(format!(
+                "pixel value {} is above the maximum value {}",
+                value, max_value
+            ));
+        }
+
+        let scale = 1.0 / max_value as f32;
+        let texels = values
+            .chunks_exact(3)
+            .map(|rgb| {
+                let [r, g, b] = [0, 1, 2].map(|i| color::srgb_to_linear(rgb[i] as f32 * scale));
+                LinearColor::new(r, g, b, 1.0)
+            })
+            .collect();
+        Ok(Texture {
+            width,
+            height,
+            texels,
+        })
+    }
+
+    /// Samples the texture between its four nearest texels, repeating it beyond the
+    /// range from 0.0 to 1.0.
+    ///
+    /// # Arguments
+    /// - `uv`: The texture coordinates, with `u` running right and `v` running up from
+    ///   the bottom-left corner of the image.
+    ///
+    /// # Returns
+    /// The color of the texture at `uv`, in linear light.
+    pub fn sample(&self, uv: Vec2) -> LinearColor {
+        // Texel centers lie half a texel in from the image's edges.
+        let x = uv.x * self.width as f32 - 0.5;
+        let y = (1.0 - uv.y) * self.height as f32 - 0.5;
+        if !x.is_finite() || !y.is_finite() {
+            return self.texels[0];
+        }
+        let (left, top) = (x.floor(), y.floor());
+        let (tx, ty) = (x - left, y - top);
+        let column = |offset: i64| (left as i64 + offset).rem_euclid(self.width as i64) as usize;
+        let row = |offset: i64| (top as i64 + offset).rem_euclid(self.height as i64) as usize;
+        let texel = |column: usize, row: usize| self.texels[row * self.width + column];
+
+        let upper = texel(column(0), row(0)).lerp(texel(column(1), row(0)), tx);
+        let lower = texel(column(0), row(1)).lerp(texel(column(1), row(1)), tx);
+        upper.lerp(lower, ty)
+    }
+}
+
+/// Reads the next decimal number of a PPM file, skipping whitespace and `#` comments
+/// before it.
+fn header_number(bytes: &[u8], position: &mut usize, name: &str) -> Result<usize, String> {
+    while let Some(&byte) = bytes.get(*position) {
+        if byte == b'#' {
+            while bytes.get(*position).is_some_and(|&byte| byte != b'\n') {
+                *position += 1;
+            }
+        } else if byte.is_ascii_whitespace() {
+            *position += 1;
+        } else {
+            break;
+        }
+    }
+
+    let start = *position;
+    while bytes.get(*position).is_some_and(u8::is_ascii_digit) {
+        *position += 1;
+    }
+    if start == *position {
+        return Err(format!("expected the {} at byte {}", name, start));
+    }
+    std::str::from_utf8(&bytes[start..*position])
+        .ok()
+        .and_then(|digits| digits.parse().ok())
+        .ok_or_else(|| format!("{} at byte {} is too large", name, start))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 2 by 2 image with a red, green, blue and white pixel, at the maximum value 255.
+    fn checker(binary: bool) -> Vec<u8> {
+        let values = [255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255];
+        if binary {
+            let mut bytes = b"P6\n# a comment\n2 2\n255\n".to_vec();
+            bytes.extend(values.map(|value| value as u8));
+            bytes
+        } else {
+            let text: Vec<String> = values.iter().map(|value| value.to_string()).collect();
+            format!("P3 2 2 # size\n255\n{}\n", text.join(" ")).into_bytes()
+        }
+    }
+
+    #[test]
+    fn ascii_and_binary_images_are_read() {
+        for binary in [false, true] {
+            let texture = Texture::parse_ppm(&checker(binary)).unwrap();
+            assert_eq!((texture.width, texture.height), (2, 2));
+            assert_eq!(
+                texture.texels,
+                vec![
+                    LinearColor::new(1.0, 0.0, 0.0, 1.0),
+                    LinearColor::new(0.0, 1.0, 0.0, 1.0),
+                    LinearColor::new(0.0, 0.0, 1.0, 1.0),
+                    LinearColor::new(1.0, 1.0, 1.0, 1.0),
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn channels_are_decoded_into_linear_light() {
+        // Sixteen-bit values are stored big-endian.
+        let mut bytes = b"P6 1 1 1000\n".to_vec();
+        bytes.extend([0x01, 0xf4, 0x03, 0xe8, 0x00, 0x00]);
+        let texture = Texture::parse_ppm(&bytes).unwrap();
+        let texel = texture.texels[0];
+        assert_eq!(texel.r, color::srgb_to_linear(0.5));
+        assert_eq!((texel.g, texel.b, texel.a), (1.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn invalid_images_are_errors() {
+        let cases: [(&[u8], &str); 6] = [
+            (b"P5 1 1 255\n\0", "not a PPM image"),
+            (b"P3 2", "expected the height"),
+            (b"P3 0 1 255", "empty image"),
+            (b"P3 1 1 70000 0 0 0", "maximum value 70000"),
+            (b"P3 1 1 15 0 16 0", "pixel value 16 is above"),
+            (
+                b"P6 2 1 255\n\x01\x02\x03",
+                "expected 6 bytes of pixel data, found 3",
+            ),
+        ];
+        for (bytes, message) in cases {
+            let error = Texture::parse_ppm(bytes).unwrap_err();
+            assert!(error.contains(message), "{:?}: {}", message, error);
+        }
+    }
+
+    #[test]
+    fn samples_blend_neighbouring_texels_and_repeat() {
+        let texture = Texture::parse_ppm(&checker(false)).unwrap();
+        let red = LinearColor::new(1.0, 0.0, 0.0, 1.0);
+        let white = LinearColor::new(1.0, 1.0, 1.0, 1.0);
+
+        // Texel centers, with the top row of the image at the top of the texture.
+        assert_eq!(texture.sample(Vec2::new(0.25, 0.75)), red);
+        assert_eq!(texture.sample(Vec2::new(0.75, 0.25)), white);
+        // Repeated a whole number of times.
+        assert_eq!(texture.sample(Vec2::new(-1.75, 2.75)), red);
+        // Halfway between all four texels, and across the wrapping edge.
+        let average = LinearColor::new(0.5, 0.5, 0.5, 1.0);
+        assert_eq!(texture.sample(Vec2::new(0.5, 0.5)), average);
+        assert_eq!(texture.sample(Vec2::new(1.0, 0.0)), average);
+        assert_eq!(texture.sample(Vec2::new(f32::NAN, 0.5)), red);
+    }
+}
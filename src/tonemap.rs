@@ -0,0 +1,203 @@
+// This file contains the resolve pass, which turns the HDR frame into the bytes shown in
+// the window.
+//
+// The scene is lit and blended in linear light, where a brightly lit surface can be many
+// times brighter than white. The resolve pass scales the frame by the exposure, maps the
+// unbounded values into 0.0 to 1.0 with a tone mapping curve, and encodes the result
+// with the sRGB transfer curve, as the screen expects.
+
+use std::sync::OnceLock;
+
+use crate::blend::{BYTES_PER_PIXEL, CHANNELS};
+use crate::color;
+
+/// Number of entries in the table that encodes linear values with the sRGB curve. It is
+/// fine enough that every byte decoded with `Color::to_linear` encodes back to itself.
+const ENCODE_STEPS: usize = 4096;
+
+/// Fewest pixels worth handing to another thread.
+const MIN_CHUNK: usize = 16384;
+
+/// How values brighter than white are brought into the range the screen can show.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Tonemapper {
+    /// Values are clipped at white.
+    None,
+    /// `x / (1 + x)`, which compresses highlights smoothly but greys out the whites.
+    Reinhard,
+    /// Krzysztof Narkowicz's fit of the ACES filmic curve, which keeps more contrast and
+    /// saturation than `Reinhard`.
+    Aces,
+}
+
+impl Tonemapper {
+    /// Parses a tone mapping curve from its name in a scene file.
+    ///
+    /// # Arguments
+    /// - `name`: One of `none`, `reinhard` or `aces`.
+    ///
+    /// # Returns
+    /// The matching `Tonemapper`, or `None` if the name is unknown.
+    pub fn from_name(name: &str) -> Option<Tonemapper> {
+        match name {
+            "none" => Some(Tonemapper::None),
+            "reinhard" => Some(Tonemapper::Reinhard),
+            "aces" => Some(Tonemapper::Aces),
+            _ => None,
+        }
+    }
+
+    /// Returns the name of the curve, as written in a scene file.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Tonemapper::None => "none",
+            Tonemapper::Reinhard => "reinhard",
+            Tonemapper::Aces => "aces",
+        }
+    }
+
+    /// Returns the curve after this one, for cycling through them at runtime.
+    pub fn next(&self) -> Tonemapper {
+        match self {
+            Tonemapper::None => Tonemapper::Reinhard,
+            Tonemapper::Reinhard => Tonemapper::Aces,
+            Tonemapper::Aces => Tonemapper::None,
+        }
+    }
+
+    /// Maps a channel in linear light onto the curve.
+    ///
+    /// # Arguments
+    /// - `c`: The channel, 0.0 or more. Negative values map to black, and infinite ones
+    ///   to white.
+    ///
+    /// # Returns
+    /// The mapped channel, from 0.0 to 1.0.
+    pub fn map(&self, c: f32) -> f32 {
+        // The rational curves turn back up below 0, and would divide infinity by
+        // infinity far past where every curve is white.
+        let c = c.clamp(0.0, 1e18);
+        let mapped = match self {
+            Tonemapper::None => c,
+            Tonemapper::Reinhard => c / (1.0 + c),
+            Tonemapper::Aces => (c * (2.51 * c + 0.03)) / (c * (2.43 * c + 0.59) + 0.14),
+        };
+        mapped.clamp(0.0, 1.0)
+    }
+}
+
+/// Returns the table encoding linear values from 0.0 to 1.0, in `ENCODE_STEPS` even
+/// steps, into sRGB bytes.
+fn encode_table() -> &'static [u8] {
+    static TABLE: OnceLock<Vec<u8>> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        (0..ENCODE_STEPS)
+            .map(|step| {
+                let linear = step as f32 / (ENCODE_STEPS - 1) as f32;
+                (color::linear_to_srgb(linear) * 255.0).round() as u8
+            })
+            .collect()
+    })
+}
+
+/// Tone maps an HDR frame into the window's color buffer.
+///
+/// # Arguments
+/// - `hdr_buffer`: The frame, `CHANNELS` floats in linear light per pixel.
+/// - `exposure`: How much the frame is brightened before it is mapped, in stops; each
+///   stop doubles it.
+/// - `tonemapper`: The curve that brings the frame into the range of the screen.
+/// - `color_buffer`: The window's color buffer, which is overwritten with opaque pixels.
+/// - `threads`: The number of threads to split the pixels between.
+pub fn resolve(
+    hdr_buffer: &[f32],
+    exposure: f32,
+    tonemapper: Tonemapper,
+    color_buffer: &mut [u8],
+    threads: usize,
+) {
+    let scale = exposure.exp2();
+    let table = encode_table();
+    let encode = |c: f32| {
+        let mapped = tonemapper.map(c * scale);
+        // `mapped` is clamped, and a NaN casts to 0.
+        table[(mapped * (ENCODE_STEPS - 1) as f32 + 0.5) as usize]
+    };
+    let resolve_chunk = |source: &[f32], target: &mut [u8]| {
+        for (pixel, sample) in target
+            .chunks_exact_mut(BYTES_PER_PIXEL)
+            .zip(source.chunks_exact(CHANNELS))
+        {
+            pixel.copy_from_slice(&[encode(sample[0]), encode(sample[1]), encode(sample[2]), 255]);
+        }
+    };
+
+    let pixels = color_buffer.len() / BYTES_PER_PIXEL;
+    let chunk = pixels.div_ceil(threads.max(1)).max(MIN_CHUNK);
+    if chunk >= pixels {
+        resolve_chunk(hdr_buffer, color_buffer);
+        return;
+    }
+    std::thread::scope(|scope| {
+        for (source, target) in hdr_buffer
+            .chunks(chunk * CHANNELS)
+            .zip(color_buffer.chunks_mut(chunk * BYTES_PER_PIXEL))
+        {
+            scope.spawn(move || resolve_chunk(source, target));
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks that a curve maps values as expected, to within rounding.
+    fn assert_maps(tonemapper: Tonemapper, expected: &[(f32, f32)]) {
+        for &(c, mapped) in expected {
+            let actual = tonemapper.map(c);
+            assert!(
+                (actual - mapped).abs() < 1e-5,
+                "{:?} maps {} to {}, not {}",
+                tonemapper,
+                c,
+                actual,
+                mapped
+            );
+        }
+    }
+
+    #[test]
+    fn curves_map_black_white_and_highlights() {
+        assert_maps(
+            Tonemapper::None,
+            &[(0.0, 0.0), (0.5, 0.5), (1.0, 1.0), (1e6, 1.0)],
+        );
+        assert_maps(
+            Tonemapper::Reinhard,
+            &[(0.0, 0.0), (1.0, 0.5), (3.0, 0.75), (1e6, 1.0)],
+        );
+        // 1.0 maps to 2.54 / 3.16; very bright values approach 2.51 / 2.43 and clip.
+        assert_maps(
+            Tonemapper::Aces,
+            &[(0.0, 0.0), (1.0, 2.54 / 3.16), (1e6, 1.0)],
+        );
+        for tonemapper in [Tonemapper::None, Tonemapper::Reinhard, Tonemapper::Aces] {
+            assert_eq!(tonemapper.map(f32::INFINITY), 1.0, "{:?}", tonemapper);
+            assert_eq!(tonemapper.map(-1.0), 0.0, "{:?}", tonemapper);
+        }
+    }
+
+    #[test]
+    fn resolve_encodes_opaque_srgb_bytes() {
+        let hdr_buffer = [0.0, 0.5, 1.0, 0.25, 2.0, 1e6];
+        let mut color_buffer = [0; 2 * BYTES_PER_PIXEL];
+
+        resolve(&hdr_buffer, 0.0, Tonemapper::None, &mut color_buffer, 1);
+        assert_eq!(color_buffer, [0, 188, 255, 255, 137, 255, 255, 255]);
+
+        // One stop of exposure doubles the values before they are mapped.
+        resolve(&hdr_buffer, 1.0, Tonemapper::Reinhard, &mut color_buffer, 1);
+        assert_eq!(color_buffer, [0, 188, 213, 255, 156, 231, 255, 255]);
+    }
+}
@@ -0,0 +1,416 @@
+// This file contains the drawing of transparent triangles, whose colors are blended with
+// the surfaces behind them and so depend on the order they are drawn in.
+//
+// Opaque triangles are filled first and write their depth. Transparent triangles are
+// then tested against that depth without writing it, so they never hide one another,
+// and combined in one of three ways:
+//
+// - Sorted: the triangles are sorted back to front by their mean depth and blended in
+//   that order. It is cheap, but wrong where transparent triangles intersect or overlap
+//   in a cycle.
+// - Weighted blended order-independent transparency (McGuire and Bavoil, 2013): every
+//   sample sums its fragments weighted by alpha and depth, and the sums are composited
+//   over the frame once. It needs no sorting and a fixed amount of memory, but only
+//   approximates the result.
+// - A-buffer: every sample keeps a linked list of its fragments, which are sorted by
+//   depth and blended in order once all triangles are drawn. It is exact, but its memory
+//   grows with the number of fragments.
+
+use crate::blend::{BlendMode, CHANNELS};
+use crate::color::LinearColor;
+use crate::raster::{self, RasterTarget};
+use crate::simd::Kernel;
+use crate::texture::Texture;
+use crate::triangle::Triangle;
+
+/// How transparent triangles are combined with the frame.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Transparency {
+    /// Blended back to front after sorting the triangles by depth.
+    Sorted,
+    /// Approximated by weighted blended order-independent transparency.
+    WeightedBlended,
+    /// Blended back to front after sorting the fragments of every sample by depth.
+    ABuffer,
+}
+
+impl Transparency {
+    /// Parses a transparency mode from its name in a scene file.
+    ///
+    /// # Arguments
+    /// - `name`: One of `sorted`, `weighted` or `abuffer`.
+    ///
+    /// # Returns
+    /// The matching `Transparency`, or `None` if the name is unknown.
+    pub fn from_name(name: &str) -> Option<Transparency> {
+        match name {
+            "sorted" => Some(Transparency::Sorted),
+            "weighted" => Some(Transparency::WeightedBlended),
+            "abuffer" => Some(Transparency::ABuffer),
+            _ => None,
+        }
+    }
+
+    /// Returns the name of the mode, as written in a scene file.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Transparency::Sorted => "sorted",
+            Transparency::WeightedBlended => "weighted",
+            Transparency::ABuffer => "abuffer",
+        }
+    }
+
+    /// Returns the mode after this one, for cycling through them at runtime.
+    pub fn next(&self) -> Transparency {
+        match self {
+            Transparency::Sorted => Transparency::WeightedBlended,
+            Transparency::WeightedBlended => Transparency::ABuffer,
+            Transparency::ABuffer => Transparency::Sorted,
+        }
+    }
+}
+
+/// Marks the end of a list of fragments in the A-buffer.
+const NO_FRAGMENT: u32 = u32::MAX;
+
+/// A sample of a transparent triangle stored in the A-buffer.
+struct Fragment {
+    /// The next fragment of the same sample, or `NO_FRAGMENT`.
+    next: u32,
+    /// The inverse view depth of the fragment.
+    depth: f32,
+    /// The color of the fragment, in linear light.
+    color: LinearColor,
+    /// How the color is combined with the fragments behind it.
+    blend: BlendMode,
+}
+
+/// The buffers the order-independent modes gather fragments in, kept between frames to
+/// save allocating them every frame.
+pub struct TransparencyBuffers {
+    /// The weighted sum of the premultiplied colors and of the alphas of each sample.
+    accumulated: Vec<[f32; 4]>,
+    /// The product of `1 - alpha` of the fragments of each sample: how much of the
+    /// surface behind them still shows.
+    revealage: Vec<f32>,
+    /// The index of the last fragment stored for each sample, or `NO_FRAGMENT`.
+    heads: Vec<u32>,
+    /// The fragments of every sample, each linked to the one stored before it.
+    fragments: Vec<Fragment>,
+}
+
+impl TransparencyBuffers {
+    /// Creates empty buffers, sized when they are first used.
+    pub fn new() -> TransparencyBuffers {
+        TransparencyBuffers {
+            accumulated: Vec::new(),
+            revealage: Vec::new(),
+            heads: Vec::new(),
+            fragments: Vec::new(),
+        }
+    }
+}
+
+/// Fills a frame's opaque triangles, then blends its transparent ones over them.
+///
+/// A triangle is transparent when its blend mode lets the frame show through any of its
+/// colors; see `Triangle::is_transparent`.
+///
+/// # Arguments
+/// - `target`: The whole frame, cleared.
+/// - `triangles`: The triangles to draw, in the order of the scene.
+/// - `textures`: The textures, indexed by the triangles' `TextureId`.
+/// - `mode`: How transparent triangles are combined.
+/// - `buffers`: Scratch space for the order-independent modes.
+/// - `threads`: The number of threads to fill with. Fragments for the
+///   order-independent modes are gathered on the calling thread.
+/// - `kernel`: The implementation of the rasterizer's inner loop to use.
+///
+/// # Returns
+/// The number of transparent triangles.
+pub fn fill_scene(
+    mut target: RasterTarget,
+    triangles: &[Triangle],
+    textures: &[Texture],
+    mode: Transparency,
+    buffers: &mut TransparencyBuffers,
+    threads: usize,
+    kernel: Kernel,
+) -> usize {
+    let (opaque, mut transparent): (Vec<Triangle>, Vec<Triangle>) = triangles
+        .iter()
+        .partition(|triangle| !triangle.is_transparent());
+    raster::fill_triangles(target.reborrow(), &opaque, textures, threads, kernel);
+    if transparent.is_empty() {
+        return 0;
+    }
+
+    target.depth_write = false;
+    match mode {
+        Transparency::Sorted => {
+            sort_back_to_front(&mut transparent);
+            raster::fill_triangles(target, &transparent, textures, threads, kernel);
+        }
+        Transparency::WeightedBlended => {
+            // Only the "over" operators are approximated. Additive and multiplicative
+            // blending do not depend on the order, so they are drawn over the result.
+            let (over, mut commutative): (Vec<Triangle>, Vec<Triangle>) =
+                transparent.iter().partition(|triangle| {
+                    matches!(
+                        triangle.blend,
+                        BlendMode::AlphaOver | BlendMode::Premultiplied
+                    )
+                });
+            fill_weighted_blended(&mut target, &over, textures, buffers, kernel);
+            sort_back_to_front(&mut commutative);
+            raster::fill_triangles(target, &commutative, textures, threads, kernel);
+        }
+        Transparency::ABuffer => {
+            fill_a_buffer(&mut target, &transparent, textures, buffers, kernel)
+        }
+    }
+    transparent.len()
+}
+
+/// Sorts triangles from the farthest to the closest by the mean view depth of their
+/// vertices. Triangles at the same depth keep their order.
+fn sort_back_to_front(triangles: &mut [Triangle]) {
+    let depth = |triangle: &Triangle| triangle.inv_depths.iter().map(|d| 1.0 / d).sum::<f32>();
+    triangles.sort_by(|a, b| depth(b).total_cmp(&depth(a)));
+}
+
+/// Approximates blending triangles over the frame in depth order with weighted blended
+/// order-independent transparency.
+///
+/// Every fragment adds its premultiplied color and its alpha to its sample, weighted by
+/// a function that falls with distance, so closer fragments dominate. The average color
+/// is then laid over the frame by how much the fragments together cover it.
+fn fill_weighted_blended(
+    target: &mut RasterTarget,
+    triangles: &[Triangle],
+    textures: &[Texture],
+    buffers: &mut TransparencyBuffers,
+    kernel: Kernel,
+) {
+    let samples = target.depth_buffer.len();
+    buffers.accumulated.clear();
+    buffers.accumulated.resize(samples, [0.0; 4]);
+    buffers.revealage.clear();
+    buffers.revealage.resize(samples, 1.0);
+
+    let (accumulated, revealage) = (&mut buffers.accumulated, &mut buffers.revealage);
+    raster::fill_fragments(
+        target,
+        triangles,
+        textures,
+        kernel,
+        |index, color, depth, blend| {
+            let alpha = color.a;
+            // Equation 7 of the paper, for view depths of a few units to a few hundred.
+            let z = 1.0 / depth;
+            let falloff = 10.0 / (1e-5 + (z / 5.0).powi(2) + (z / 200.0).powi(6));
+            let weight = alpha * falloff.clamp(1e-2, 3e3);
+            let premultiply = match blend {
+                BlendMode::Premultiplied => 1.0,
+                _ => alpha,
+            };
+            let sum = &mut accumulated[index];
+            for (total, channel) in sum.iter_mut().zip([color.r, color.g, color.b]) {
+                *total += channel * premultiply * weight;
+            }
+            sum[3] += alpha * weight;
+            revealage[index] *= 1.0 - alpha;
+        },
+    );
+
+    for ((sample, sum), &revealage) in target
+        .color_buffer
+        .chunks_exact_mut(CHANNELS)
+        .zip(accumulated.iter())
+        .zip(revealage.iter())
+    {
+        if revealage >= 1.0 {
+            continue; // No fragment covers the sample.
+        }
+        let coverage = 1.0 - revealage;
+        for (value, total) in sample.iter_mut().zip(sum) {
+            let average = total / sum[3].max(1e-5);
+            *value = average * coverage + *value * revealage;
+        }
+    }
+}
+
+/// Blends triangles over the frame exactly, by gathering the fragments of every sample
+/// into a list and blending each list in depth order.
+fn fill_a_buffer(
+    target: &mut RasterTarget,
+    triangles: &[Triangle],
+    textures: &[Texture],
+    buffers: &mut TransparencyBuffers,
+    kernel: Kernel,
+) {
+    buffers.heads.clear();
+    buffers.heads.resize(target.depth_buffer.len(), NO_FRAGMENT);
+    buffers.fragments.clear();
+
+    let (heads, fragments) = (&mut buffers.heads, &mut buffers.fragments);
+    raster::fill_fragments(
+        target,
+        triangles,
+        textures,
+        kernel,
+        |index, color, depth, blend| {
+            fragments.push(Fragment {
+                next: heads[index],
+                depth,
+                color,
+                blend,
+            });
+            heads[index] = (fragments.len() - 1) as u32;
+        },
+    );
+
+    let mut list = Vec::new();
+    for (sample, &head) in target
+        .color_buffer
+        .chunks_exact_mut(CHANNELS)
+        .zip(heads.iter())
+    {
+        list.clear();
+        let mut next = head;
+        while next != NO_FRAGMENT {
+            list.push(next);
+            next = fragments[next as usize].next;
+        }
+        // Farthest first; fragments at the same depth in the order they were drawn.
+        list.sort_by(|&a, &b| {
+            let (first, second) = (&fragments[a as usize], &fragments[b as usize]);
+            first.depth.total_cmp(&second.depth).then(a.cmp(&b))
+        });
+        for &fragment in &list {
+            let fragment = &fragments[fragment as usize];
+            fragment.blend.apply_linear(sample, fragment.color, 1.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::raster::MSAA_SAMPLES;
+    use crate::vector::Vec2;
+
+    const WIDTH: u32 = 48;
+    const HEIGHT: u32 = 40;
+
+    /// A triangle at one view depth, with one color.
+    fn flat(points: [(f32, f32); 3], depth: f32, color: LinearColor, blend: BlendMode) -> Triangle {
+        Triangle::new(
+            points.map(|(x, y)| Vec2::new(x, y)),
+            [1.0 / depth; 3],
+            [color; 3],
+            blend,
+        )
+    }
+
+    /// An opaque background and overlapping transparent triangles at different depths,
+    /// listed closest first so they must be reordered.
+    fn scene() -> Vec<Triangle> {
+        vec![
+            flat(
+                [(1.0, 2.0), (30.0, 4.0), (12.0, 37.0)],
+                2.0,
+                LinearColor::new(0.9, 0.2, 0.1, 0.5),
+                BlendMode::AlphaOver,
+            ),
+            flat(
+                [(8.0, 1.0), (45.0, 20.0), (6.0, 30.0)],
+                3.0,
+                LinearColor::new(0.1, 0.8, 0.3, 0.4),
+                BlendMode::Multiply,
+            ),
+            flat(
+                [(20.0, 0.5), (47.0, 39.0), (3.0, 25.0)],
+                4.0,
+                LinearColor::new(0.1, 0.2, 0.45, 0.3),
+                BlendMode::Premultiplied,
+            ),
+            flat(
+                [(0.0, 0.0), (48.0, 0.0), (24.0, 40.0)],
+                5.0,
+                LinearColor::new(0.2, 0.1, 0.9, 0.6),
+                BlendMode::AlphaOver,
+            ),
+            flat(
+                [(-10.0, -10.0), (100.0, -10.0), (-10.0, 100.0)],
+                10.0,
+                LinearColor::new(0.5, 0.5, 0.5, 1.0),
+                BlendMode::Replace,
+            ),
+        ]
+    }
+
+    /// Draws triangles into a cleared frame.
+    ///
+    /// # Returns
+    /// The color buffer.
+    fn render(
+        triangles: &[Triangle],
+        mode: Transparency,
+        samples: u32,
+        threads: usize,
+    ) -> Vec<f32> {
+        let count = (WIDTH * HEIGHT * samples) as usize;
+        let mut color_buffer = vec![0.0; count * CHANNELS];
+        let mut depth_buffer = vec![0.0; count];
+        let target =
+            RasterTarget::new(&mut color_buffer, &mut depth_buffer, WIDTH, HEIGHT, samples);
+        let mut buffers = TransparencyBuffers::new();
+        let transparent = fill_scene(
+            target,
+            triangles,
+            &[],
+            mode,
+            &mut buffers,
+            threads,
+            Kernel::Scalar,
+        );
+        assert_eq!(transparent, 4);
+        color_buffer
+    }
+
+    #[test]
+    fn a_buffer_matches_sorted_triangles() {
+        let triangles = scene();
+        for samples in [1, MSAA_SAMPLES] {
+            for threads in [1, 3] {
+                let sorted = render(&triangles, Transparency::Sorted, samples, threads);
+                let a_buffer = render(&triangles, Transparency::ABuffer, samples, threads);
+                // The triangles do not intersect, so sorting whole triangles puts the
+                // fragments of every sample in depth order too.
+                assert_eq!(a_buffer, sorted, "{} samples, {} threads", samples, threads);
+            }
+        }
+    }
+
+    #[test]
+    fn a_buffer_sorts_fragments_listed_out_of_order() {
+        let mut triangles = scene();
+        let drawn = render(&triangles, Transparency::ABuffer, 1, 1);
+        triangles.reverse();
+        assert_eq!(render(&triangles, Transparency::ABuffer, 1, 1), drawn);
+        triangles.swap(1, 3);
+        assert_eq!(render(&triangles, Transparency::ABuffer, 1, 1), drawn);
+
+        // Blending in the order listed gives a different frame, so the order matters.
+        let triangles = scene();
+        let count = (WIDTH * HEIGHT) as usize;
+        let mut color_buffer = vec![0.0; count * CHANNELS];
+        let mut depth_buffer = vec![0.0; count];
+        let mut target = RasterTarget::new(&mut color_buffer, &mut depth_buffer, WIDTH, HEIGHT, 1);
+        raster::fill_triangles(target.reborrow(), &triangles[4..], &[], 1, Kernel::Scalar);
+        target.depth_write = false;
+        raster::fill_triangles(target, &triangles[..4], &[], 1, Kernel::Scalar);
+        assert_ne!(color_buffer, drawn);
+    }
+}
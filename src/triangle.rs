@@ -1,3 +1,6 @@
+use crate::blend::BlendMode;
+use crate::color::LinearColor;
+use crate::texture::TextureId;
 use crate::vector::Vec2;
 
 /// Represents a triangle in 2D space using three points (vertices).
@@ -5,6 +8,17 @@ use crate::vector::Vec2;
 pub struct Triangle {
     /// The three points (vertices) of the triangle.
     pub(crate) points: [Vec2; 3],
+    /// The inverse view depth (`1 / z`) of each vertex, used for depth testing.
+    pub(crate) inv_depths: [f32; 3],
+    /// The shaded fill color at each vertex in linear light, blended across the triangle.
+    pub(crate) colors: [LinearColor; 3],
+    /// How the triangle's colors are combined with the frame.
+    pub(crate) blend: BlendMode,
+    /// The texture multiplying the colors, if any.
+    pub(crate) texture: Option<TextureId>,
+    /// The texture coordinates of each vertex, blended across the triangle like the
+    /// colors. Unused without a texture.
+    pub(crate) uvs: [Vec2; 3],
 }
 
 /// Represents a face of a 3D object using indices that point to vertices in a shared vertex array.
@@ -12,7 +26,7 @@ pub struct Triangle {
 /// # Note
 /// This is commonly used in 3D graphics to define which vertices in a vertex array
 /// form a triangular face.
-#[derive(Debug, Copy, Clone)] // Enables debugging, copying, and cloning of Face instances.
+#[derive(Debug, Copy, Clone, PartialEq)] // Enables debugging, copying, cloning and comparing of Face instances.
 pub struct Face {
     /// Index of the first vertex in the vertex array.
     pub(crate) a: usize,
@@ -28,11 +42,50 @@ impl Triangle {
     ///
     /// # Arguments
     /// - `points`: An array of three `Vec2` points representing the vertices of the triangle.
+    /// - `inv_depths`: The inverse view depth (`1 / z`) of each vertex.
+    /// - `colors`: The fill color at each vertex, in linear light.
+    /// - `blend`: How the colors are combined with the frame.
     ///
     /// # Returns
-    /// A new `Triangle` with the given vertices.
-    pub fn new(points: [Vec2; 3]) -> Triangle {
-        Triangle { points }
+    /// A new untextured `Triangle` with the given vertices.
+    pub fn new(
+        points: [Vec2; 3],
+        inv_depths: [f32; 3],
+        colors: [LinearColor; 3],
+        blend: BlendMode,
+    ) -> Triangle {
+        Triangle {
+            points,
+            inv_depths,
+            colors,
+            blend,
+            texture: None,
+            uvs: [Vec2::new(0.0, 0.0); 3],
+        }
+    }
+
+    /// Textures the triangle.
+    ///
+    /// # Arguments
+    /// - `texture`: The texture multiplying the colors.
+    /// - `uvs`: The texture coordinates of each vertex.
+    ///
+    /// # Returns
+    /// The triangle with the texture applied.
+    pub fn with_texture(self, texture: TextureId, uvs: [Vec2; 3]) -> Triangle {
+        Triangle {
+            texture: Some(texture),
+            uvs,
+            ..self
+        }
+    }
+
+    /// Checks whether the frame shows through the triangle anywhere, so it must be drawn
+    /// after the opaque triangles and without writing depth.
+    pub fn is_transparent(&self) -> bool {
+        self.colors
+            .iter()
+            .any(|&color| !self.blend.replaces(color.a))
     }
 }
 
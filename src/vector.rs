@@ -1,7 +1,7 @@
 use std::ops::{Add, Div, Mul, Neg, Sub};
 
 /// A 2D vector struct, representing a point or direction in 2D space.
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub struct Vec2 {
     /// The x-coordinate of the vector.
     pub(crate) x: f32,
@@ -171,7 +171,7 @@ impl Neg for Vec2 {
 }
 
 /// A 3D vector struct, representing a point or direction in 3D space.
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub struct Vec3 {
     /// The x-coordinate of the vector.
     pub x: f32,
@@ -278,6 +278,18 @@ impl Vec3 {
             z: self.x * other.y - self.y * other.x,
         }
     }
+
+    /// Linearly interpolates between two vectors.
+    ///
+    /// # Arguments
+    /// - `other`: The vector to interpolate towards.
+    /// - `t`: The interpolation factor, where `0.0` gives `self` and `1.0` gives `other`.
+    ///
+    /// # Returns
+    /// A new interpolated `Vec3`.
+    pub fn lerp(&self, other: Vec3, t: f32) -> Vec3 {
+        *self + (other - *self) * t
+    }
 }
 
 /// Implements the subtraction operator for `Vec3`.
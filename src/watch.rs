@@ -0,0 +1,79 @@
+// This file contains the file watcher used to reload assets while the viewer runs.
+//
+// Files are watched by polling their modification times, which works the same on every
+// platform without a notification library.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Minimum time between two checks of the watched files.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Reads when a file was last modified.
+///
+/// # Returns
+/// The modification time, or `None` if the file is missing or cannot be read.
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// Notices when any file of a set is modified, created or deleted.
+pub struct FileWatcher {
+    /// The watched files and their modification times when last checked.
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+    /// When the files were last checked.
+    last_poll: Instant,
+}
+
+impl FileWatcher {
+    /// Creates a new `FileWatcher`.
+    ///
+    /// # Arguments
+    /// - `paths`: The files to watch.
+    ///
+    /// # Returns
+    /// A new `FileWatcher` that considers the files unchanged as of now.
+    pub fn new(paths: Vec<PathBuf>) -> FileWatcher {
+        FileWatcher {
+            files: paths
+                .into_iter()
+                .map(|path| {
+                    let time = modified(&path);
+                    (path, time)
+                })
+                .collect(),
+            last_poll: Instant::now(),
+        }
+    }
+
+    /// Returns the watched files.
+    pub fn paths(&self) -> impl Iterator<Item = &PathBuf> {
+        self.files.iter().map(|(path, _)| path)
+    }
+
+    /// Checks whether any watched file changed since the last check.
+    ///
+    /// The files are only checked once per `POLL_INTERVAL`; calls in between report
+    /// no change.
+    ///
+    /// # Returns
+    /// `true` if at least one file was modified, created or deleted.
+    pub fn poll(&mut self) -> bool {
+        if self.last_poll.elapsed() < POLL_INTERVAL {
+            return false;
+        }
+        self.last_poll = Instant::now();
+
+        let mut changed = false;
+        for (path, time) in &mut self.files {
+            let current = modified(path);
+            if current != *time {
+                *time = current;
+                changed = true;
+            }
+        }
+        changed
+    }
+}